@@ -0,0 +1,38 @@
+//! cpp/occt.cpp（chijin が公開していない OCCT の機能）を cxx でビルドする。
+//!
+//! OCCT は chijin の prebuilt と同じ OCCT_ROOT のものを使う。OCCT のライブラリのリンクは chijin に任せ、
+//! chijin がリンクしないものだけをここで足す。
+
+use std::path::PathBuf;
+
+fn main() {
+	let root = PathBuf::from(
+		std::env::var("OCCT_ROOT")
+			.or_else(|_| std::env::var("CASROOT"))
+			.expect("OCCT_ROOT または CASROOT を設定してください"),
+	);
+	let include = [
+		root.join("include").join("opencascade"),
+		root.join("inc"),
+		root.join("include"),
+	]
+	.into_iter()
+	.find(|dir| dir.exists())
+	.unwrap_or_else(|| root.join("include"));
+
+	cxx_build::bridge("src/occt.rs")
+		.file("cpp/occt.cpp")
+		.include(include)
+		.std("c++17")
+		.define("_USE_MATH_DEFINES", None)
+		.compile("api_occt");
+
+	// IGES の読み書き (IGESCAFControl) は chijin がリンクしない。ライブラリの場所は chijin が指定する
+	println!("cargo:rustc-link-lib=static=TKDEIGES");
+
+	println!("cargo:rerun-if-changed=src/occt.rs");
+	println!("cargo:rerun-if-changed=cpp/occt.h");
+	println!("cargo:rerun-if-changed=cpp/occt.cpp");
+	println!("cargo:rerun-if-env-changed=OCCT_ROOT");
+	println!("cargo:rerun-if-env-changed=CASROOT");
+}
@@ -0,0 +1,412 @@
+// chijin が公開していない OCCT の機能。形状は BinTools のバイナリ BRep で受け渡す（src/occt.rs）。
+// OCCT の例外 (Standard_Failure) は std::runtime_error にして cxx の Result で Rust に返す。
+
+#include "api/cpp/occt.h"
+#include "api/src/occt.rs.h"
+
+#include <BRepBuilderAPI_Sewing.hxx>
+#include <BRep_Builder.hxx>
+#include <BRep_Tool.hxx>
+#include <BinTools.hxx>
+#include <IFSelect_ReturnStatus.hxx>
+#include <IGESCAFControl_Reader.hxx>
+#include <IGESCAFControl_Writer.hxx>
+#include <IGESControl_Controller.hxx>
+#include <Interface_Static.hxx>
+#include <Quantity_Color.hxx>
+#include <STEPCAFControl_Controller.hxx>
+#include <STEPCAFControl_Writer.hxx>
+#include <STEPControl_Writer.hxx>
+#include <ShapeBuild_ReShape.hxx>
+#include <ShapeFix_Shape.hxx>
+#include <ShapeFix_Solid.hxx>
+#include <Standard_Failure.hxx>
+#include <TDF_ChildIterator.hxx>
+#include <TDF_Label.hxx>
+#include <TDF_LabelSequence.hxx>
+#include <TDocStd_Document.hxx>
+#include <TopExp_Explorer.hxx>
+#include <TopoDS.hxx>
+#include <TopoDS_Compound.hxx>
+#include <TopoDS_Iterator.hxx>
+#include <TopoDS_Shape.hxx>
+#include <TopoDS_Shell.hxx>
+#include <XCAFDoc_ColorTool.hxx>
+#include <XCAFDoc_DocumentTool.hxx>
+#include <XCAFDoc_ShapeTool.hxx>
+
+#include <mutex>
+#include <sstream>
+#include <stdexcept>
+#include <string>
+#include <unordered_map>
+#include <utility>
+#include <vector>
+
+namespace {
+
+TopoDS_Shape read_brep(rust::Slice<const uint8_t> brep) {
+    std::istringstream stream(
+        std::string(reinterpret_cast<const char*>(brep.data()), brep.size()),
+        std::ios::binary);
+    TopoDS_Shape shape;
+    BinTools::Read(shape, stream);
+    if (shape.IsNull()) {
+        throw std::runtime_error("BRep を読めません");
+    }
+    return shape;
+}
+
+rust::Vec<uint8_t> to_vec(const std::string& bytes) {
+    rust::Vec<uint8_t> out;
+    out.reserve(bytes.size());
+    for (const char c : bytes) {
+        out.push_back(static_cast<uint8_t>(c));
+    }
+    return out;
+}
+
+rust::Vec<uint8_t> write_brep(const TopoDS_Shape& shape) {
+    std::ostringstream stream(std::ios::binary);
+    BinTools::Write(shape, stream);
+    return to_vec(stream.str());
+}
+
+template <typename F>
+auto occt(F f) -> decltype(f()) {
+    try {
+        return f();
+    } catch (const Standard_Failure& e) {
+        throw std::runtime_error(e.GetMessageString());
+    }
+}
+
+// コンパウンドはグループ、ソリッド・シェル・面は葉のノードにする。
+// 葉の面は TopExp_Explorer と同じ深さ優先の順に face_solids に並ぶ。面のない葉は作らない
+void build(const TopoDS_Shape& shape, int32_t parent, ShapeParts& parts, int32_t& solids) {
+    switch (shape.ShapeType()) {
+    case TopAbs_COMPOUND:
+    case TopAbs_COMPSOLID: {
+        const int32_t index = static_cast<int32_t>(parts.parents.size());
+        parts.parents.push_back(parent);
+        parts.solids.push_back(-1);
+        for (TopoDS_Iterator it(shape); it.More(); it.Next()) {
+            build(it.Value(), index, parts, solids);
+        }
+        break;
+    }
+    case TopAbs_SOLID:
+    case TopAbs_SHELL:
+    case TopAbs_FACE: {
+        const size_t before = parts.face_solids.size();
+        for (TopExp_Explorer ex(shape, TopAbs_FACE); ex.More(); ex.Next()) {
+            parts.face_solids.push_back(static_cast<uint32_t>(solids));
+        }
+        if (parts.face_solids.size() == before) {
+            break;
+        }
+        parts.parents.push_back(parent);
+        parts.solids.push_back(solids++);
+        break;
+    }
+    default:
+        break;
+    }
+}
+
+// 面の TShape ごとの色
+using Colors = std::unordered_map<const void*, Quantity_Color>;
+
+// ColoredBrep の色（面の番号ごと）を shape の面の TShape ごとの色にする
+Colors face_colors(const TopoDS_Shape& shape, const ColoredBrep& input) {
+    std::vector<const void*> faces;
+    for (TopExp_Explorer ex(shape, TopAbs_FACE); ex.More(); ex.Next()) {
+        faces.push_back(ex.Current().TShape().get());
+    }
+    Colors colors;
+    for (size_t i = 0; i < input.faces.size() && 3 * i + 2 < input.colors.size(); i++) {
+        if (input.faces[i] < faces.size()) {
+            colors[faces[input.faces[i]]] = Quantity_Color(
+                input.colors[3 * i], input.colors[3 * i + 1], input.colors[3 * i + 2], Quantity_TOC_sRGB);
+        }
+    }
+    return colors;
+}
+
+// shape を面の色と一緒に ColoredBrep にする
+ColoredBrep colored(const TopoDS_Shape& shape, const Colors& colors) {
+    ColoredBrep out;
+    uint32_t index = 0;
+    for (TopExp_Explorer ex(shape, TopAbs_FACE); ex.More(); ex.Next(), index++) {
+        const auto found = colors.find(ex.Current().TShape().get());
+        if (found == colors.end()) {
+            continue;
+        }
+        Standard_Real r, g, b;
+        found->second.Values(r, g, b, Quantity_TOC_sRGB);
+        out.faces.push_back(index);
+        out.colors.push_back(static_cast<float>(r));
+        out.colors.push_back(static_cast<float>(g));
+        out.colors.push_back(static_cast<float>(b));
+    }
+    out.brep = write_brep(shape);
+    return out;
+}
+
+// 色付きの形状を XCAF のドキュメントにする。色は面ごとに付ける
+Handle(TDocStd_Document) color_document(const TopoDS_Shape& shape, const Colors& colors) {
+    Handle(TDocStd_Document) doc = new TDocStd_Document("XmlXCAF");
+    Handle(XCAFDoc_ShapeTool) shape_tool = XCAFDoc_DocumentTool::ShapeTool(doc->Main());
+    Handle(XCAFDoc_ColorTool) color_tool = XCAFDoc_DocumentTool::ColorTool(doc->Main());
+    const TDF_Label root = shape_tool->AddShape(shape, Standard_False);
+
+    for (TopExp_Explorer ex(shape, TopAbs_FACE); ex.More(); ex.Next()) {
+        const auto found = colors.find(ex.Current().TShape().get());
+        if (found == colors.end()) {
+            continue;
+        }
+        TDF_Label label;
+        if (!shape_tool->FindSubShape(root, ex.Current(), label)) {
+            label = shape_tool->AddSubShape(root, ex.Current());
+        }
+        if (!label.IsNull()) {
+            color_tool->SetColor(label, found->second, XCAFDoc_ColorSurf);
+        }
+    }
+    return doc;
+}
+
+// XCAF のドキュメントの形状を 1 つのコンパウンドにし、面の色と一緒に ColoredBrep にする。
+// 面のラベルに色がなければ、その面を含む形状のラベルのうち一番内側のものの色を使う
+ColoredBrep colored_brep(const Handle(TDocStd_Document)& doc) {
+    Handle(XCAFDoc_ShapeTool) shape_tool = XCAFDoc_DocumentTool::ShapeTool(doc->Main());
+    Handle(XCAFDoc_ColorTool) color_tool = XCAFDoc_DocumentTool::ColorTool(doc->Main());
+
+    TDF_LabelSequence roots;
+    shape_tool->GetFreeShapes(roots);
+    BRep_Builder builder;
+    TopoDS_Compound compound;
+    builder.MakeCompound(compound);
+    for (int i = 1; i <= roots.Length(); i++) {
+        builder.Add(compound, XCAFDoc_ShapeTool::GetShape(roots.Value(i)));
+    }
+
+    Colors own_colors;
+    Colors colors;
+    // 深さ優先なので、内側のラベルの色が後から上書きする
+    for (TDF_ChildIterator it(doc->Main(), Standard_True); it.More(); it.Next()) {
+        const TDF_Label& label = it.Value();
+        if (!XCAFDoc_ShapeTool::IsShape(label)) {
+            continue;
+        }
+        const TopoDS_Shape shape = XCAFDoc_ShapeTool::GetShape(label);
+        Quantity_Color color;
+        if (shape.IsNull() || !(color_tool->GetColor(label, XCAFDoc_ColorSurf, color) ||
+                                color_tool->GetColor(label, XCAFDoc_ColorGen, color))) {
+            continue;
+        }
+        if (shape.ShapeType() == TopAbs_FACE) {
+            own_colors[shape.TShape().get()] = color;
+            continue;
+        }
+        for (TopExp_Explorer ex(shape, TopAbs_FACE); ex.More(); ex.Next()) {
+            colors[ex.Current().TShape().get()] = color;
+        }
+    }
+    for (const auto& [face, color] : own_colors) {
+        colors[face] = color;
+    }
+    return colored(compound, colors);
+}
+
+// 直す前の面 from の色を、直した後の形状 to の面にも付ける
+void carry_colors(const TopoDS_Shape& from, const TopoDS_Shape& to, Colors& colors) {
+    const auto found = colors.find(from.TShape().get());
+    if (found == colors.end() || to.IsNull()) {
+        return;
+    }
+    const Quantity_Color color = found->second;
+    for (TopExp_Explorer ex(to, TopAbs_FACE); ex.More(); ex.Next()) {
+        colors.emplace(ex.Current().TShape().get(), color);
+    }
+}
+
+// 葉（ソリッド・シェル・面）の面を縫い合わせ、閉じたシェルはソリッドにしてから ShapeFix で直す。
+// 面の色は縫合と ShapeFix の置き換えをたどって付け替える
+TopoDS_Shape heal_leaf(const TopoDS_Shape& leaf, double tolerance, Colors& colors) {
+    BRepBuilderAPI_Sewing sewing(tolerance);
+    sewing.Add(leaf);
+    sewing.Perform();
+    const TopoDS_Shape sewed = sewing.SewedShape();
+    if (sewed.IsNull()) {
+        return leaf;
+    }
+    for (TopExp_Explorer ex(leaf, TopAbs_FACE); ex.More(); ex.Next()) {
+        if (sewing.IsModified(ex.Current())) {
+            carry_colors(ex.Current(), sewing.Modified(ex.Current()), colors);
+        }
+    }
+
+    BRep_Builder builder;
+    TopoDS_Compound solids;
+    builder.MakeCompound(solids);
+    int count = 0;
+    for (TopExp_Explorer ex(sewed, TopAbs_SHELL); ex.More(); ex.Next(), count++) {
+        const TopoDS_Shell& shell = TopoDS::Shell(ex.Current());
+        if (BRep_Tool::IsClosed(shell)) {
+            builder.Add(solids, ShapeFix_Solid().SolidFromShell(shell));
+        } else {
+            builder.Add(solids, shell);
+        }
+    }
+    for (TopExp_Explorer ex(sewed, TopAbs_FACE, TopAbs_SHELL); ex.More(); ex.Next(), count++) {
+        builder.Add(solids, ex.Current());
+    }
+    TopoDS_Shape shape = solids;
+    if (count == 1) {
+        shape = TopoDS_Iterator(solids).Value();
+    }
+
+    ShapeFix_Shape fixer(shape);
+    fixer.SetMaxTolerance(tolerance);
+    fixer.Perform();
+    for (TopExp_Explorer ex(shape, TopAbs_FACE); ex.More(); ex.Next()) {
+        carry_colors(ex.Current(), fixer.Context()->Value(ex.Current()), colors);
+    }
+    return fixer.Shape();
+}
+
+// コンパウンドの木はそのままにして、葉ごとに直す
+TopoDS_Shape heal_tree(const TopoDS_Shape& shape, double tolerance, Colors& colors) {
+    switch (shape.ShapeType()) {
+    case TopAbs_COMPOUND:
+    case TopAbs_COMPSOLID: {
+        BRep_Builder builder;
+        TopoDS_Compound compound;
+        builder.MakeCompound(compound);
+        for (TopoDS_Iterator it(shape); it.More(); it.Next()) {
+            builder.Add(compound, heal_tree(it.Value(), tolerance, colors));
+        }
+        return compound;
+    }
+    case TopAbs_SOLID:
+    case TopAbs_SHELL:
+    case TopAbs_FACE:
+        return heal_leaf(shape, tolerance, colors);
+    default:
+        return shape;
+    }
+}
+
+// Interface_Static はプロセス全体で共有されるので、書き出しの間だけ値を変えて戻す
+std::mutex static_mutex;
+
+class StaticValues {
+public:
+    StaticValues() : lock_(static_mutex) {}
+    StaticValues(const StaticValues&) = delete;
+    StaticValues& operator=(const StaticValues&) = delete;
+
+    ~StaticValues() {
+        for (auto it = saved_.rbegin(); it != saved_.rend(); ++it) {
+            Interface_Static::SetCVal(it->first.c_str(), it->second.c_str());
+        }
+    }
+
+    void set(const char* name, const std::string& value) {
+        const Standard_CString old = Interface_Static::CVal(name);
+        saved_.emplace_back(name, old ? old : "");
+        if (!Interface_Static::SetCVal(name, value.c_str())) {
+            throw std::runtime_error(std::string(name) + " に " + value + " を設定できません");
+        }
+    }
+
+private:
+    std::lock_guard<std::mutex> lock_;
+    std::vector<std::pair<std::string, std::string>> saved_;
+};
+
+}  // namespace
+
+ShapeParts shape_parts(rust::Slice<const uint8_t> brep) {
+    return occt([&] {
+        const TopoDS_Shape shape = read_brep(brep);
+        ShapeParts parts;
+        int32_t solids = 0;
+        build(shape, -1, parts, solids);
+        return parts;
+    });
+}
+
+rust::Vec<uint8_t> write_step(const ColoredBrep& input, rust::Str schema, rust::Str unit) {
+    return occt([&] {
+        const TopoDS_Shape shape = read_brep(rust::Slice<const uint8_t>(input.brep.data(), input.brep.size()));
+        Handle(TDocStd_Document) doc = color_document(shape, face_colors(shape, input));
+
+        STEPCAFControl_Controller::Init();
+        StaticValues statics;
+        statics.set("write.step.schema", std::string(schema));
+        statics.set("write.step.unit", std::string(unit));
+
+        STEPCAFControl_Writer writer;
+        writer.SetColorMode(Standard_True);
+        if (!writer.Transfer(doc)) {
+            throw std::runtime_error("STEP への変換に失敗しました");
+        }
+        std::ostringstream stream(std::ios::binary);
+        if (writer.ChangeWriter().WriteStream(stream) != IFSelect_RetDone) {
+            throw std::runtime_error("STEP を書けません");
+        }
+        return to_vec(stream.str());
+    });
+}
+
+ColoredBrep read_iges(rust::Str path) {
+    return occt([&] {
+        IGESControl_Controller::Init();
+        IGESCAFControl_Reader reader;
+        reader.SetColorMode(Standard_True);
+        reader.SetNameMode(Standard_True);
+        if (reader.ReadFile(std::string(path).c_str()) != IFSelect_RetDone) {
+            throw std::runtime_error("IGES を読めません");
+        }
+        Handle(TDocStd_Document) doc = new TDocStd_Document("XmlXCAF");
+        if (!reader.Transfer(doc)) {
+            throw std::runtime_error("IGES の形状への変換に失敗しました");
+        }
+        return colored_brep(doc);
+    });
+}
+
+ColoredBrep heal(const ColoredBrep& input, double tolerance) {
+    return occt([&] {
+        const TopoDS_Shape shape = read_brep(rust::Slice<const uint8_t>(input.brep.data(), input.brep.size()));
+        Colors colors = face_colors(shape, input);
+        const TopoDS_Shape healed = heal_tree(shape, tolerance, colors);
+        return colored(healed, colors);
+    });
+}
+
+rust::Vec<uint8_t> write_iges(const ColoredBrep& input) {
+    return occt([&] {
+        const TopoDS_Shape shape = read_brep(rust::Slice<const uint8_t>(input.brep.data(), input.brep.size()));
+        Handle(TDocStd_Document) doc = color_document(shape, face_colors(shape, input));
+
+        IGESControl_Controller::Init();
+        StaticValues statics;
+        // 面はトリム曲面 (Type 144) として書く。BRep (Type 186) より読める CAD が多い
+        statics.set("write.iges.brep.mode", "Faces");
+        statics.set("write.iges.unit", "MM");
+
+        IGESCAFControl_Writer writer;
+        writer.SetColorMode(Standard_True);
+        if (!writer.Transfer(doc)) {
+            throw std::runtime_error("IGES への変換に失敗しました");
+        }
+        std::ostringstream stream(std::ios::binary);
+        if (!writer.Write(stream)) {
+            throw std::runtime_error("IGES を書けません");
+        }
+        return to_vec(stream.str());
+    });
+}
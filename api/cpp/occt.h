@@ -0,0 +1,15 @@
+#pragma once
+
+#include "rust/cxx.h"
+
+#include <cstdint>
+
+// src/occt.rs の共有構造体（定義は cxx が生成する occt.rs.h）
+struct ShapeParts;
+struct ColoredBrep;
+
+ShapeParts shape_parts(rust::Slice<const uint8_t> brep);
+ColoredBrep read_iges(rust::Str path);
+ColoredBrep heal(const ColoredBrep& input, double tolerance);
+rust::Vec<uint8_t> write_step(const ColoredBrep& input, rust::Str schema, rust::Str unit);
+rust::Vec<uint8_t> write_iges(const ColoredBrep& input);
@@ -1,34 +1,136 @@
+mod drawing;
+mod dxf;
+mod material;
+mod step;
+mod thumbnail;
+mod topology;
+mod usdz;
+
+use crate::openapi::{Annotation, StepAssemblyNode};
 use chijin::Shape;
 use gltf_json as json;
 use std::io::Write;
+use topology::PartNode;
+
+pub use drawing::drawing_svg;
+pub use dxf::dxf;
+pub use material::{MaterialPreset, material_names, material_preset};
+pub use step::{StepOptions, StepSchema, StepUnit};
+pub(crate) use step::{
+	parse_step_string, split_entity, split_params, split_statements, step_real, step_string,
+};
+pub use thumbnail::{
+	THUMBNAIL_SIZE, THUMBNAIL_VIEW, mesh_thumbnail_png, thumbnail_png, thumbnail_view,
+};
+pub use usdz::usdz;
 
 /// ShapeをSTEPバイト列に変換する。
-pub fn shape_to_step(shape: &Shape) -> Result<Vec<u8>, String> {
+/// options でスキーマ (AP203/AP214/AP242)・長さ単位 (mm/inch)・製品名と品番を指定できる。
+pub fn shape_to_step(shape: &Shape, options: &StepOptions) -> Result<Vec<u8>, String> {
+	let buf = crate::occt::write_step(shape, options.schema.occt_name(), options.unit.occt_name())?;
+	step::apply_options(&buf, options)
+}
+
+/// ShapeをIGESバイト列に変換する。
+/// 面は三角形分割せず、元の曲面をトリム曲面 (Type 144) として書く。面の色は色定義で保持する。単位は mm。
+pub fn shape_to_iges(shape: &Shape) -> Result<Vec<u8>, String> {
+	crate::occt::write_iges(shape)
+}
+
+/// Shapeを色付きBRep (CHJC形式) のバイト列に変換する。
+pub fn shape_to_brep_color(shape: &Shape) -> Result<Vec<u8>, String> {
+	let mut buf = Vec::new();
+	shape
+		.write_brep_color(&mut buf)
+		.map_err(|e| format!("BRep書き込み失敗: {:?}", e))?;
+	Ok(buf)
+}
+
+/// Shapeを OCCT 標準のテキスト BRep に変換する。色情報は含まれない。
+pub fn shape_to_brep_text(shape: &Shape) -> Result<Vec<u8>, String> {
 	let mut buf = Vec::new();
 	shape
-		.write_step_with_colors(&mut buf)
-		.map_err(|e| format!("STEP書き込み失敗: {:?}", e))?;
+		.write_brep_text(&mut buf)
+		.map_err(|e| format!("BRep書き込み失敗: {:?}", e))?;
+	Ok(buf)
+}
+
+/// Shapeを既定の GLB（material・info なし）のバイト列に変換する。mesh は shape_mesh でメッシュ化済みのもの、
+/// products は STEP の製品構造（ノード名に使う）。
+/// レスポンスへ流す場合は GlbPlan を使い、これはキャッシュに保存するときに使う。
+pub fn shape_to_glb(
+	shape: &Shape,
+	mesh: chijin::Mesh,
+	products: &[StepAssemblyNode],
+) -> Result<Vec<u8>, String> {
+	let plan = GlbPlan::from_mesh(shape, mesh, None, None, products)?;
+	let mut buf = Vec::with_capacity(plan.byte_len());
+	plan.write_to(&mut buf)
+		.map_err(|e| format!("GLB書き込み失敗: {:?}", e))?;
 	Ok(buf)
 }
 
-/// GLB (GLTF Binary) を生成する。
-/// shape.colormap が空の場合はグレー単色プリミティブ、
-/// 色情報がある場合は色グループ別プリミティブを生成する。
-pub fn gltf_binary(shape: &Shape) -> Result<Vec<u8>, String> {
-	let mesh = shape
-		.mesh_with_tolerance(0.1)
-		.map_err(|e| format!("mesh_with_tolerance failed: {:?}", e))?;
+/// メッシュ化の線形許容差 (mm)
+const MESH_TOLERANCE: f64 = 0.1;
 
-	// Rgb は Hash 未実装なので bits() でキー化
-	fn rgb_key(rgb: chijin::Rgb) -> (u32, u32, u32) {
-		(rgb.r.to_bits(), rgb.g.to_bits(), rgb.b.to_bits())
-	}
+/// 色なし面の既定色
+const DEFAULT_RGB: chijin::Rgb = chijin::Rgb {
+	r: 0.8,
+	g: 0.8,
+	b: 0.8,
+};
+
+/// Shape を三角形メッシュに変換する。全メッシュ形式で共通の許容差を使う。
+pub(crate) fn shape_mesh(shape: &Shape) -> Result<chijin::Mesh, String> {
+	shape
+		.mesh_with_tolerance(MESH_TOLERANCE)
+		.map_err(|e| format!("mesh_with_tolerance failed: {:?}", e))
+}
 
-	// 三角形を色グループ別インデックスリストに振り分ける
+/// Shape のエッジをメッシュと同じ許容差で折れ線にする
+pub(crate) fn shape_edges(shape: &Shape) -> Vec<Vec<glam::DVec3>> {
+	shape
+		.edges()
+		.map(|edge| edge.approximation_segments(MESH_TOLERANCE).collect())
+		.collect()
+}
+
+/// メッシュ頂点の軸平行バウンディングボックス (min, max)。頂点がなければ None。
+pub(crate) fn bounding_box(mesh: &chijin::Mesh) -> Option<(glam::DVec3, glam::DVec3)> {
+	let first = *mesh.vertices.first()?;
+	Some(
+		mesh.vertices
+			.iter()
+			.fold((first, first), |(lo, hi), &v| (lo.min(v), hi.max(v))),
+	)
+}
+
+/// 色グループ別インデックスリスト。色なしの面は `None` にまとめる。
+type ColorGroups = Vec<(Option<chijin::Rgb>, Vec<usize>)>;
+
+// Rgb は Hash 未実装なので bits() でキー化
+fn rgb_key(rgb: chijin::Rgb) -> (u32, u32, u32) {
+	(rgb.r.to_bits(), rgb.g.to_bits(), rgb.b.to_bits())
+}
+
+/// GLB に埋め込む ShapeOutput 由来の部品情報
+pub struct GlbInfo<'a> {
+	pub label: Option<&'a str>,
+	pub annotations: &'a [Box<Annotation>],
+	/// ShapeNode の正規化 JSON の SHA-256（キャッシュキーと同じ）
+	pub shape_hash: String,
+}
+
+/// 三角形を色グループ別インデックスリストに振り分ける。
+/// グループが1つかつ色なし → グレー単色（従来と同等）
+/// グループが1つかつ色あり → その色で単色
+/// グループが複数 → 色別プリミティブ
+fn color_groups(shape: &Shape, mesh: &chijin::Mesh, triangles: &[usize]) -> ColorGroups {
 	// face_ids.len() == indices.len() / 3
 	let mut groups: std::collections::HashMap<(u32, u32, u32), (Option<chijin::Rgb>, Vec<usize>)> =
 		std::collections::HashMap::new();
-	for (tri_idx, &face_id) in mesh.face_ids.iter().enumerate() {
+	for &tri_idx in triangles {
+		let face_id = mesh.face_ids[tri_idx];
 		let rgb = shape.colormap.get(&chijin::TShapeId(face_id)).copied();
 		let key = rgb.map(rgb_key).unwrap_or((0, 0, 0));
 		let entry = groups.entry(key).or_insert((rgb, Vec::new()));
@@ -36,278 +138,993 @@ pub fn gltf_binary(shape: &Shape) -> Result<Vec<u8>, String> {
 			.1
 			.extend_from_slice(&mesh.indices[tri_idx * 3..tri_idx * 3 + 3]);
 	}
+	groups.into_values().collect()
+}
 
-	// グループが1つかつ色なし → グレー単色（従来と同等）
-	// グループが1つかつ色あり → その色で単色
-	// グループが複数 → 色別プリミティブ
-	let groups: Vec<(Option<chijin::Rgb>, Vec<usize>)> = groups.into_values().collect();
-
-	build_glb(shape, &mesh, &groups)
+/// 頂点座標を 1e-6 mm 単位に量子化したキー。面をまたいで同じ点を同一視するのに使う。
+fn position_key(p: glam::DVec3) -> (i64, i64, i64) {
+	(
+		(p.x * 1e6).round() as i64,
+		(p.y * 1e6).round() as i64,
+		(p.z * 1e6).round() as i64,
+	)
 }
 
-/// 色グループ別プリミティブの GLB を組み立てる。
-/// 頂点バッファは全グループで共有し、インデックスバッファだけ色ごとに分ける。
-fn build_glb(
+/// 三角形をソリッドごとに分け、ソリッドごとの三角形番号リストと GLB のノードの木を返す。
+/// ソリッドとアセンブリの木は OCCT のトポロジーから読み（topology.rs）、products（STEP の製品構造）があれば
+/// 製品名をノード名にする。トポロジーを読めなければ面の連結成分をソリッドとみなして平らに並べる
+fn split_parts(
 	shape: &Shape,
 	mesh: &chijin::Mesh,
-	groups: &[(Option<chijin::Rgb>, Vec<usize>)],
-) -> Result<Vec<u8>, String> {
-	use json::validation::Checked::Valid;
+	products: &[StepAssemblyNode],
+) -> (Vec<Vec<usize>>, Vec<PartNode>) {
+	if let Some((mut nodes, faces)) = topology::shape_parts(shape) {
+		topology::name_parts(&mut nodes, products);
+		if let Some(parts) = assign_triangles(mesh, nodes, &faces) {
+			return parts;
+		}
+	}
+	let solids = split_solids(mesh);
+	let nodes = flat_parts(solids.len());
+	(solids, nodes)
+}
 
-	let positions: Vec<[f32; 3]> = mesh
-		.vertices
-		.iter()
-		.map(|v| [v.x as f32, v.y as f32, v.z as f32])
-		.collect();
+/// ソリッドを平らに並べたノードの木
+fn flat_parts(count: usize) -> Vec<PartNode> {
+	(0..count)
+		.map(|solid| PartNode::Solid { name: None, solid })
+		.collect()
+}
 
-	let mut pos_min = [f32::INFINITY; 3];
-	let mut pos_max = [f32::NEG_INFINITY; 3];
-	for p in &positions {
-		for i in 0..3 {
-			pos_min[i] = pos_min[i].min(p[i]);
-			pos_max[i] = pos_max[i].max(p[i]);
+/// 三角形を面の出現の属するソリッドに振り分ける。三角形のないソリッドはノードの木から取り除く。
+///
+/// faces は面の出現ごとの (TShapeId, ソリッドの番号) を TopExp_Explorer の順に並べたもの。
+/// メッシュも同じ順に面の出現ごとの三角形を並べるので、同じ面 (TShapeId) の三角形の連なりを順に出現へ対応させる。
+/// 同じ面の出現が続くとき（1 面の部品を続けて配置したときなど）は、連なりを出現の数で等分する。
+/// 三角形分割のない面はメッシュにないので飛ばす。対応が付かなければ None
+fn assign_triangles(
+	mesh: &chijin::Mesh,
+	nodes: Vec<PartNode>,
+	faces: &[(u64, usize)],
+) -> Option<(Vec<Vec<usize>>, Vec<PartNode>)> {
+	let count = faces.iter().map(|&(_, solid)| solid + 1).max().unwrap_or(0);
+	let mut triangles: Vec<Vec<usize>> = vec![Vec::new(); count];
+	let mut rest = faces.chunk_by(|a, b| a.0 == b.0);
+	let mut start = 0;
+	for run in mesh.face_ids.chunk_by(|a, b| a == b) {
+		let occurrences = rest.find(|occurrences| occurrences[0].0 == run[0])?;
+		if run.len() % occurrences.len() != 0 {
+			return None;
+		}
+		let per_face = run.len() / occurrences.len();
+		for (i, &(_, solid)) in occurrences.iter().enumerate() {
+			let first = start + i * per_face;
+			triangles[solid].extend(first..first + per_face);
+		}
+		start += run.len();
+	}
+	let mut map = Vec::with_capacity(count);
+	let mut solids = Vec::new();
+	for list in triangles {
+		map.push((!list.is_empty()).then_some(solids.len()));
+		if !list.is_empty() {
+			solids.push(list);
 		}
 	}
+	Some((solids, topology::renumber(nodes, &map)))
+}
 
-	let mut root = json::Root::default();
-	root.buffers.push(json::Buffer {
-		byte_length: json::validation::USize64(0),
-		name: None,
-		uri: None,
-		extensions: None,
-		extras: Default::default(),
-	});
+/// 三角形をソリッド単位に分割し、ソリッドごとの三角形番号リストを返す。
+///
+/// トポロジーを読めないときの代わりに、頂点座標を共有する面同士を同じソリッドとみなす（面の連結成分）。
+/// 隣接する面の境界頂点はエッジの離散化点を共有するので座標が一致する（接しているソリッドも 1 つになる）。
+fn split_solids(mesh: &chijin::Mesh) -> Vec<Vec<usize>> {
+	fn find(parent: &mut [usize], mut i: usize) -> usize {
+		while parent[i] != i {
+			parent[i] = parent[parent[i]];
+			i = parent[i];
+		}
+		i
+	}
 
-	let mut buffer: Vec<u8> = Vec::new();
+	let mut face_index: std::collections::HashMap<u64, usize> = std::collections::HashMap::new();
+	for &face_id in &mesh.face_ids {
+		let n = face_index.len();
+		face_index.entry(face_id).or_insert(n);
+	}
+	let mut parent: Vec<usize> = (0..face_index.len()).collect();
 
-	// 頂点バッファ（全グループ共有）
-	let pos_bytes = unsafe {
-		std::slice::from_raw_parts(positions.as_ptr() as *const u8, positions.len() * 12)
-	};
-	let pos_view = root.buffer_views.len() as u32;
-	buffer.extend_from_slice(pos_bytes);
-	root.buffer_views.push(json::buffer::View {
-		buffer: json::Index::new(0),
-		byte_length: json::validation::USize64(pos_bytes.len() as u64),
-		byte_offset: Some(json::validation::USize64(0)),
-		byte_stride: None,
-		name: None,
-		target: Some(Valid(json::buffer::Target::ArrayBuffer)),
-		extensions: None,
-		extras: Default::default(),
-	});
-	let pos_accessor = root.accessors.len() as u32;
-	root.accessors.push(json::Accessor {
-		buffer_view: Some(json::Index::new(pos_view)),
-		byte_offset: Some(json::validation::USize64(0)),
-		count: json::validation::USize64(positions.len() as u64),
-		component_type: Valid(json::accessor::GenericComponentType(
-			json::accessor::ComponentType::F32,
-		)),
-		type_: Valid(json::accessor::Type::Vec3),
-		extensions: None,
-		extras: Default::default(),
-		min: Some(serde_json::json!([pos_min[0], pos_min[1], pos_min[2]])),
-		max: Some(serde_json::json!([pos_max[0], pos_max[1], pos_max[2]])),
-		name: None,
-		normalized: false,
-		sparse: None,
-	});
+	// 量子化した頂点座標 → 最初にその座標を使った面
+	let mut owner: std::collections::HashMap<(i64, i64, i64), usize> =
+		std::collections::HashMap::new();
+	for (tri_idx, face_id) in mesh.face_ids.iter().enumerate() {
+		let face = face_index[face_id];
+		for &v in &mesh.indices[tri_idx * 3..tri_idx * 3 + 3] {
+			let other = *owner.entry(position_key(mesh.vertices[v])).or_insert(face);
+			let (a, b) = (find(&mut parent, face), find(&mut parent, other));
+			if a != b {
+				parent[a.max(b)] = a.min(b);
+			}
+		}
+	}
 
-	// 色グループごとにインデックスバッファ + プリミティブを生成
-	let mut primitives: Vec<json::mesh::Primitive> = Vec::new();
-	let mut materials: Vec<serde_json::Value> = Vec::new();
-
-	for (color, indices) in groups {
-		let idx_count = indices.len();
-		let (idx_bytes, idx_component_type) = if positions.len() <= 65535 {
-			let v: Vec<u16> = indices.iter().map(|&i| i as u16).collect();
-			let bytes = unsafe { std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len() * 2) }
-				.to_vec();
-			(bytes, json::accessor::ComponentType::U16)
+	// ルートの出現順にソリッド番号を振る（面の出現順 = 決定的）
+	let mut solid_of_root: std::collections::HashMap<usize, usize> =
+		std::collections::HashMap::new();
+	let mut solids: Vec<Vec<usize>> = Vec::new();
+	for (tri_idx, face_id) in mesh.face_ids.iter().enumerate() {
+		let root = find(&mut parent, face_index[face_id]);
+		let n = solids.len();
+		let solid = *solid_of_root.entry(root).or_insert(n);
+		if solid == solids.len() {
+			solids.push(Vec::new());
+		}
+		solids[solid].push(tri_idx);
+	}
+	solids
+}
+
+/// ソリッドの番号ごとのノード名
+fn solid_names(parts: &[PartNode], names: &mut [Option<String>]) {
+	for part in parts {
+		match part {
+			PartNode::Group { children, .. } => solid_names(children, names),
+			PartNode::Solid { name, solid } => names[*solid] = name.clone(),
+		}
+	}
+}
+
+/// ノードの木を glTF のノードとして追加し、追加した最上位のノードの番号を返す。
+/// ソリッドのノードは同じ番号のメッシュを持ち、名前もメッシュと同じにする
+fn push_part_nodes(
+	nodes: &mut Vec<json::Node>,
+	parts: &[PartNode],
+	meshes: &[json::Mesh],
+	groups: &mut usize,
+) -> Vec<json::Index<json::Node>> {
+	parts
+		.iter()
+		.map(|part| {
+			let index = nodes.len();
+			match part {
+				PartNode::Solid { solid, .. } => nodes.push(json::Node {
+					mesh: Some(json::Index::new(*solid as u32)),
+					name: meshes[*solid].name.clone(),
+					..Default::default()
+				}),
+				PartNode::Group { name, children } => {
+					nodes.push(json::Node {
+						name: Some(name.clone().unwrap_or_else(|| format!("assembly_{groups}"))),
+						..Default::default()
+					});
+					*groups += 1;
+					let children = push_part_nodes(nodes, children, meshes, groups);
+					nodes[index].children = Some(children);
+				}
+			}
+			json::Index::new(index as u32)
+		})
+		.collect()
+}
+
+/// GLB のバイナリチャンクを構成する区画。書き出し時にメッシュから直接変換する。
+enum BinSegment {
+	Positions,
+	Normals,
+	/// solids[solid][group] のインデックス列
+	Indices {
+		solid: usize,
+		group: usize,
+	},
+	Edges,
+}
+
+/// JSON チャンクとバイナリチャンクのレイアウトを先に確定させた GLB。
+///
+/// バイナリチャンクは [`GlbPlan::write_to`] で区画ごとにメッシュから変換しながら書き出すので、
+/// バッファ全体や GLB 全体のコピーをメモリに持たない。サイズは書き出し前に [`GlbPlan::byte_len`] で分かる。
+/// ノード構成: scene → ルートノード → アセンブリ・ソリッドのノードの木と注釈ノード。
+/// ノード名は STEP の製品名で、なければ solid_{ソリッドの番号} / assembly_{番号}
+pub struct GlbPlan {
+	/// 4 バイト境界までスペースで埋めた JSON チャンク
+	json: Vec<u8>,
+	mesh: chijin::Mesh,
+	solids: Vec<ColorGroups>,
+	/// エッジの線分（始点・終点の組）
+	edges: Vec<glam::DVec3>,
+	segments: Vec<BinSegment>,
+	/// 頂点数が u16 に収まらないときは u32 インデックス
+	wide_indices: bool,
+	bin_len: usize,
+}
+
+impl GlbPlan {
+	/// Shape をメッシュ化し、GLB (GLTF Binary) のレイアウトを決める。
+	/// ソリッドごとに1つの glTF ノード（メッシュ）を作り、アセンブリ（コンパウンド）の木の通りに並べる。
+	/// products（STEP の製品構造）を渡すと、木の形が合うノードに製品名を付ける。
+	/// shape.colormap が空の場合はグレー単色プリミティブ、
+	/// 色情報がある場合は色グループ別プリミティブを生成する。
+	/// material を指定すると面の色の代わりにそのプリセットの PBR マテリアルで全体を描き、法線も出力する。
+	/// info を指定すると注釈を空ノードとして、ラベル・寸法・体積・単位・ハッシュを extras として埋め込む。
+	pub fn new(
+		shape: &Shape,
+		material: Option<&MaterialPreset>,
+		info: Option<&GlbInfo>,
+		products: &[StepAssemblyNode],
+	) -> Result<Self, String> {
+		Self::from_mesh(shape, shape_mesh(shape)?, material, info, products)
+	}
+
+	/// shape_mesh でメッシュ化済みの mesh から GLB のレイアウトを決める（new と同じ）
+	pub fn from_mesh(
+		shape: &Shape,
+		mesh: chijin::Mesh,
+		material: Option<&MaterialPreset>,
+		info: Option<&GlbInfo>,
+		products: &[StepAssemblyNode],
+	) -> Result<Self, String> {
+		let (solids, parts) = split_parts(shape, &mesh, products);
+		let solids: Vec<ColorGroups> = solids
+			.iter()
+			.map(|triangles| color_groups(shape, &mesh, triangles))
+			.collect();
+		Self::from_parts(shape, mesh, solids, parts, material, info)
+	}
+
+	/// ソリッドを平らに並べた GLB
+	#[cfg(test)]
+	fn flat(
+		shape: &Shape,
+		mesh: chijin::Mesh,
+		solids: Vec<ColorGroups>,
+		material: Option<&MaterialPreset>,
+		info: Option<&GlbInfo>,
+	) -> Result<Self, String> {
+		let parts = flat_parts(solids.len());
+		Self::from_parts(shape, mesh, solids, parts, material, info)
+	}
+
+	/// 頂点バッファは全グループで共有し、インデックスバッファだけ色ごとに分ける。
+	fn from_parts(
+		shape: &Shape,
+		mesh: chijin::Mesh,
+		solids: Vec<ColorGroups>,
+		parts: Vec<PartNode>,
+		material: Option<&MaterialPreset>,
+		info: Option<&GlbInfo>,
+	) -> Result<Self, String> {
+		use json::accessor::{ComponentType, Type};
+		use json::buffer::Target;
+		use json::validation::Checked::Valid;
+
+		let mut root = json::Root::default();
+		let mut segments: Vec<BinSegment> = Vec::new();
+		let mut offset = 0usize;
+
+		// 頂点バッファ（全グループ共有）
+		let vertex_count = mesh.vertices.len();
+		let pos_accessor = push_accessor(
+			&mut root,
+			offset,
+			vertex_count * 12,
+			Target::ArrayBuffer,
+			vertex_count,
+			ComponentType::F32,
+			Type::Vec3,
+		);
+		let mut pos_min = [f32::INFINITY; 3];
+		let mut pos_max = [f32::NEG_INFINITY; 3];
+		for v in &mesh.vertices {
+			for (i, c) in [v.x as f32, v.y as f32, v.z as f32].into_iter().enumerate() {
+				pos_min[i] = pos_min[i].min(c);
+				pos_max[i] = pos_max[i].max(c);
+			}
+		}
+		root.accessors[pos_accessor as usize].min = Some(serde_json::json!(pos_min));
+		root.accessors[pos_accessor as usize].max = Some(serde_json::json!(pos_max));
+		segments.push(BinSegment::Positions);
+		offset += vertex_count * 12;
+
+		// 法線（PBR マテリアル指定時のみ。unlit では不要）
+		let normal_accessor = if material.is_some() {
+			let accessor = push_accessor(
+				&mut root,
+				offset,
+				vertex_count * 12,
+				Target::ArrayBuffer,
+				vertex_count,
+				ComponentType::F32,
+				Type::Vec3,
+			);
+			segments.push(BinSegment::Normals);
+			offset += vertex_count * 12;
+			Some(accessor)
 		} else {
-			let v: Vec<u32> = indices.iter().map(|&i| i as u32).collect();
-			let bytes = unsafe { std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len() * 4) }
-				.to_vec();
-			(bytes, json::accessor::ComponentType::U32)
+			None
 		};
 
-		let idx_view = root.buffer_views.len() as u32;
-		let idx_offset = buffer.len();
-		buffer.extend_from_slice(&idx_bytes);
-		while buffer.len() % 4 != 0 {
-			buffer.push(0);
-		}
-		root.buffer_views.push(json::buffer::View {
-			buffer: json::Index::new(0),
-			byte_length: json::validation::USize64(idx_bytes.len() as u64),
-			byte_offset: Some(json::validation::USize64(idx_offset as u64)),
-			byte_stride: None,
-			name: None,
-			target: Some(Valid(json::buffer::Target::ElementArrayBuffer)),
-			extensions: None,
-			extras: Default::default(),
-		});
-		let idx_accessor = root.accessors.len() as u32;
-		root.accessors.push(json::Accessor {
-			buffer_view: Some(json::Index::new(idx_view)),
-			byte_offset: Some(json::validation::USize64(0)),
-			count: json::validation::USize64(idx_count as u64),
-			component_type: Valid(json::accessor::GenericComponentType(idx_component_type)),
-			type_: Valid(json::accessor::Type::Scalar),
-			extensions: None,
-			extras: Default::default(),
-			min: None,
-			max: None,
-			name: None,
-			normalized: false,
-			sparse: None,
-		});
+		// ソリッドごとにメッシュを作り、色グループごとにインデックスバッファ + プリミティブを生成
+		// マテリアルは色が同じならソリッド間で共有する
+		let wide_indices = vertex_count > 65535;
+		let (index_size, index_type) = if wide_indices {
+			(4, ComponentType::U32)
+		} else {
+			(2, ComponentType::U16)
+		};
+		let mut meshes: Vec<json::Mesh> = Vec::new();
+		let mut materials: Vec<serde_json::Value> = Vec::new();
+		let mut material_of_color: std::collections::HashMap<(u32, u32, u32), u32> =
+			std::collections::HashMap::new();
+		let mut names = vec![None; solids.len()];
+		solid_names(&parts, &mut names);
 
-		let material_index = materials.len() as u32;
-		let (r, g, b) = color
-			.map(|c| (c.r as f64, c.g as f64, c.b as f64))
-			.unwrap_or((0.8, 0.8, 0.8));
-		materials.push(serde_json::json!({
-			"extensions": { "KHR_materials_unlit": {} },
-			"pbrMetallicRoughness": {
-				"baseColorFactor": [r, g, b, 1.0],
-				"metallicFactor": 0.0,
-				"roughnessFactor": 1.0
-			},
-			"alphaMode": "OPAQUE",
-			"doubleSided": true
-		}));
-
-		primitives.push(json::mesh::Primitive {
-			attributes: {
-				let mut map = std::collections::BTreeMap::new();
-				map.insert(
+		for (solid, groups) in solids.iter().enumerate() {
+			let mut primitives: Vec<json::mesh::Primitive> = Vec::new();
+			for (group, (color, indices)) in groups.iter().enumerate() {
+				let byte_length = indices.len() * index_size;
+				let idx_accessor = push_accessor(
+					&mut root,
+					offset,
+					byte_length,
+					Target::ElementArrayBuffer,
+					indices.len(),
+					index_type,
+					Type::Scalar,
+				);
+				segments.push(BinSegment::Indices { solid, group });
+				offset += byte_length.next_multiple_of(4);
+
+				// プリセット指定時は全プリミティブで 1 つのマテリアルを共有する
+				let key = match material {
+					Some(_) => (u32::MAX, u32::MAX, u32::MAX),
+					None => color.map(rgb_key).unwrap_or((0, 0, 0)),
+				};
+				let material_index = *material_of_color.entry(key).or_insert_with(|| {
+					if let Some(preset) = material {
+						let [r, g, b] = preset.base_color;
+						materials.push(serde_json::json!({
+							"name": preset.name,
+							"pbrMetallicRoughness": {
+								"baseColorFactor": [r, g, b, 1.0],
+								"metallicFactor": preset.metallic,
+								"roughnessFactor": preset.roughness
+							},
+							"alphaMode": "OPAQUE",
+							"doubleSided": true
+						}));
+						return (materials.len() - 1) as u32;
+					}
+					let (r, g, b) = color
+						.map(|c| (c.r as f64, c.g as f64, c.b as f64))
+						.unwrap_or((0.8, 0.8, 0.8));
+					materials.push(serde_json::json!({
+						"extensions": { "KHR_materials_unlit": {} },
+						"pbrMetallicRoughness": {
+							"baseColorFactor": [r, g, b, 1.0],
+							"metallicFactor": 0.0,
+							"roughnessFactor": 1.0
+						},
+						"alphaMode": "OPAQUE",
+						"doubleSided": true
+					}));
+					(materials.len() - 1) as u32
+				});
+
+				let mut attributes = std::collections::BTreeMap::new();
+				attributes.insert(
 					Valid(json::mesh::Semantic::Positions),
 					json::Index::new(pos_accessor),
 				);
-				map
-			},
-			indices: Some(json::Index::new(idx_accessor)),
-			extensions: None,
-			extras: Default::default(),
-			material: Some(json::Index::new(material_index)),
-			mode: Valid(json::mesh::Mode::Triangles),
-			targets: None,
-		});
-	}
+				if let Some(idx) = normal_accessor {
+					attributes.insert(Valid(json::mesh::Semantic::Normals), json::Index::new(idx));
+				}
+				primitives.push(json::mesh::Primitive {
+					attributes,
+					indices: Some(json::Index::new(idx_accessor)),
+					extensions: None,
+					extras: Default::default(),
+					material: Some(json::Index::new(material_index)),
+					mode: Valid(json::mesh::Mode::Triangles),
+					targets: None,
+				});
+			}
+			meshes.push(json::Mesh {
+				extensions: None,
+				extras: Default::default(),
+				name: Some(
+					names[solid]
+						.take()
+						.unwrap_or_else(|| format!("solid_{solid}")),
+				),
+				primitives,
+				weights: None,
+			});
+		}
 
-	// エッジデータ
-	let mut edge_data: Vec<f32> = Vec::new();
-	for edge in shape.edges() {
-		let segments: Vec<_> = edge.approximation_segments(0.1).collect();
-		for w in segments.windows(2) {
-			edge_data.extend_from_slice(&[
-				w[0].x as f32,
-				w[0].y as f32,
-				w[0].z as f32,
-				w[1].x as f32,
-				w[1].y as f32,
-				w[1].z as f32,
-			]);
-		}
-	}
-	let edge_accessor_index = if !edge_data.is_empty() {
-		let edge_bytes = unsafe {
-			std::slice::from_raw_parts(edge_data.as_ptr() as *const u8, edge_data.len() * 4)
+		// エッジデータ（線分の端点の組）
+		let mut edges: Vec<glam::DVec3> = Vec::new();
+		for edge in shape.edges() {
+			let points: Vec<_> = edge.approximation_segments(MESH_TOLERANCE).collect();
+			for w in points.windows(2) {
+				edges.extend_from_slice(w);
+			}
+		}
+		let edge_accessor_index = if !edges.is_empty() {
+			let accessor = push_accessor(
+				&mut root,
+				offset,
+				edges.len() * 12,
+				Target::ArrayBuffer,
+				edges.len(),
+				ComponentType::F32,
+				Type::Vec3,
+			);
+			segments.push(BinSegment::Edges);
+			offset += edges.len() * 12;
+			Some(accessor)
+		} else {
+			None
 		};
-		let edge_view = root.buffer_views.len() as u32;
-		let edge_offset = buffer.len();
-		buffer.extend_from_slice(edge_bytes);
-		root.buffer_views.push(json::buffer::View {
-			buffer: json::Index::new(0),
-			byte_length: json::validation::USize64(edge_bytes.len() as u64),
-			byte_offset: Some(json::validation::USize64(edge_offset as u64)),
-			byte_stride: None,
+
+		root.buffers.push(json::Buffer {
+			byte_length: json::validation::USize64(offset as u64),
 			name: None,
-			target: Some(Valid(json::buffer::Target::ArrayBuffer)),
+			uri: None,
 			extensions: None,
 			extras: Default::default(),
 		});
-		let accessor_index = root.accessors.len() as u32;
-		root.accessors.push(json::Accessor {
-			buffer_view: Some(json::Index::new(edge_view)),
-			byte_offset: Some(json::validation::USize64(0)),
-			count: json::validation::USize64((edge_data.len() / 3) as u64),
-			component_type: Valid(json::accessor::GenericComponentType(
-				json::accessor::ComponentType::F32,
-			)),
-			type_: Valid(json::accessor::Type::Vec3),
+
+		// ルートノード (index 0) の子としてアセンブリ・ソリッドのノードの木を並べる
+		root.nodes.push(json::Node {
+			name: Some("shape".to_string()),
+			..Default::default()
+		});
+		let mut children = push_part_nodes(&mut root.nodes, &parts, &meshes, &mut 0);
+		root.meshes = meshes;
+
+		// 注釈ノード: PointAnnotation は位置に置いた空ノード、
+		// DistanceAnnotation は始点・終点の子ノードを持つ空ノード。注釈の内容は各ノードの extras に入れる
+		let mut node_extras: Vec<(usize, serde_json::Value)> = Vec::new();
+		for annotation in info.map(|info| info.annotations).unwrap_or_default() {
+			let index = root.nodes.len();
+			children.push(json::Index::new(index as u32));
+			match annotation.as_ref() {
+				Annotation::Point(point) => {
+					node_extras.push((
+						index,
+						serde_json::json!({
+							"annotation": "point",
+							"label": point.label,
+							"position": point.position,
+						}),
+					));
+					root.nodes.push(json::Node {
+						name: Some(point.label.clone()),
+						translation: annotation_translation(&point.position),
+						..Default::default()
+					});
+				}
+				Annotation::Distance(distance) => {
+					let length = match (
+						annotation_translation(&distance.start),
+						annotation_translation(&distance.end),
+					) {
+						(Some(_), Some(_)) => Some(
+							distance
+								.start
+								.iter()
+								.zip(&distance.end)
+								.map(|(a, b)| (b - a) * (b - a))
+								.sum::<f64>()
+								.sqrt(),
+						),
+						_ => None,
+					};
+					node_extras.push((
+						index,
+						serde_json::json!({
+							"annotation": "distance",
+							"label": distance.label,
+							"start": distance.start,
+							"end": distance.end,
+							"distance": length,
+						}),
+					));
+					root.nodes.push(json::Node {
+						name: Some(distance.label.clone()),
+						children: Some(vec![
+							json::Index::new(index as u32 + 1),
+							json::Index::new(index as u32 + 2),
+						]),
+						..Default::default()
+					});
+					for (name, point) in [("start", &distance.start), ("end", &distance.end)] {
+						root.nodes.push(json::Node {
+							name: Some(name.to_string()),
+							translation: annotation_translation(point),
+							..Default::default()
+						});
+					}
+				}
+			}
+		}
+
+		if !children.is_empty() {
+			root.nodes[0].children = Some(children);
+		}
+		root.scenes.push(json::Scene {
 			extensions: None,
 			extras: Default::default(),
-			min: None,
-			max: None,
 			name: None,
-			normalized: false,
-			sparse: None,
+			nodes: vec![json::Index::new(0)],
 		});
-		Some(accessor_index)
-	} else {
-		None
-	};
+		root.scene = Some(json::Index::new(0));
+
+		// JSON 組み立て・マテリアル注入
+		let mut json_val = serde_json::to_value(&root).map_err(|e| e.to_string())?;
+		if material.is_none() {
+			json_val["extensionsUsed"] = serde_json::json!(["KHR_materials_unlit"]);
+		}
+		json_val["materials"] = serde_json::Value::Array(materials);
+		for (index, extras) in node_extras {
+			json_val["nodes"][index]["extras"] = extras;
+		}
+		let mut extras = serde_json::Map::new();
+		if let Some(idx) = edge_accessor_index {
+			extras.insert("edgeAccessor".to_string(), serde_json::json!(idx));
+		}
+		if let Some(info) = info {
+			extras.insert("label".to_string(), serde_json::json!(info.label));
+			extras.insert("units".to_string(), serde_json::json!("mm"));
+			extras.insert("shapeHash".to_string(), serde_json::json!(info.shape_hash));
+			extras.insert("volume".to_string(), serde_json::json!(shape.volume()));
+			if let Some((lo, hi)) = bounding_box(&mesh) {
+				extras.insert(
+					"boundingBox".to_string(),
+					serde_json::json!({ "min": lo.to_array(), "max": hi.to_array() }),
+				);
+			}
+		}
+		if !extras.is_empty() {
+			json_val["extras"] = serde_json::Value::Object(extras);
+		}
+
+		let mut json_bytes = serde_json::to_vec(&json_val).map_err(|e| e.to_string())?;
+		json_bytes.resize(json_bytes.len().next_multiple_of(4), b' ');
+
+		Ok(Self {
+			json: json_bytes,
+			mesh,
+			solids,
+			edges,
+			segments,
+			wide_indices,
+			bin_len: offset,
+		})
+	}
+
+	/// GLB 全体のバイト数（ヘッダ + JSON チャンク + バイナリチャンク）
+	pub fn byte_len(&self) -> usize {
+		12 + 8 + self.json.len() + 8 + self.bin_len
+	}
 
-	root.buffers[0].byte_length = json::validation::USize64(buffer.len() as u64);
+	/// GLB を先頭から順に書き出す。バイナリチャンクは区画ごとに変換しながら書く。
+	pub fn write_to<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+		let total = u32::try_from(self.byte_len())
+			.map_err(|_| std::io::Error::other("GLB が 4GiB を超えます"))?;
+		out.write_all(b"glTF")?;
+		out.write_all(&2u32.to_le_bytes())?;
+		out.write_all(&total.to_le_bytes())?;
+		out.write_all(&(self.json.len() as u32).to_le_bytes())?;
+		out.write_all(b"JSON")?;
+		out.write_all(&self.json)?;
+		out.write_all(&(self.bin_len as u32).to_le_bytes())?;
+		out.write_all(b"BIN\0")?;
 
-	root.meshes.push(json::Mesh {
+		let write_vec3 = |out: &mut W, v: glam::DVec3| {
+			out.write_all(&(v.x as f32).to_le_bytes())?;
+			out.write_all(&(v.y as f32).to_le_bytes())?;
+			out.write_all(&(v.z as f32).to_le_bytes())
+		};
+		for segment in &self.segments {
+			match *segment {
+				BinSegment::Positions => {
+					for &v in &self.mesh.vertices {
+						write_vec3(out, v)?;
+					}
+				}
+				BinSegment::Normals => {
+					for n in &self.mesh.normals {
+						write_vec3(out, n.normalize_or_zero())?;
+					}
+				}
+				BinSegment::Indices { solid, group } => {
+					let indices = &self.solids[solid][group].1;
+					if self.wide_indices {
+						for &i in indices {
+							out.write_all(&(i as u32).to_le_bytes())?;
+						}
+					} else {
+						for &i in indices {
+							out.write_all(&(i as u16).to_le_bytes())?;
+						}
+						// 4 バイト境界までパディング
+						if indices.len() % 2 == 1 {
+							out.write_all(&[0, 0])?;
+						}
+					}
+				}
+				BinSegment::Edges => {
+					for &p in &self.edges {
+						write_vec3(out, p)?;
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+/// バイナリチャンク内の区画を指す bufferView と accessor を 1 組追加し、accessor 番号を返す
+fn push_accessor(
+	root: &mut json::Root,
+	offset: usize,
+	byte_length: usize,
+	target: json::buffer::Target,
+	count: usize,
+	component_type: json::accessor::ComponentType,
+	type_: json::accessor::Type,
+) -> u32 {
+	use json::validation::Checked::Valid;
+
+	let view = root.buffer_views.len() as u32;
+	root.buffer_views.push(json::buffer::View {
+		buffer: json::Index::new(0),
+		byte_length: json::validation::USize64(byte_length as u64),
+		byte_offset: Some(json::validation::USize64(offset as u64)),
+		byte_stride: None,
+		name: None,
+		target: Some(Valid(target)),
 		extensions: None,
 		extras: Default::default(),
-		name: None,
-		primitives,
-		weights: None,
-	});
-	root.nodes.push(json::Node {
-		mesh: Some(json::Index::new(0)),
-		..Default::default()
 	});
-	root.scenes.push(json::Scene {
+	root.accessors.push(json::Accessor {
+		buffer_view: Some(json::Index::new(view)),
+		byte_offset: Some(json::validation::USize64(0)),
+		count: json::validation::USize64(count as u64),
+		component_type: Valid(json::accessor::GenericComponentType(component_type)),
+		type_: Valid(type_),
 		extensions: None,
 		extras: Default::default(),
+		min: None,
+		max: None,
 		name: None,
-		nodes: vec![json::Index::new(0)],
+		normalized: false,
+		sparse: None,
 	});
-	root.scene = Some(json::Index::new(0));
+	root.accessors.len() as u32 - 1
+}
+
+/// 注釈の座標 [x, y, z] をノードの translation に変換する。要素数が 3 でなければ None。
+fn annotation_translation(v: &[f64]) -> Option<[f32; 3]> {
+	match v {
+		[x, y, z] => Some([*x as f32, *y as f32, *z as f32]),
+		_ => None,
+	}
+}
 
-	// JSON 組み立て・マテリアル注入・GLB バイナリ出力
-	let json_string = json::serialize::to_string(&root).map_err(|e| e.to_string())?;
-	let mut json_val: serde_json::Value =
-		serde_json::from_str(&json_string).map_err(|e| e.to_string())?;
+// ---------------------------------------------------------------------------
+// メッシュ形式 (STL / OBJ / PLY)
+// ---------------------------------------------------------------------------
 
-	json_val["extensionsUsed"] = serde_json::json!(["KHR_materials_unlit"]);
-	json_val["materials"] = serde_json::Value::Array(materials);
-	if let Some(idx) = edge_accessor_index {
-		json_val["extras"] = serde_json::json!({ "edgeAccessor": idx });
+/// 三角形の面法線（右手系・単位ベクトル）。縮退三角形はゼロベクトル。
+fn triangle_normal(mesh: &chijin::Mesh, tri: &[usize]) -> glam::DVec3 {
+	let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| mesh.vertices[i]);
+	(b - a).cross(c - a).normalize_or_zero()
+}
+
+/// 0.0..=1.0 の色成分を 0..=255 に変換する
+fn rgb_u8(c: chijin::Rgb) -> [u8; 3] {
+	[c.r, c.g, c.b].map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// STL を生成する。ascii=false でバイナリ STL、true でテキスト STL。
+/// STL は色を持てないため colormap は無視する。
+pub fn stl(shape: &Shape, ascii: bool) -> Result<Vec<u8>, String> {
+	let mesh = shape_mesh(shape)?;
+	Ok(if ascii {
+		write_stl_ascii(&mesh)
+	} else {
+		write_stl_binary(&mesh)
+	})
+}
+
+/// バイナリ STL: 80 バイトヘッダ + 三角形数 (u32) + 三角形ごとに 50 バイト
+fn write_stl_binary(mesh: &chijin::Mesh) -> Vec<u8> {
+	let count = mesh.indices.len() / 3;
+	let mut buf = Vec::with_capacity(84 + count * 50);
+	// ヘッダは "solid" で始めてはいけない（テキスト STL と誤認される）
+	let mut header = [0u8; 80];
+	let title = b"lambda360 binary STL (mm)";
+	header[..title.len()].copy_from_slice(title);
+	buf.extend_from_slice(&header);
+	buf.extend_from_slice(&(count as u32).to_le_bytes());
+	for tri in mesh.indices.chunks_exact(3) {
+		let normal = triangle_normal(mesh, tri);
+		for v in [
+			normal,
+			mesh.vertices[tri[0]],
+			mesh.vertices[tri[1]],
+			mesh.vertices[tri[2]],
+		] {
+			for x in v.to_array() {
+				buf.extend_from_slice(&(x as f32).to_le_bytes());
+			}
+		}
+		buf.extend_from_slice(&0u16.to_le_bytes());
 	}
+	buf
+}
 
-	let json_string = serde_json::to_string(&json_val).map_err(|e| e.to_string())?;
-	let mut json_bytes = json_string.into_bytes();
-	while json_bytes.len() % 4 != 0 {
-		json_bytes.push(b' ');
+/// テキスト STL
+fn write_stl_ascii(mesh: &chijin::Mesh) -> Vec<u8> {
+	use std::fmt::Write as _;
+	let mut s = String::from("solid lambda360\n");
+	for tri in mesh.indices.chunks_exact(3) {
+		let n = triangle_normal(mesh, tri);
+		writeln!(
+			s,
+			"  facet normal {} {} {}",
+			n.x as f32, n.y as f32, n.z as f32
+		)
+		.unwrap();
+		s.push_str("    outer loop\n");
+		for &i in tri {
+			let v = mesh.vertices[i];
+			writeln!(
+				s,
+				"      vertex {} {} {}",
+				v.x as f32, v.y as f32, v.z as f32
+			)
+			.unwrap();
+		}
+		s.push_str("    endloop\n  endfacet\n");
 	}
+	s.push_str("endsolid lambda360\n");
+	s.into_bytes()
+}
 
-	let mut glb = Vec::new();
-	let total_size = 12 + 8 + json_bytes.len() + 8 + buffer.len();
-	glb.write_all(b"glTF").unwrap();
-	glb.write_all(&2u32.to_le_bytes()).unwrap();
-	glb.write_all(&(total_size as u32).to_le_bytes()).unwrap();
-	glb.write_all(&(json_bytes.len() as u32).to_le_bytes())
+/// OBJ + MTL を生成し、model.obj / model.mtl を含む ZIP として返す。
+/// 色グループごとに MTL マテリアルを1つ作り、usemtl で面を切り替える。
+pub fn obj_archive(shape: &Shape) -> Result<Vec<u8>, String> {
+	let mesh = shape_mesh(shape)?;
+	let triangles: Vec<usize> = (0..mesh.face_ids.len()).collect();
+	let groups = color_groups(shape, &mesh, &triangles);
+	let (obj, mtl) = write_obj(&mesh, &groups, "model.mtl");
+	zip_archive(&[("model.obj", obj), ("model.mtl", mtl)])
+}
+
+/// OBJ 本体と MTL を文字列で返す。頂点番号は 1 始まり。
+fn write_obj(mesh: &chijin::Mesh, groups: &ColorGroups, mtl_name: &str) -> (Vec<u8>, Vec<u8>) {
+	use std::fmt::Write as _;
+	let mut obj = format!("# lambda360 (units: mm)\nmtllib {mtl_name}\n");
+	for v in &mesh.vertices {
+		writeln!(obj, "v {} {} {}", v.x as f32, v.y as f32, v.z as f32).unwrap();
+	}
+	for n in &mesh.normals {
+		writeln!(obj, "vn {} {} {}", n.x as f32, n.y as f32, n.z as f32).unwrap();
+	}
+	let mut mtl = String::from("# lambda360\n");
+	for (i, (color, indices)) in groups.iter().enumerate() {
+		let c = color.unwrap_or(DEFAULT_RGB);
+		writeln!(
+			mtl,
+			"newmtl color_{i}\nKa 0 0 0\nKd {} {} {}\nKs 0 0 0\nd 1\nillum 1",
+			c.r, c.g, c.b
+		)
 		.unwrap();
-	glb.write_all(b"JSON").unwrap();
-	glb.write_all(&json_bytes).unwrap();
-	glb.write_all(&(buffer.len() as u32).to_le_bytes()).unwrap();
-	glb.write_all(b"BIN\0").unwrap();
-	glb.write_all(&buffer).unwrap();
+		writeln!(obj, "usemtl color_{i}").unwrap();
+		for tri in indices.chunks_exact(3) {
+			let [a, b, c] = [tri[0] + 1, tri[1] + 1, tri[2] + 1];
+			writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c}").unwrap();
+		}
+	}
+	(obj.into_bytes(), mtl.into_bytes())
+}
+
+/// バイナリ PLY (little endian) を生成する。面の色を頂点色 (red/green/blue) として書く。
+/// chijin のメッシュは面ごとに頂点を持つので、頂点色で面の色がそのまま表現できる。
+pub fn ply(shape: &Shape) -> Result<Vec<u8>, String> {
+	let mesh = shape_mesh(shape)?;
+	let mut vertex_colors = vec![DEFAULT_RGB; mesh.vertices.len()];
+	for (tri_idx, face_id) in mesh.face_ids.iter().enumerate() {
+		if let Some(&rgb) = shape.colormap.get(&chijin::TShapeId(*face_id)) {
+			for &v in &mesh.indices[tri_idx * 3..tri_idx * 3 + 3] {
+				vertex_colors[v] = rgb;
+			}
+		}
+	}
+	Ok(write_ply(&mesh, &vertex_colors))
+}
+
+fn write_ply(mesh: &chijin::Mesh, vertex_colors: &[chijin::Rgb]) -> Vec<u8> {
+	let face_count = mesh.indices.len() / 3;
+	let mut buf = format!(
+		"ply\nformat binary_little_endian 1.0\ncomment lambda360 (units: mm)\n\
+		 element vertex {}\nproperty float x\nproperty float y\nproperty float z\n\
+		 property uchar red\nproperty uchar green\nproperty uchar blue\n\
+		 element face {}\nproperty list uchar uint vertex_indices\nend_header\n",
+		mesh.vertices.len(),
+		face_count,
+	)
+	.into_bytes();
+	for (v, &c) in mesh.vertices.iter().zip(vertex_colors) {
+		for x in v.to_array() {
+			buf.extend_from_slice(&(x as f32).to_le_bytes());
+		}
+		buf.extend_from_slice(&rgb_u8(c));
+	}
+	for tri in mesh.indices.chunks_exact(3) {
+		buf.push(3);
+		for &i in tri {
+			buf.extend_from_slice(&(i as u32).to_le_bytes());
+		}
+	}
+	buf
+}
 
-	Ok(glb)
+/// 3MF を生成する。
+/// ソリッドごとに1つの object を作り、頂点を溶接して多様体メッシュにする（3MF の要件）。
+/// 色は basematerials にまとめ、三角形ごとに p1 で参照する。単位はミリメートル。
+pub fn three_mf(shape: &Shape) -> Result<Vec<u8>, String> {
+	let mesh = shape_mesh(shape)?;
+	let model = write_3mf_model(shape, &mesh);
+	zip_archive(&[
+		(
+			"[Content_Types].xml",
+			THREE_MF_CONTENT_TYPES.as_bytes().to_vec(),
+		),
+		("_rels/.rels", THREE_MF_RELS.as_bytes().to_vec()),
+		("3D/3dmodel.model", model),
+	])
+}
+
+const THREE_MF_CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+ <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+ <Default Extension="model" ContentType="application/vnd.ms-package.3dmanufacturing-3dmodel+xml"/>
+</Types>
+"#;
+
+const THREE_MF_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+ <Relationship Target="/3D/3dmodel.model" Id="rel0" Type="http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel"/>
+</Relationships>
+"#;
+
+/// 3D/3dmodel.model の XML を組み立てる
+fn write_3mf_model(shape: &Shape, mesh: &chijin::Mesh) -> Vec<u8> {
+	use std::fmt::Write as _;
+
+	// basematerials: 色ごとに1エントリ。色なし面は既定色のエントリを使う
+	let mut bases: Vec<chijin::Rgb> = Vec::new();
+	let mut base_of_color: std::collections::HashMap<(u32, u32, u32), usize> =
+		std::collections::HashMap::new();
+	let mut base_index = |rgb: chijin::Rgb| {
+		*base_of_color.entry(rgb_key(rgb)).or_insert_with(|| {
+			bases.push(rgb);
+			bases.len() - 1
+		})
+	};
+	let tri_base: Vec<usize> = mesh
+		.face_ids
+		.iter()
+		.map(|id| {
+			let rgb = shape.colormap.get(&chijin::TShapeId(*id)).copied();
+			base_index(rgb.unwrap_or(DEFAULT_RGB))
+		})
+		.collect();
+
+	let mut xml = String::from(
+		"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+		 <model unit=\"millimeter\" xml:lang=\"en-US\" \
+		 xmlns=\"http://schemas.microsoft.com/3dmanufacturing/core/2015/02\">\n\
+		 \t<metadata name=\"Application\">lambda360</metadata>\n\
+		 \t<resources>\n\
+		 \t\t<basematerials id=\"1\">\n",
+	);
+	for (i, c) in bases.iter().enumerate() {
+		let [r, g, b] = rgb_u8(*c);
+		writeln!(
+			xml,
+			"\t\t\t<base name=\"color_{i}\" displaycolor=\"#{r:02X}{g:02X}{b:02X}FF\"/>"
+		)
+		.unwrap();
+	}
+	xml.push_str("\t\t</basematerials>\n");
+
+	let solids = split_solids(mesh);
+	for (solid_idx, triangles) in solids.iter().enumerate() {
+		let (vertices, welded) = weld_triangles(mesh, triangles);
+		let object_id = solid_idx + 2;
+		let default_base = triangles.first().map(|&t| tri_base[t]).unwrap_or(0);
+		writeln!(
+			xml,
+			"\t\t<object id=\"{object_id}\" name=\"solid_{solid_idx}\" type=\"model\" pid=\"1\" pindex=\"{default_base}\">\n\t\t\t<mesh>\n\t\t\t\t<vertices>"
+		)
+		.unwrap();
+		for v in &vertices {
+			writeln!(
+				xml,
+				"\t\t\t\t\t<vertex x=\"{}\" y=\"{}\" z=\"{}\"/>",
+				v.x as f32, v.y as f32, v.z as f32
+			)
+			.unwrap();
+		}
+		xml.push_str("\t\t\t\t</vertices>\n\t\t\t\t<triangles>\n");
+		for (tri_idx, [v1, v2, v3]) in welded {
+			writeln!(
+				xml,
+				"\t\t\t\t\t<triangle v1=\"{v1}\" v2=\"{v2}\" v3=\"{v3}\" pid=\"1\" p1=\"{}\"/>",
+				tri_base[tri_idx]
+			)
+			.unwrap();
+		}
+		xml.push_str("\t\t\t\t</triangles>\n\t\t\t</mesh>\n\t\t</object>\n");
+	}
+	xml.push_str("\t</resources>\n\t<build>\n");
+	for solid_idx in 0..solids.len() {
+		writeln!(xml, "\t\t<item objectid=\"{}\"/>", solid_idx + 2).unwrap();
+	}
+	xml.push_str("\t</build>\n</model>\n");
+	xml.into_bytes()
+}
+
+/// 指定した三角形の頂点を座標で溶接する。
+/// 返り値は (溶接後の頂点, (元の三角形番号, 溶接後の頂点番号)) 。溶接で縮退した三角形は除く。
+fn weld_triangles(
+	mesh: &chijin::Mesh,
+	triangles: &[usize],
+) -> (Vec<glam::DVec3>, Vec<(usize, [usize; 3])>) {
+	let mut vertices = Vec::new();
+	let mut index_of: std::collections::HashMap<(i64, i64, i64), usize> =
+		std::collections::HashMap::new();
+	let mut welded = Vec::with_capacity(triangles.len());
+	for &tri_idx in triangles {
+		let tri = [0, 1, 2].map(|k| {
+			let p = mesh.vertices[mesh.indices[tri_idx * 3 + k]];
+			*index_of.entry(position_key(p)).or_insert_with(|| {
+				vertices.push(p);
+				vertices.len() - 1
+			})
+		});
+		if tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0] {
+			welded.push((tri_idx, tri));
+		}
+	}
+	(vertices, welded)
+}
+
+/// (ファイル名, 中身) の組から Deflate 圧縮の ZIP を作る
+fn zip_archive(entries: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, String> {
+	let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+	let options = zip::write::SimpleFileOptions::default()
+		.compression_method(zip::CompressionMethod::Deflated);
+	for (name, data) in entries {
+		zip.start_file(*name, options)
+			.map_err(|e| format!("ZIP書き込み失敗: {e:?}"))?;
+		zip.write_all(data)
+			.map_err(|e| format!("ZIP書き込み失敗: {e:?}"))?;
+	}
+	let cursor = zip
+		.finish()
+		.map_err(|e| format!("ZIP書き込み失敗: {e:?}"))?;
+	Ok(cursor.into_inner())
 }
 
 #[cfg(test)]
 mod tests {
-	use super::gltf_binary;
+	use super::*;
 	use crate::openapi::*;
 	use crate::shape::eval_shape;
 	use chijin::Shape;
@@ -357,12 +1174,493 @@ mod tests {
 		println!("生成完了: {} ({} bytes)", TEST_BREP_PATH, brep_buf.len());
 	}
 
+	/// 三角形 1 枚 = 1 面のメッシュを組み立てる
+	fn triangles_mesh(triangles: &[[[f64; 3]; 3]]) -> chijin::Mesh {
+		let mut mesh = chijin::Mesh {
+			vertices: Vec::new(),
+			uvs: Vec::new(),
+			normals: Vec::new(),
+			indices: Vec::new(),
+			face_ids: Vec::new(),
+		};
+		for (i, tri) in triangles.iter().enumerate() {
+			for p in tri {
+				mesh.indices.push(mesh.vertices.len());
+				mesh.vertices.push(glam::DVec3::from_array(*p));
+				mesh.uvs.push(glam::DVec2::ZERO);
+				mesh.normals.push(glam::DVec3::Z);
+			}
+			mesh.face_ids.push(100 + i as u64);
+		}
+		mesh
+	}
+
+	#[test]
+	fn split_solids_groups_connected_faces() {
+		// 0 と 1 は辺を共有、2 は離れている
+		let mesh = triangles_mesh(&[
+			[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+			[[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+			[[5.0, 0.0, 0.0], [6.0, 0.0, 0.0], [5.0, 1.0, 0.0]],
+		]);
+		assert_eq!(split_solids(&mesh), vec![vec![0, 1], vec![2]]);
+	}
+
+	#[test]
+	fn touching_solids_follow_topology() {
+		// 面 100 と 101 は頂点を共有して接しているが、トポロジー上は別のソリッド。面 102 のソリッドは三角形がない
+		let mesh = triangles_mesh(&[
+			[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+			[[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+		]);
+		assert_eq!(split_solids(&mesh), vec![vec![0, 1]]);
+		let nodes = vec![PartNode::Group {
+			name: Some("Assy".to_string()),
+			children: flat_parts(3),
+		}];
+		let faces = [(100, 0), (101, 1), (102, 2)];
+		let (solids, nodes) = assign_triangles(&mesh, nodes, &faces).unwrap();
+		assert_eq!(solids, vec![vec![0], vec![1]]);
+		assert_eq!(
+			nodes,
+			vec![PartNode::Group {
+				name: Some("Assy".to_string()),
+				children: flat_parts(2),
+			}]
+		);
+
+		let groups = solids
+			.iter()
+			.map(|triangles| color_groups(&Shape::empty(), &mesh, triangles))
+			.collect();
+		let plan = GlbPlan::from_parts(&Shape::empty(), mesh, groups, nodes, None, None).unwrap();
+		let doc = validate_glb(&glb_bytes(&plan));
+		let names: Vec<&str> = doc["nodes"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|n| n["name"].as_str().unwrap())
+			.collect();
+		assert_eq!(names, ["shape", "Assy", "solid_0", "solid_1"]);
+		assert_eq!(doc["nodes"][1]["children"], serde_json::json!([2, 3]));
+	}
+
+	#[test]
+	fn repeated_placements_keep_their_own_nodes() {
+		// 面 100・101 の部品を 2 か所に配置したアセンブリ。面 (TShape) は配置どうしで共有される
+		let mut mesh = triangles_mesh(&[
+			[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+			[[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+			[[5.0, 0.0, 0.0], [6.0, 0.0, 0.0], [5.0, 1.0, 0.0]],
+			[[6.0, 0.0, 0.0], [6.0, 1.0, 0.0], [5.0, 1.0, 0.0]],
+		]);
+		mesh.face_ids = vec![100, 101, 100, 101];
+		let nodes = vec![PartNode::Group {
+			name: Some("Assy".to_string()),
+			children: flat_parts(2),
+		}];
+		let faces = [(100, 0), (101, 0), (100, 1), (101, 1)];
+		let (solids, parts) = assign_triangles(&mesh, nodes.clone(), &faces).unwrap();
+		assert_eq!(solids, vec![vec![0, 1], vec![2, 3]]);
+		assert_eq!(parts, nodes);
+
+		// 1 面の部品を続けて配置すると、同じ面の三角形の連なりを配置の数で等分する
+		mesh.face_ids = vec![100; 4];
+		let faces = [(100, 0), (100, 1)];
+		let (solids, parts) = assign_triangles(&mesh, nodes.clone(), &faces).unwrap();
+		assert_eq!(solids, vec![vec![0, 1], vec![2, 3]]);
+		assert_eq!(parts, nodes);
+	}
+
+	#[test]
+	fn stl_binary_has_50_bytes_per_triangle() {
+		let mesh = triangles_mesh(&[
+			[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+			[[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+		]);
+		let stl = write_stl_binary(&mesh);
+		assert_eq!(stl.len(), 84 + 2 * 50);
+		assert_eq!(u32::from_le_bytes(stl[80..84].try_into().unwrap()), 2);
+		assert!(!stl.starts_with(b"solid"));
+		// 1枚目の法線は +Z
+		let nz = f32::from_le_bytes(stl[92..96].try_into().unwrap());
+		assert_eq!(nz, 1.0);
+	}
+
+	#[test]
+	fn stl_ascii_lists_every_facet() {
+		let mesh = triangles_mesh(&[[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]]);
+		let stl = String::from_utf8(write_stl_ascii(&mesh)).unwrap();
+		assert!(stl.starts_with("solid "));
+		assert_eq!(stl.matches("facet normal 0 0 1").count(), 1);
+		assert_eq!(stl.matches("vertex ").count(), 3);
+		assert!(stl.trim_end().ends_with("endsolid lambda360"));
+	}
+
+	#[test]
+	fn obj_uses_one_material_per_color_group() {
+		let mesh = triangles_mesh(&[
+			[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+			[[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+		]);
+		let red = chijin::Rgb {
+			r: 1.0,
+			g: 0.0,
+			b: 0.0,
+		};
+		let groups = vec![(Some(red), vec![0, 1, 2]), (None, vec![3, 4, 5])];
+		let (obj, mtl) = write_obj(&mesh, &groups, "model.mtl");
+		let (obj, mtl) = (
+			String::from_utf8(obj).unwrap(),
+			String::from_utf8(mtl).unwrap(),
+		);
+		assert!(obj.contains("mtllib model.mtl"));
+		assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 6);
+		assert!(obj.contains("usemtl color_0\nf 1//1 2//2 3//3\n"));
+		assert!(obj.contains("usemtl color_1\nf 4//4 5//5 6//6\n"));
+		assert!(mtl.contains("newmtl color_0\nKa 0 0 0\nKd 1 0 0"));
+		assert!(mtl.contains("newmtl color_1\nKa 0 0 0\nKd 0.8 0.8 0.8"));
+	}
+
+	#[test]
+	fn ply_header_matches_payload() {
+		let mesh = triangles_mesh(&[[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]]);
+		let ply = write_ply(&mesh, &[DEFAULT_RGB; 3]);
+		let header_end = b"end_header\n";
+		let pos = ply
+			.windows(header_end.len())
+			.position(|w| w == header_end)
+			.unwrap() + header_end.len();
+		let header = std::str::from_utf8(&ply[..pos]).unwrap();
+		assert!(header.contains("element vertex 3\n"));
+		assert!(header.contains("element face 1\n"));
+		// 頂点: 3 * (12 + 3) バイト、面: 1 + 3 * 4 バイト
+		assert_eq!(ply.len() - pos, 3 * 15 + 13);
+	}
+
+	#[test]
+	fn three_mf_welds_shared_vertices() {
+		let mesh = triangles_mesh(&[
+			[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+			[[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+		]);
+		let (vertices, welded) = weld_triangles(&mesh, &[0, 1]);
+		assert_eq!(vertices.len(), 4);
+		assert_eq!(welded, vec![(0, [0, 1, 2]), (1, [1, 3, 2])]);
+	}
+
+	#[test]
+	fn glb_embeds_annotations_and_metadata() {
+		let mesh = triangles_mesh(&[[[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 3.0, 0.0]]]);
+		let shape = Shape::empty();
+		let solids = vec![color_groups(&shape, &mesh, &[0])];
+		let annotations = vec![
+			Box::new(Annotation::Point(PointAnnotation {
+				label: "穴".to_string(),
+				position: vec![1.0, 1.0, 0.0],
+			})),
+			Box::new(Annotation::Distance(DistanceAnnotation {
+				label: "幅".to_string(),
+				start: vec![0.0, 0.0, 0.0],
+				end: vec![2.0, 0.0, 0.0],
+			})),
+		];
+		let info = GlbInfo {
+			label: Some("ブラケット"),
+			annotations: &annotations,
+			shape_hash: "abc".to_string(),
+		};
+		let plan = GlbPlan::flat(&shape, mesh, solids, None, Some(&info)).unwrap();
+		let mut glb = Vec::new();
+		plan.write_to(&mut glb).unwrap();
+		assert_eq!(glb.len(), plan.byte_len());
+		let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+		let root: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+
+		assert_eq!(root["extras"]["label"], "ブラケット");
+		assert_eq!(root["extras"]["units"], "mm");
+		assert_eq!(root["extras"]["shapeHash"], "abc");
+		assert_eq!(
+			root["extras"]["boundingBox"]["max"],
+			serde_json::json!([2.0, 3.0, 0.0])
+		);
+		let names: Vec<&str> = root["nodes"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|n| n["name"].as_str().unwrap())
+			.collect();
+		assert_eq!(names, ["shape", "solid_0", "穴", "幅", "start", "end"]);
+		assert_eq!(root["nodes"][0]["children"], serde_json::json!([1, 2, 3]));
+		assert_eq!(
+			root["nodes"][2]["translation"],
+			serde_json::json!([1.0, 1.0, 0.0])
+		);
+		assert_eq!(root["nodes"][3]["extras"]["distance"], 2.0);
+	}
+
+	/// GLB の構造検証。違反があれば panic し、解析した JSON チャンクを返す。
+	///
+	/// チャンクの配置と 4 バイト境界、bufferView / accessor の範囲、
+	/// インデックスの範囲、POSITION の min/max、法線の長さ、マテリアル・ノード・メッシュの参照を確かめる。
+	fn validate_glb(glb: &[u8]) -> serde_json::Value {
+		let u32_at = |o: usize| u32::from_le_bytes(glb[o..o + 4].try_into().unwrap()) as usize;
+		let as_usize = |v: &serde_json::Value| v.as_u64().expect("非負整数ではありません") as usize;
+
+		// ヘッダとチャンク
+		assert_eq!(&glb[0..4], b"glTF", "magic");
+		assert_eq!(u32_at(4), 2, "version");
+		assert_eq!(u32_at(8), glb.len(), "ヘッダの全体長");
+		let json_len = u32_at(12);
+		assert_eq!(&glb[16..20], b"JSON");
+		assert_eq!(json_len % 4, 0, "JSON チャンクが 4 バイト境界にありません");
+		let doc: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+		let bin_start = 20 + json_len;
+		let bin_len = u32_at(bin_start);
+		assert_eq!(&glb[bin_start + 4..bin_start + 8], b"BIN\0");
+		assert_eq!(bin_len % 4, 0, "BIN チャンクが 4 バイト境界にありません");
+		assert_eq!(
+			bin_start + 8 + bin_len,
+			glb.len(),
+			"BIN チャンクの後に余りがあります"
+		);
+		let bin = &glb[bin_start + 8..];
+		let buffer_len = as_usize(&doc["buffers"][0]["byteLength"]);
+		assert!(buffer_len <= bin_len && bin_len - buffer_len < 4);
+
+		// bufferView: バッファ内に収まり、4 バイト境界から始まる
+		let views = doc["bufferViews"].as_array().unwrap();
+		for view in views {
+			let offset = as_usize(&view["byteOffset"]);
+			assert!(offset + as_usize(&view["byteLength"]) <= buffer_len);
+			assert_eq!(offset % 4, 0);
+		}
+
+		// accessor: bufferView 内に収まり、要素が型のサイズに揃っている
+		let accessors = doc["accessors"].as_array().unwrap();
+		let component_size = |a: &serde_json::Value| match a["componentType"].as_u64().unwrap() {
+			5123 => 2,
+			5125 | 5126 => 4,
+			other => panic!("想定外の componentType {other}"),
+		};
+		let components = |a: &serde_json::Value| match a["type"].as_str().unwrap() {
+			"SCALAR" => 1,
+			"VEC3" => 3,
+			other => panic!("想定外の type {other}"),
+		};
+		let start_of = |a: &serde_json::Value| {
+			let view = &views[as_usize(&a["bufferView"])];
+			as_usize(&view["byteOffset"]) + as_usize(&a["byteOffset"])
+		};
+		for accessor in accessors {
+			let view = &views[as_usize(&accessor["bufferView"])];
+			let size = component_size(accessor);
+			assert!(
+				as_usize(&accessor["byteOffset"])
+					+ as_usize(&accessor["count"]) * size * components(accessor)
+					<= as_usize(&view["byteLength"])
+			);
+			assert_eq!(start_of(accessor) % size, 0);
+		}
+		let read_scalar = |a: &serde_json::Value, i: usize| {
+			let o = start_of(a) + i * component_size(a);
+			match component_size(a) {
+				2 => u16::from_le_bytes(bin[o..o + 2].try_into().unwrap()) as usize,
+				_ => u32::from_le_bytes(bin[o..o + 4].try_into().unwrap()) as usize,
+			}
+		};
+		let read_vec3 = |a: &serde_json::Value, i: usize| {
+			let o = start_of(a) + i * 12;
+			[0, 4, 8].map(|k| f32::from_le_bytes(bin[o + k..o + k + 4].try_into().unwrap()))
+		};
+
+		// プリミティブ: 頂点・法線・インデックス・マテリアル
+		let materials = doc["materials"].as_array().unwrap();
+		let meshes = doc["meshes"].as_array().unwrap();
+		for mesh in meshes {
+			for primitive in mesh["primitives"].as_array().unwrap() {
+				let position = &accessors[as_usize(&primitive["attributes"]["POSITION"])];
+				let vertex_count = as_usize(&position["count"]);
+				let mut min = [f32::INFINITY; 3];
+				let mut max = [f32::NEG_INFINITY; 3];
+				for i in 0..vertex_count {
+					let p = read_vec3(position, i);
+					for k in 0..3 {
+						min[k] = min[k].min(p[k]);
+						max[k] = max[k].max(p[k]);
+					}
+				}
+				for k in 0..3 {
+					assert_eq!(
+						position["min"][k].as_f64().unwrap() as f32,
+						min[k],
+						"POSITION.min"
+					);
+					assert_eq!(
+						position["max"][k].as_f64().unwrap() as f32,
+						max[k],
+						"POSITION.max"
+					);
+				}
+				if let Some(normal) = primitive["attributes"].get("NORMAL") {
+					let normal = &accessors[as_usize(normal)];
+					assert_eq!(as_usize(&normal["count"]), vertex_count);
+					for i in 0..vertex_count {
+						let len = glam::Vec3::from_array(read_vec3(normal, i)).length();
+						assert!(len == 0.0 || (len - 1.0).abs() < 1e-4, "法線の長さ {len}");
+					}
+				}
+				let indices = &accessors[as_usize(&primitive["indices"])];
+				let count = as_usize(&indices["count"]);
+				assert_eq!(count % 3, 0, "三角形のインデックス数");
+				for i in 0..count {
+					assert!(
+						read_scalar(indices, i) < vertex_count,
+						"インデックスが頂点数を超えます"
+					);
+				}
+				assert!(as_usize(&primitive["material"]) < materials.len());
+			}
+		}
+		let unlit = materials
+			.iter()
+			.any(|m| m["extensions"].get("KHR_materials_unlit").is_some());
+		let used = doc["extensionsUsed"]
+			.as_array()
+			.is_some_and(|e| e.contains(&serde_json::json!("KHR_materials_unlit")));
+		assert_eq!(
+			unlit, used,
+			"extensionsUsed と unlit マテリアルが一致しません"
+		);
+
+		// ノード・シーン・エッジ
+		let nodes = doc["nodes"].as_array().unwrap();
+		for node in nodes {
+			if let Some(mesh) = node.get("mesh") {
+				assert!(as_usize(mesh) < meshes.len());
+			}
+			for child in node["children"].as_array().into_iter().flatten() {
+				assert!(as_usize(child) < nodes.len());
+			}
+		}
+		let scene = &doc["scenes"][as_usize(&doc["scene"])];
+		for node in scene["nodes"].as_array().unwrap() {
+			assert!(as_usize(node) < nodes.len());
+		}
+		if let Some(edge) = doc["extras"].get("edgeAccessor") {
+			let edge = &accessors[as_usize(edge)];
+			assert_eq!(edge["type"], "VEC3");
+			assert_eq!(as_usize(&edge["count"]) % 2, 0, "エッジは線分の端点の組");
+		}
+		doc
+	}
+
+	fn glb_bytes(plan: &GlbPlan) -> Vec<u8> {
+		let mut glb = Vec::new();
+		plan.write_to(&mut glb).unwrap();
+		assert_eq!(glb.len(), plan.byte_len());
+		glb
+	}
+
+	/// 三角形 2 枚 + 離れた三角形 1 枚（2 ソリッド）で、面 101 だけ赤
+	fn colored_plan(material: Option<&MaterialPreset>, info: Option<&GlbInfo>) -> GlbPlan {
+		let mesh = triangles_mesh(&[
+			[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+			[[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+			[[5.0, 5.0, 5.0], [6.0, 5.0, 5.0], [5.0, 6.0, 5.0]],
+		]);
+		let mut shape = Shape::empty();
+		shape.colormap.insert(
+			chijin::TShapeId(101),
+			chijin::Rgb {
+				r: 1.0,
+				g: 0.0,
+				b: 0.0,
+			},
+		);
+		let solids = split_solids(&mesh)
+			.iter()
+			.map(|triangles| color_groups(&shape, &mesh, triangles))
+			.collect();
+		GlbPlan::flat(&shape, mesh, solids, material, info).unwrap()
+	}
+
+	#[test]
+	fn glb_color_groups_are_valid() {
+		let doc = validate_glb(&glb_bytes(&colored_plan(None, None)));
+		assert_eq!(doc["meshes"].as_array().unwrap().len(), 2);
+		assert_eq!(doc["materials"].as_array().unwrap().len(), 2);
+	}
+
+	#[test]
+	fn glb_material_preset_is_valid() {
+		let preset = material_preset("stainless").unwrap();
+		let doc = validate_glb(&glb_bytes(&colored_plan(Some(preset), None)));
+		assert_eq!(doc["materials"].as_array().unwrap().len(), 1);
+		assert_eq!(
+			doc["materials"][0]["pbrMetallicRoughness"]["metallicFactor"],
+			1.0
+		);
+		assert!(doc["meshes"][0]["primitives"][0]["attributes"]["NORMAL"].is_u64());
+	}
+
+	#[test]
+	fn glb_with_annotations_is_valid() {
+		let annotations = vec![Box::new(Annotation::Point(PointAnnotation {
+			label: "p".to_string(),
+			position: vec![0.0, 0.0, 1.0],
+		}))];
+		let info = GlbInfo {
+			label: None,
+			annotations: &annotations,
+			shape_hash: "hash".to_string(),
+		};
+		validate_glb(&glb_bytes(&colored_plan(None, Some(&info))));
+	}
+
+	#[test]
+	fn glb_wide_indices_are_valid() {
+		// 65535 を超える頂点数で u32 インデックスになる
+		let triangles: Vec<[[f64; 3]; 3]> = (0..22_000)
+			.map(|i| {
+				let x = i as f64;
+				[[x, 0.0, 0.0], [x + 1.0, 0.0, 0.0], [x, 1.0, 0.0]]
+			})
+			.collect();
+		let mesh = triangles_mesh(&triangles);
+		let shape = Shape::empty();
+		let solids = vec![color_groups(
+			&shape,
+			&mesh,
+			&(0..triangles.len()).collect::<Vec<_>>(),
+		)];
+		let plan = GlbPlan::flat(&shape, mesh, solids, None, None).unwrap();
+		let doc = validate_glb(&glb_bytes(&plan));
+		let indices = as_index(&doc["meshes"][0]["primitives"][0]["indices"]);
+		assert_eq!(doc["accessors"][indices]["componentType"], 5125);
+	}
+
+	#[test]
+	fn glb_from_step_shape_is_valid() {
+		let plan = GlbPlan::new(&load_test_shape(), None, None, &[]).unwrap();
+		let doc = validate_glb(&glb_bytes(&plan));
+		assert!(doc["extras"]["edgeAccessor"].is_u64());
+	}
+
+	fn as_index(v: &serde_json::Value) -> usize {
+		v.as_u64().unwrap() as usize
+	}
+
 	#[test]
 	#[ignore]
 	fn generate_glb() {
 		let shape = eval_shape(&step_node(TEST_KEY), &mut shapes_map(TEST_KEY))
 			.expect("eval_shape() に失敗しました");
-		let glb = gltf_binary(&shape).expect("GLBの生成に失敗しました");
+		let mut glb = Vec::new();
+		GlbPlan::new(&shape, None, None, &[])
+			.expect("GLBの生成に失敗しました")
+			.write_to(&mut glb)
+			.expect("GLBの書き込みに失敗しました");
 		std::fs::write("examples/colored_box.glb", &glb)
 			.expect("GLBファイルの書き込みに失敗しました");
 		println!("生成完了: examples/colored_box.glb ({} bytes)", glb.len());
@@ -0,0 +1,561 @@
+//! 三面図（正面図・平面図・右側面図）の SVG 出力。
+//!
+//! chijin は隠線処理 (HLR) を公開していないため、メッシュを投影方向ごとに
+//! 深度バッファへラスタライズし、エッジの離散化点の可視判定で隠線を除去する。
+
+use super::{MESH_TOLERANCE, bounding_box, position_key, shape_mesh};
+use crate::openapi::Annotation;
+use chijin::Shape;
+use glam::{DVec2, DVec3};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// 用紙サイズ（A4 横, mm）
+const SHEET_W: f64 = 297.0;
+const SHEET_H: f64 = 210.0;
+/// 外枠の余白
+const MARGIN: f64 = 10.0;
+/// 表題欄の幅と高さ
+const TITLE_W: f64 = 120.0;
+const TITLE_H: f64 = 16.0;
+/// 投影図どうしの間隔。寸法線もこの中に描く
+const VIEW_GAP: f64 = 25.0;
+/// 外形寸法線を投影図から離す距離
+const DIM_OFFSET: f64 = 8.0;
+/// 深度バッファの長辺のセル数
+const DEPTH_RESOLUTION: usize = 1024;
+/// 選択可能な尺度（用紙上の長さ / 実物の長さ）、大きい順
+const SCALES: [f64; 15] = [
+	10.0, 5.0, 2.0, 1.0, 0.5, 0.2, 0.1, 0.05, 0.02, 0.01, 0.005, 0.002, 0.001, 0.0005, 0.0002,
+];
+
+/// 正投影の視点。u / v は画面の右 / 上方向、toward は視点側を向く単位ベクトル。
+#[derive(Clone, Copy)]
+struct View {
+	name: &'static str,
+	u: DVec3,
+	v: DVec3,
+	toward: DVec3,
+}
+
+impl View {
+	fn project(&self, p: DVec3) -> DVec2 {
+		DVec2::new(p.dot(self.u), p.dot(self.v))
+	}
+
+	/// 視点に近いほど大きい奥行き値
+	fn depth(&self, p: DVec3) -> f64 {
+		p.dot(self.toward)
+	}
+}
+
+/// 上方向軸に応じた第三角法の 3 視点（正面図・平面図・右側面図）
+fn views(axis_up: Option<&str>) -> [View; 3] {
+	match axis_up {
+		Some("Y") => [
+			View {
+				name: "front",
+				u: DVec3::X,
+				v: DVec3::Y,
+				toward: DVec3::Z,
+			},
+			View {
+				name: "top",
+				u: DVec3::X,
+				v: DVec3::NEG_Z,
+				toward: DVec3::Y,
+			},
+			View {
+				name: "right",
+				u: DVec3::NEG_Z,
+				v: DVec3::Y,
+				toward: DVec3::X,
+			},
+		],
+		_ => [
+			View {
+				name: "front",
+				u: DVec3::X,
+				v: DVec3::Z,
+				toward: DVec3::NEG_Y,
+			},
+			View {
+				name: "top",
+				u: DVec3::X,
+				v: DVec3::Y,
+				toward: DVec3::Z,
+			},
+			View {
+				name: "right",
+				u: DVec3::Y,
+				v: DVec3::Z,
+				toward: DVec3::X,
+			},
+		],
+	}
+}
+
+/// ShapeOutput の形状から三面図の SVG を生成する。
+///
+/// 外形寸法はバウンディングボックスから、追加の寸法線は DistanceAnnotation から描く。
+pub fn drawing_svg(
+	shape: &Shape,
+	annotations: &[Box<Annotation>],
+	label: Option<&str>,
+	axis_up: Option<&str>,
+) -> Result<Vec<u8>, String> {
+	let mesh = shape_mesh(shape)?;
+	let (lo, hi) = bounding_box(&mesh).ok_or("形状が空です")?;
+	let corners: Vec<DVec3> = (0..8)
+		.map(|i| {
+			DVec3::new(
+				if i & 1 == 0 { lo.x } else { hi.x },
+				if i & 2 == 0 { lo.y } else { hi.y },
+				if i & 4 == 0 { lo.z } else { hi.z },
+			)
+		})
+		.collect();
+	let edges: Vec<Vec<DVec3>> = shape
+		.edges()
+		.map(|edge| edge.approximation_segments(MESH_TOLERANCE).collect())
+		.collect();
+	let views = views(axis_up).map(|view| {
+		let (min, max) = corners.iter().fold(
+			(DVec2::splat(f64::INFINITY), DVec2::splat(f64::NEG_INFINITY)),
+			|(a, b), &p| (a.min(view.project(p)), b.max(view.project(p))),
+		);
+		(view, min, max)
+	});
+
+	// 尺度と配置: 正面図を左下、その上に平面図、右に右側面図
+	let size = |i: usize| views[i].2 - views[i].1;
+	let (front_size, top_size, right_size) = (size(0), size(1), size(2));
+	let avail = DVec2::new(
+		SHEET_W - 2.0 * (MARGIN + VIEW_GAP),
+		SHEET_H - 2.0 * (MARGIN + VIEW_GAP) - TITLE_H,
+	);
+	let total = DVec2::new(front_size.x + right_size.x, front_size.y + top_size.y);
+	let scale = choose_scale(((avail.x - VIEW_GAP) / total.x).min((avail.y - VIEW_GAP) / total.y));
+	let pad = (avail - (total * scale + VIEW_GAP)).max(DVec2::ZERO) / 2.0;
+	let left = MARGIN + VIEW_GAP + pad.x;
+	let bottom = SHEET_H - MARGIN - TITLE_H - VIEW_GAP - pad.y;
+	let origins = [
+		DVec2::new(left, bottom),
+		DVec2::new(left, bottom - front_size.y * scale - VIEW_GAP),
+		DVec2::new(left + front_size.x * scale + VIEW_GAP, bottom),
+	];
+	let to_sheet = |i: usize, q: DVec2| {
+		let d = (q - views[i].1) * scale;
+		DVec2::new(origins[i].x + d.x, origins[i].y - d.y)
+	};
+
+	let mut svg = String::new();
+	let _ = write!(
+		svg,
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{SHEET_W}mm" height="{SHEET_H}mm" viewBox="0 0 {SHEET_W} {SHEET_H}" font-family="sans-serif">
+<defs><marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerUnits="userSpaceOnUse" markerWidth="3" markerHeight="3" orient="auto-start-reverse"><path d="M0,2 L10,5 L0,8 z"/></marker></defs>
+<rect x="{MARGIN}" y="{MARGIN}" width="{}" height="{}" fill="none" stroke="black" stroke-width="0.5"/>
+"#,
+		SHEET_W - 2.0 * MARGIN,
+		SHEET_H - 2.0 * MARGIN,
+	);
+
+	// 外形線（曲面の輪郭線を含む）
+	for (i, (view, min, max)) in views.iter().enumerate() {
+		let buffer = DepthBuffer::new(view, &mesh, *min, *max);
+		let lines = edges
+			.iter()
+			.map(Vec::as_slice)
+			.chain(silhouettes(view, &mesh).iter().map(|s| s.as_slice()))
+			.flat_map(|points| buffer.visible_runs(view, points))
+			.collect::<Vec<_>>();
+		let _ = write!(
+			svg,
+			r#"<path id="{}" fill="none" stroke="black" stroke-width="0.35" stroke-linecap="round" d=""#,
+			view.name
+		);
+		for run in &lines {
+			for (k, q) in run.iter().enumerate() {
+				let p = to_sheet(i, *q);
+				let _ = write!(
+					svg,
+					"{}{:.3},{:.3}",
+					if k == 0 { "M" } else { " L" },
+					p.x,
+					p.y
+				);
+			}
+		}
+		svg.push_str("\"/>\n");
+	}
+
+	// 外形寸法: 正面図の幅と高さ、平面図の奥行き
+	let (fmin, fmax) = (views[0].1, views[0].2);
+	dimension(
+		&mut svg,
+		to_sheet(0, fmin),
+		to_sheet(0, DVec2::new(fmax.x, fmin.y)),
+		DVec2::new(0.0, DIM_OFFSET),
+		&format_mm(front_size.x),
+	);
+	dimension(
+		&mut svg,
+		to_sheet(0, fmin),
+		to_sheet(0, DVec2::new(fmin.x, fmax.y)),
+		DVec2::new(-DIM_OFFSET, 0.0),
+		&format_mm(front_size.y),
+	);
+	let (tmin, tmax) = (views[1].1, views[1].2);
+	dimension(
+		&mut svg,
+		to_sheet(1, tmin),
+		to_sheet(1, DVec2::new(tmin.x, tmax.y)),
+		DVec2::new(-DIM_OFFSET, 0.0),
+		&format_mm(top_size.y),
+	);
+
+	// DistanceAnnotation: 投影長が最も長くなる投影図に描く
+	for annotation in annotations {
+		let Annotation::Distance(distance) = annotation.as_ref() else {
+			continue;
+		};
+		let (Some(start), Some(end)) = (to_point(&distance.start), to_point(&distance.end)) else {
+			continue;
+		};
+		let i = (0..3)
+			.max_by(|&a, &b| {
+				let len = |k: usize| (views[k].0.project(end) - views[k].0.project(start)).length();
+				len(a).total_cmp(&len(b))
+			})
+			.unwrap_or(0);
+		let (a, b) = (
+			to_sheet(i, views[i].0.project(start)),
+			to_sheet(i, views[i].0.project(end)),
+		);
+		if a.distance(b) < 1e-6 {
+			continue;
+		}
+		// 投影図の中心から離れる向きに寸法線をずらす
+		let center = to_sheet(i, (views[i].1 + views[i].2) / 2.0);
+		let mut normal = (b - a).perp().normalize();
+		if normal.dot((a + b) / 2.0 - center) < 0.0 {
+			normal = -normal;
+		}
+		let value = format_mm(start.distance(end));
+		let text = if distance.label.is_empty() {
+			value
+		} else {
+			format!("{} {value}", distance.label)
+		};
+		dimension(&mut svg, a, b, normal * DIM_OFFSET / 2.0, &text);
+	}
+
+	// 表題欄
+	let (tx, ty) = (SHEET_W - MARGIN - TITLE_W, SHEET_H - MARGIN - TITLE_H);
+	let _ = write!(
+		svg,
+		r#"<g stroke="black" stroke-width="0.35" fill="none"><rect x="{tx}" y="{ty}" width="{TITLE_W}" height="{TITLE_H}"/><line x1="{tx}" y1="{}" x2="{}" y2="{}"/></g>
+<text x="{}" y="{}" font-size="4">{}</text>
+<text x="{}" y="{}" font-size="3">尺度 {}　単位 mm　第三角法</text>
+</svg>
+"#,
+		ty + TITLE_H / 2.0,
+		tx + TITLE_W,
+		ty + TITLE_H / 2.0,
+		tx + 2.0,
+		ty + 5.5,
+		xml_escape(label.unwrap_or("")),
+		tx + 2.0,
+		ty + TITLE_H - 2.5,
+		scale_label(scale),
+	);
+	Ok(svg.into_bytes())
+}
+
+/// 図が収まる最大の尺度以下で、最も大きい標準尺度を選ぶ
+fn choose_scale(fit: f64) -> f64 {
+	SCALES
+		.iter()
+		.copied()
+		.find(|&s| s <= fit)
+		.unwrap_or(SCALES[SCALES.len() - 1])
+}
+
+/// 尺度の表記（1:2, 5:1 など）
+fn scale_label(scale: f64) -> String {
+	if scale >= 1.0 {
+		format!("{}:1", scale.round())
+	} else {
+		format!("1:{}", (1.0 / scale).round())
+	}
+}
+
+/// 寸法値の表記。小数点以下 2 桁までで末尾の 0 を省く
+fn format_mm(value: f64) -> String {
+	let s = format!("{value:.2}");
+	s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn xml_escape(s: &str) -> String {
+	s.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
+
+fn to_point(v: &[f64]) -> Option<DVec3> {
+	match v {
+		[x, y, z] => Some(DVec3::new(*x, *y, *z)),
+		_ => None,
+	}
+}
+
+/// 用紙座標の 2 点 a, b の寸法線を offset だけずらして描く（補助線・矢印・寸法値）
+fn dimension(svg: &mut String, a: DVec2, b: DVec2, offset: DVec2, text: &str) {
+	let (da, db) = (a + offset, b + offset);
+	let overshoot = offset.normalize_or_zero() * 1.5;
+	let mid = (da + db) / 2.0 + overshoot;
+	let dir = db - da;
+	let mut angle = dir.y.atan2(dir.x).to_degrees();
+	if angle > 90.0 {
+		angle -= 180.0;
+	} else if angle <= -90.0 {
+		angle += 180.0;
+	}
+	let _ = writeln!(
+		svg,
+		r#"<g stroke="black" stroke-width="0.18" fill="none"><line x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}"/><line x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}"/><line x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}" marker-start="url(#arrow)" marker-end="url(#arrow)"/></g><text x="{:.3}" y="{:.3}" font-size="3.5" text-anchor="middle" transform="rotate({angle:.3} {:.3} {:.3})">{}</text>"#,
+		a.x,
+		a.y,
+		da.x + overshoot.x,
+		da.y + overshoot.y,
+		b.x,
+		b.y,
+		db.x + overshoot.x,
+		db.y + overshoot.y,
+		da.x,
+		da.y,
+		db.x,
+		db.y,
+		mid.x,
+		mid.y,
+		mid.x,
+		mid.y,
+		xml_escape(text),
+	);
+}
+
+/// 同一面内の三角形の辺のうち、視線に対して表裏が切り替わるもの（曲面の輪郭線）
+fn silhouettes(view: &View, mesh: &chijin::Mesh) -> Vec<[DVec3; 2]> {
+	type Key = (i64, i64, i64);
+	let mut open: HashMap<(Key, Key), (f64, u64)> = HashMap::new();
+	let mut result = Vec::new();
+	for (t, tri) in mesh.indices.chunks_exact(3).enumerate() {
+		let normal = mesh.normals[tri[0]] + mesh.normals[tri[1]] + mesh.normals[tri[2]];
+		let facing = normal.dot(view.toward);
+		let face_id = mesh.face_ids[t];
+		for k in 0..3 {
+			let (a, b) = (mesh.vertices[tri[k]], mesh.vertices[tri[(k + 1) % 3]]);
+			let (ka, kb) = (position_key(a), position_key(b));
+			let key = if ka < kb { (ka, kb) } else { (kb, ka) };
+			match open.remove(&key) {
+				Some((other, other_face)) => {
+					if other_face == face_id && other * facing < 0.0 {
+						result.push([a, b]);
+					}
+				}
+				None => {
+					open.insert(key, (facing, face_id));
+				}
+			}
+		}
+	}
+	result
+}
+
+/// 投影面上の格子に、各セルで最も視点に近い奥行きを記録したもの
+struct DepthBuffer {
+	origin: DVec2,
+	cell: f64,
+	width: usize,
+	height: usize,
+	depth: Vec<f64>,
+}
+
+impl DepthBuffer {
+	fn new(view: &View, mesh: &chijin::Mesh, min: DVec2, max: DVec2) -> Self {
+		let cell = (max - min).max_element().max(1e-9) / DEPTH_RESOLUTION as f64;
+		let width = ((max.x - min.x) / cell).ceil() as usize + 1;
+		let height = ((max.y - min.y) / cell).ceil() as usize + 1;
+		let mut depth = vec![f64::NEG_INFINITY; width * height];
+		for tri in mesh.indices.chunks_exact(3) {
+			let p = [0, 1, 2].map(|k| mesh.vertices[tri[k]]);
+			let q = p.map(|x| (view.project(x) - min) / cell);
+			let d = p.map(|x| view.depth(x));
+			let area = (q[1] - q[0]).perp_dot(q[2] - q[0]);
+			if area.abs() < 1e-12 {
+				continue;
+			}
+			let lo = q[0].min(q[1]).min(q[2]).max(DVec2::ZERO);
+			let hi = q[0].max(q[1]).max(q[2]);
+			let (x1, y1) = (
+				(hi.x.ceil() as usize).min(width - 1),
+				(hi.y.ceil() as usize).min(height - 1),
+			);
+			for iy in lo.y.floor() as usize..=y1 {
+				for ix in lo.x.floor() as usize..=x1 {
+					let c = DVec2::new(ix as f64 + 0.5, iy as f64 + 0.5);
+					let w0 = (q[2] - q[1]).perp_dot(c - q[1]) / area;
+					let w1 = (q[0] - q[2]).perp_dot(c - q[2]) / area;
+					let w2 = 1.0 - w0 - w1;
+					if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+						continue;
+					}
+					let z = w0 * d[0] + w1 * d[1] + w2 * d[2];
+					let slot = &mut depth[iy * width + ix];
+					if z > *slot {
+						*slot = z;
+					}
+				}
+			}
+		}
+		Self {
+			origin: min,
+			cell,
+			width,
+			height,
+			depth,
+		}
+	}
+
+	/// 投影座標 q・奥行き depth の点が見えるか。
+	/// ラスタライズ誤差で輪郭付近を隠線と誤判定しないよう、周囲 3x3 セルの最も奥の値と比べる。
+	fn visible(&self, q: DVec2, depth: f64) -> bool {
+		let c = (q - self.origin) / self.cell;
+		let (cx, cy) = (c.x.floor() as isize, c.y.floor() as isize);
+		let mut farthest = f64::INFINITY;
+		for y in cy - 1..=cy + 1 {
+			for x in cx - 1..=cx + 1 {
+				if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+					farthest = farthest.min(self.depth[y as usize * self.width + x as usize]);
+				}
+			}
+		}
+		depth >= farthest - 2.0 * self.cell
+	}
+
+	/// 3D ポリラインをセル間隔で標本化し、見える区間を 2D ポリラインの列として返す
+	fn visible_runs(&self, view: &View, points: &[DVec3]) -> Vec<Vec<DVec2>> {
+		let mut samples: Vec<DVec3> = points.first().copied().into_iter().collect();
+		for w in points.windows(2) {
+			let len = (view.project(w[1]) - view.project(w[0])).length();
+			let n = ((len / self.cell).ceil() as usize).max(1);
+			samples.extend((1..=n).map(|k| w[0].lerp(w[1], k as f64 / n as f64)));
+		}
+		let mut runs = Vec::new();
+		let mut run: Vec<DVec2> = Vec::new();
+		for p in samples {
+			let q = view.project(p);
+			if self.visible(q, view.depth(p)) {
+				// 同じ直線上の中間点は省く
+				if run.len() >= 2 {
+					let (a, b) = (run[run.len() - 2], run[run.len() - 1]);
+					if (b - a).perp_dot(q - b).abs() < 1e-9 * (1.0 + (b - a).length_squared()) {
+						run.pop();
+					}
+				}
+				run.push(q);
+			} else if run.len() >= 2 {
+				runs.push(std::mem::take(&mut run));
+			} else {
+				run.clear();
+			}
+		}
+		if run.len() >= 2 {
+			runs.push(run);
+		}
+		runs
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// 原点に置いた 10 mm 立方体の 12 三角形（外向き法線）
+	fn cube_mesh() -> chijin::Mesh {
+		let v = |i: usize| {
+			DVec3::new(
+				(i & 1) as f64 * 10.0,
+				((i >> 1) & 1) as f64 * 10.0,
+				((i >> 2) & 1) as f64 * 10.0,
+			)
+		};
+		let quads: [([usize; 4], DVec3); 6] = [
+			([0, 2, 3, 1], DVec3::NEG_Z),
+			([4, 5, 7, 6], DVec3::Z),
+			([0, 1, 5, 4], DVec3::NEG_Y),
+			([2, 6, 7, 3], DVec3::Y),
+			([0, 4, 6, 2], DVec3::NEG_X),
+			([1, 3, 7, 5], DVec3::X),
+		];
+		let mut mesh = chijin::Mesh {
+			vertices: vec![],
+			uvs: vec![],
+			normals: vec![],
+			indices: vec![],
+			face_ids: vec![],
+		};
+		for (f, (quad, normal)) in quads.iter().enumerate() {
+			let base = mesh.vertices.len();
+			for &i in quad {
+				mesh.vertices.push(v(i));
+				mesh.normals.push(*normal);
+				mesh.uvs.push(DVec2::ZERO);
+			}
+			mesh.indices
+				.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+			mesh.face_ids.extend([f as u64, f as u64]);
+		}
+		mesh
+	}
+
+	#[test]
+	fn depth_buffer_hides_back_edges() {
+		let mesh = cube_mesh();
+		let [front, ..] = views(None);
+		let buffer = DepthBuffer::new(&front, &mesh, DVec2::ZERO, DVec2::splat(10.0));
+		// 正面 (y=0) の辺は見え、背面 (y=10) の辺は正面の面に隠れる
+		let visible = buffer.visible_runs(
+			&front,
+			&[DVec3::new(0.0, 0.0, 5.0), DVec3::new(10.0, 0.0, 5.0)],
+		);
+		assert_eq!(visible.len(), 1);
+		assert_eq!(visible[0].len(), 2, "直線は両端点だけにまとめる");
+		let hidden = buffer.visible_runs(
+			&front,
+			&[DVec3::new(2.0, 10.0, 5.0), DVec3::new(8.0, 10.0, 5.0)],
+		);
+		assert!(hidden.is_empty());
+	}
+
+	#[test]
+	fn cube_has_no_silhouette_inside_faces() {
+		let mesh = cube_mesh();
+		for view in views(Some("Y")) {
+			assert!(silhouettes(&view, &mesh).is_empty());
+		}
+	}
+
+	#[test]
+	fn scale_snaps_to_standard_values() {
+		assert_eq!(choose_scale(0.7), 0.5);
+		assert_eq!(choose_scale(1.0), 1.0);
+		assert_eq!(choose_scale(30.0), 10.0);
+		assert_eq!(scale_label(0.5), "1:2");
+		assert_eq!(scale_label(5.0), "5:1");
+		assert_eq!(format_mm(12.50), "12.5");
+		assert_eq!(format_mm(40.0), "40");
+	}
+}
@@ -0,0 +1,183 @@
+//! 断面・平面外形の DXF (R12 ASCII) 出力。レーザー加工などの 2D 輪郭データに使う。
+
+use super::MESH_TOLERANCE;
+use chijin::Shape;
+use glam::{DVec2, DVec3};
+use std::fmt::Write as _;
+
+/// エッジが平面上にあるとみなす距離 (mm)
+const PLANE_TOLERANCE: f64 = 1e-4;
+
+/// 法線 normal の平面の輪郭を DXF として返す。
+///
+/// offset を指定すると平面 `p·normal = offset` での断面を、省略すると法線が normal に一致する
+/// 平面のうち normal 方向に最も遠い面と同じ平面上にあるエッジ（面の外形線と穴）を出力する。
+/// 座標は normal の向きから見た平面座標 (mm)。
+/// 平面や断面が形状に当たらず輪郭がなければ None。
+pub fn dxf(shape: &Shape, normal: DVec3, offset: Option<f64>) -> Result<Option<Vec<u8>>, String> {
+	let polylines: Vec<Vec<DVec3>> = match offset {
+		Some(offset) => {
+			let section = shape
+				.intersect(&Shape::half_space(normal * offset, normal))
+				.map_err(|e| format!("断面計算失敗: {e:?}"))?;
+			section
+				.new_faces
+				.edges()
+				.map(|edge| edge.approximation_segments(MESH_TOLERANCE).collect())
+				.collect()
+		}
+		None => {
+			let Some(level) = shape
+				.faces()
+				.filter(|face| face.normal_at_center().dot(normal) > 1.0 - 1e-6)
+				.map(|face| face.center_of_mass().dot(normal))
+				.max_by(f64::total_cmp)
+			else {
+				return Ok(None);
+			};
+			shape
+				.edges()
+				.map(|edge| {
+					edge.approximation_segments(MESH_TOLERANCE)
+						.collect::<Vec<_>>()
+				})
+				.filter(|points| {
+					!points.is_empty()
+						&& points
+							.iter()
+							.all(|p| (p.dot(normal) - level).abs() < PLANE_TOLERANCE)
+				})
+				.collect()
+		}
+	};
+	if polylines.is_empty() {
+		return Ok(None);
+	}
+	let (u, v) = plane_basis(normal);
+	let polylines: Vec<Vec<DVec2>> = polylines
+		.iter()
+		.map(|points| {
+			points
+				.iter()
+				.map(|p| DVec2::new(p.dot(u), p.dot(v)))
+				.collect()
+		})
+		.collect();
+	Ok(Some(write_dxf(&polylines)))
+}
+
+/// normal の向きから平面を見たときの右方向 u と上方向 v（u × v = normal）
+fn plane_basis(normal: DVec3) -> (DVec3, DVec3) {
+	let up = if normal.z.abs() > 0.9 {
+		DVec3::Y * normal.z.signum()
+	} else {
+		DVec3::Z
+	};
+	let u = up.cross(normal).normalize();
+	(u, normal.cross(u))
+}
+
+/// 2D ポリラインを R12 ASCII DXF に書き出す。2 点なら LINE、それ以外は POLYLINE。
+/// R12 には単位のヘッダ変数 ($INSUNITS) がないので、単位 (mm) は書かない。
+fn write_dxf(polylines: &[Vec<DVec2>]) -> Vec<u8> {
+	let mut out = String::new();
+	let mut pair = |code: u32, value: &str| {
+		let _ = write!(out, "{code}\n{value}\n");
+	};
+	for (code, value) in [
+		(0, "SECTION"),
+		(2, "HEADER"),
+		(9, "$ACADVER"),
+		(1, "AC1009"),
+		(0, "ENDSEC"),
+		(0, "SECTION"),
+		(2, "ENTITIES"),
+	] {
+		pair(code, value);
+	}
+	for points in polylines {
+		let closed = points.len() > 2 && points[0].distance(points[points.len() - 1]) < 1e-9;
+		let points = if closed {
+			&points[..points.len() - 1]
+		} else {
+			&points[..]
+		};
+		match points {
+			[] | [_] => {}
+			[a, b] => {
+				pair(0, "LINE");
+				pair(8, "0");
+				for (code, value) in [
+					(10, a.x),
+					(20, a.y),
+					(30, 0.0),
+					(11, b.x),
+					(21, b.y),
+					(31, 0.0),
+				] {
+					pair(code, &format!("{value:.6}"));
+				}
+			}
+			_ => {
+				pair(0, "POLYLINE");
+				pair(8, "0");
+				pair(66, "1");
+				pair(70, if closed { "1" } else { "0" });
+				for p in points {
+					pair(0, "VERTEX");
+					pair(8, "0");
+					for (code, value) in [(10, p.x), (20, p.y), (30, 0.0)] {
+						pair(code, &format!("{value:.6}"));
+					}
+				}
+				pair(0, "SEQEND");
+				pair(8, "0");
+			}
+		}
+	}
+	pair(0, "ENDSEC");
+	pair(0, "EOF");
+	out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn plane_basis_is_right_handed() {
+		for normal in [
+			DVec3::X,
+			DVec3::NEG_X,
+			DVec3::Y,
+			DVec3::NEG_Y,
+			DVec3::Z,
+			DVec3::NEG_Z,
+		] {
+			let (u, v) = plane_basis(normal);
+			assert!(u.cross(v).distance(normal) < 1e-12, "{normal}");
+		}
+		assert_eq!(plane_basis(DVec3::Z), (DVec3::X, DVec3::Y));
+	}
+
+	#[test]
+	fn dxf_closes_loops_and_writes_lines() {
+		let square = vec![
+			DVec2::new(0.0, 0.0),
+			DVec2::new(10.0, 0.0),
+			DVec2::new(10.0, 10.0),
+			DVec2::new(0.0, 10.0),
+			DVec2::new(0.0, 0.0),
+		];
+		let line = vec![DVec2::new(0.0, 0.0), DVec2::new(5.0, 0.0)];
+		let text = String::from_utf8(write_dxf(&[square, line])).unwrap();
+		let lines: Vec<&str> = text.lines().collect();
+		assert_eq!(lines.iter().filter(|l| **l == "VERTEX").count(), 4);
+		assert_eq!(lines.iter().filter(|l| **l == "LINE").count(), 1);
+		let flag = lines.iter().position(|l| *l == "POLYLINE").unwrap();
+		assert_eq!(&lines[flag + 5..flag + 7], ["70", "1"]);
+		assert!(text.ends_with("0\nEOF\n"));
+		// R12 にないヘッダ変数は書かない
+		assert!(!text.contains("$INSUNITS"));
+	}
+}
@@ -0,0 +1,114 @@
+//! GLB 出力用の仕上げ別 PBR マテリアルプリセット。
+//!
+//! フォームの材質 SelectInput の value（プリセット名または JIS 材料記号）で選択し、
+//! 面の色の代わりに pbrMetallicRoughness の値で全体を描く。
+
+/// 仕上げごとの pbrMetallicRoughness の値（baseColor はリニア RGB）
+pub struct MaterialPreset {
+	pub name: &'static str,
+	aliases: &'static [&'static str],
+	pub base_color: [f32; 3],
+	pub metallic: f32,
+	pub roughness: f32,
+}
+
+const PRESETS: &[MaterialPreset] = &[
+	MaterialPreset {
+		name: "stainless",
+		aliases: &["sus304", "sus316", "sus430", "ステンレス"],
+		base_color: [0.67, 0.66, 0.64],
+		metallic: 1.0,
+		roughness: 0.3,
+	},
+	MaterialPreset {
+		name: "aluminium",
+		aliases: &["aluminum", "a5052", "a6061", "a7075", "アルミ"],
+		base_color: [0.91, 0.92, 0.92],
+		metallic: 1.0,
+		roughness: 0.35,
+	},
+	MaterialPreset {
+		name: "anodized_black",
+		aliases: &["黒アルマイト"],
+		base_color: [0.04, 0.04, 0.05],
+		metallic: 0.9,
+		roughness: 0.45,
+	},
+	MaterialPreset {
+		name: "anodized_red",
+		aliases: &["赤アルマイト"],
+		base_color: [0.55, 0.04, 0.05],
+		metallic: 0.9,
+		roughness: 0.4,
+	},
+	MaterialPreset {
+		name: "anodized_blue",
+		aliases: &["青アルマイト"],
+		base_color: [0.05, 0.18, 0.6],
+		metallic: 0.9,
+		roughness: 0.4,
+	},
+	MaterialPreset {
+		name: "anodized_gold",
+		aliases: &["金アルマイト"],
+		base_color: [0.8, 0.6, 0.25],
+		metallic: 0.9,
+		roughness: 0.4,
+	},
+	MaterialPreset {
+		name: "powder_white",
+		aliases: &["白塗装"],
+		base_color: [0.85, 0.85, 0.83],
+		metallic: 0.0,
+		roughness: 0.6,
+	},
+	MaterialPreset {
+		name: "powder_black",
+		aliases: &["黒塗装"],
+		base_color: [0.03, 0.03, 0.03],
+		metallic: 0.0,
+		roughness: 0.55,
+	},
+	MaterialPreset {
+		name: "brass",
+		aliases: &["c2600", "c2801", "真鍮"],
+		base_color: [0.91, 0.78, 0.42],
+		metallic: 1.0,
+		roughness: 0.3,
+	},
+];
+
+/// SelectInput の value からプリセットを探す（大文字小文字は区別しない）。
+/// 該当しなければ None で、API では 400 とする。
+pub fn material_preset(value: &str) -> Option<&'static MaterialPreset> {
+	let value = value.trim().to_lowercase();
+	PRESETS
+		.iter()
+		.find(|p| p.name == value || p.aliases.contains(&value.as_str()))
+}
+
+/// プリセットの名前（400 のメッセージで受け付ける値として示す）
+pub fn material_names() -> Vec<&'static str> {
+	PRESETS.iter().map(|p| p.name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn preset_matches_name_and_alias() {
+		assert_eq!(material_preset("stainless").unwrap().name, "stainless");
+		assert_eq!(material_preset(" SUS304 ").unwrap().name, "stainless");
+		assert_eq!(material_preset("A5052").unwrap().name, "aluminium");
+		assert_eq!(material_preset("真鍮").unwrap().name, "brass");
+		assert!(material_preset("SS400").is_none());
+		for name in material_names() {
+			assert_eq!(material_preset(name).unwrap().name, name);
+		}
+		for preset in PRESETS {
+			assert!((0.0..=1.0).contains(&preset.metallic));
+			assert!((0.0..=1.0).contains(&preset.roughness));
+		}
+	}
+}
@@ -0,0 +1,449 @@
+//! STEP 出力のスキーマ・単位・製品名の指定。
+//!
+//! スキーマと長さ単位は OCCT の STEP 書き出し (occt.rs の write_step) で選ぶ。形状は OCCT が単位に合わせて換算し、
+//! AP203 は AP203 のエンティティで書かれる。
+//! 製品名と品番は OCCT が固定の名前を書くため、書き出した Part 21 テキストのヘッダと PRODUCT を書き換える。
+
+/// 出力する STEP のアプリケーションプロトコル
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StepSchema {
+	Ap203,
+	#[default]
+	Ap214,
+	Ap242,
+}
+
+impl StepSchema {
+	/// クエリの値 (ap203 / ap214 / ap242) から選ぶ
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name.to_ascii_lowercase().as_str() {
+			"ap203" => Some(Self::Ap203),
+			"ap214" => Some(Self::Ap214),
+			"ap242" => Some(Self::Ap242),
+			_ => None,
+		}
+	}
+
+	/// OCCT の write.step.schema の値
+	pub(crate) fn occt_name(self) -> &'static str {
+		match self {
+			Self::Ap203 => "AP203",
+			Self::Ap214 => "AP214IS",
+			Self::Ap242 => "AP242DIS",
+		}
+	}
+}
+
+/// 出力する STEP の長さ単位
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StepUnit {
+	#[default]
+	Mm,
+	Inch,
+}
+
+impl StepUnit {
+	/// クエリの値 (mm / inch) から選ぶ
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name.to_ascii_lowercase().as_str() {
+			"mm" => Some(Self::Mm),
+			"inch" | "in" => Some(Self::Inch),
+			_ => None,
+		}
+	}
+
+	/// OCCT の write.step.unit の値
+	pub(crate) fn occt_name(self) -> &'static str {
+		match self {
+			Self::Mm => "MM",
+			Self::Inch => "INCH",
+		}
+	}
+}
+
+/// STEP 出力のオプション。既定値は AP214, mm, 製品名なし。
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StepOptions {
+	pub schema: StepSchema,
+	pub unit: StepUnit,
+	/// PRODUCT の名前と FILE_DESCRIPTION に書く製品名（フォームの label など）
+	pub product_name: Option<String>,
+	/// PRODUCT の id と FILE_NAME に書く品番
+	pub part_number: Option<String>,
+}
+
+/// Part 21 の 1 エンティティ。body は `#id=` の後ろから `;` の手前まで
+struct Record {
+	id: u64,
+	body: String,
+}
+
+/// OCCT が書き出した STEP テキストに製品名と品番を書く
+pub(super) fn apply_options(step: &[u8], options: &StepOptions) -> Result<Vec<u8>, String> {
+	if options.product_name.is_none() && options.part_number.is_none() {
+		return Ok(step.to_vec());
+	}
+	let text = std::str::from_utf8(step).map_err(|e| format!("STEP読み込み失敗: {e:?}"))?;
+	let header_start = text.find("HEADER;").ok_or("STEP の HEADER がありません")?;
+	let data_start = text.find("DATA;").ok_or("STEP の DATA がありません")?;
+	let header_end = text[..data_start]
+		.rfind("ENDSEC;")
+		.ok_or("STEP の HEADER が閉じていません")?;
+	let data_end = text[data_start..]
+		.find("ENDSEC;")
+		.map(|i| data_start + i)
+		.ok_or("STEP の DATA が閉じていません")?;
+
+	// ヘッダ: FILE_DESCRIPTION / FILE_NAME
+	let mut header = Vec::new();
+	for statement in split_statements(&text[header_start + "HEADER;".len()..header_end]) {
+		let (name, mut params) = split_entity(&statement);
+		match name.as_str() {
+			"FILE_DESCRIPTION" => {
+				if let Some(product) = &options.product_name {
+					params[0] = format!("({})", step_string(product));
+				}
+			}
+			"FILE_NAME" => {
+				if let Some(name) = options
+					.part_number
+					.as_ref()
+					.or(options.product_name.as_ref())
+				{
+					params[0] = step_string(name);
+				}
+			}
+			_ => {}
+		}
+		header.push(format!("{name}({})", params.join(",")));
+	}
+
+	// データ
+	let mut records: Vec<Record> = split_statements(&text[data_start + "DATA;".len()..data_end])
+		.into_iter()
+		.map(|statement| {
+			let (id, body) = statement
+				.split_once('=')
+				.ok_or("STEP のエンティティが不正です")?;
+			let id = id
+				.trim()
+				.trim_start_matches('#')
+				.parse()
+				.map_err(|e| format!("STEP のエンティティ番号が不正です: {e:?}"))?;
+			Ok(Record {
+				id,
+				body: body.trim().to_string(),
+			})
+		})
+		.collect::<Result<_, String>>()?;
+	for record in &mut records {
+		let (name, mut params) = split_entity(&record.body);
+		match name.as_str() {
+			// Shape は単一の製品として書かれる
+			"PRODUCT" if params.len() == 4 => {
+				if let Some(part_number) = &options.part_number {
+					params[0] = step_string(part_number);
+				}
+				if let Some(product) = &options.product_name {
+					params[1] = step_string(product);
+				}
+			}
+			_ => continue,
+		}
+		record.body = format!("{name}({})", params.join(","));
+	}
+
+	let mut out = String::with_capacity(step.len());
+	out.push_str(&text[..header_start]);
+	out.push_str("HEADER;\n");
+	for statement in header {
+		out.push_str(&statement);
+		out.push_str(";\n");
+	}
+	out.push_str("ENDSEC;\nDATA;\n");
+	for record in records {
+		out.push_str(&format!("#{}={};\n", record.id, record.body));
+	}
+	out.push_str(&text[data_end..]);
+	Ok(out.into_bytes())
+}
+
+/// 文字列の外側で `;` ごとに文を分ける（前後の空白とコメントは除く）
+pub(crate) fn split_statements(text: &str) -> Vec<String> {
+	let mut statements = Vec::new();
+	let mut current = String::new();
+	let mut chars = text.chars().peekable();
+	let mut in_string = false;
+	while let Some(c) = chars.next() {
+		if in_string {
+			current.push(c);
+			if c == '\'' {
+				in_string = false;
+			}
+			continue;
+		}
+		match c {
+			'\'' => {
+				in_string = true;
+				current.push(c);
+			}
+			'/' if chars.peek() == Some(&'*') => {
+				chars.next();
+				let mut last = ' ';
+				for c in chars.by_ref() {
+					if last == '*' && c == '/' {
+						break;
+					}
+					last = c;
+				}
+			}
+			';' => {
+				let statement = current.trim();
+				if !statement.is_empty() {
+					statements.push(statement.to_string());
+				}
+				current.clear();
+			}
+			_ => current.push(c),
+		}
+	}
+	statements
+}
+
+/// `NAME(a,b,...)` を名前と最上位のパラメータに分ける。複合エンティティ `( A() B() )` は名前が空になる
+pub(crate) fn split_entity(body: &str) -> (String, Vec<String>) {
+	let body = body.trim();
+	let Some(open) = body.find('(') else {
+		return (body.to_string(), Vec::new());
+	};
+	let name = body[..open].trim().to_string();
+	if name.is_empty() || !body.ends_with(')') {
+		return (String::new(), vec![body.to_string()]);
+	}
+	(name, split_params(&body[open + 1..body.len() - 1]))
+}
+
+/// 括弧の中身 `a,b,...` を最上位の `,` で分ける
+pub(crate) fn split_params(inner: &str) -> Vec<String> {
+	let mut params = Vec::new();
+	let mut current = String::new();
+	let mut depth = 0;
+	let mut in_string = false;
+	for c in inner.chars() {
+		match c {
+			'\'' => in_string = !in_string,
+			'(' if !in_string => depth += 1,
+			')' if !in_string => depth -= 1,
+			',' if !in_string && depth == 0 => {
+				params.push(current.trim().to_string());
+				current.clear();
+				continue;
+			}
+			_ => {}
+		}
+		current.push(c);
+	}
+	params.push(current.trim().to_string());
+	params
+}
+
+/// Part 21 の実数表記（仮数部に必ず小数点を含む）
+pub(crate) fn step_real(v: f64) -> String {
+	let s = format!("{:?}", v + 0.0);
+	match s.split_once('e') {
+		Some((mantissa, exponent)) if !mantissa.contains('.') => format!("{mantissa}.E{exponent}"),
+		Some((mantissa, exponent)) => format!("{mantissa}E{exponent}"),
+		None => s,
+	}
+}
+
+/// Part 21 の文字列リテラル。非 ASCII 文字は \X2\ (UTF-16) で書く
+pub(crate) fn step_string(s: &str) -> String {
+	let mut out = String::from("'");
+	let mut wide = String::new();
+	for c in s.chars() {
+		if c.is_ascii() && !c.is_ascii_control() {
+			if !wide.is_empty() {
+				out.push_str(&format!("\\X2\\{wide}\\X0\\"));
+				wide.clear();
+			}
+			match c {
+				'\'' => out.push_str("''"),
+				'\\' => out.push_str("\\\\"),
+				_ => out.push(c),
+			}
+		} else {
+			for unit in c.encode_utf16(&mut [0; 2]) {
+				wide.push_str(&format!("{unit:04X}"));
+			}
+		}
+	}
+	if !wide.is_empty() {
+		out.push_str(&format!("\\X2\\{wide}\\X0\\"));
+	}
+	out.push('\'');
+	out
+}
+
+/// Part 21 の文字列リテラルを読む（step_string の逆）。`$` など文字列でなければ None
+pub(crate) fn parse_step_string(param: &str) -> Option<String> {
+	let inner = param.trim().strip_prefix('\'')?.strip_suffix('\'')?;
+	let mut out = String::new();
+	let mut chars = inner.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'\'' => {
+				chars.next_if_eq(&'\'');
+				out.push('\'');
+			}
+			'\\' => match chars.next() {
+				Some('\\') => out.push('\\'),
+				// \X2\ (UTF-16) / \X4\ (UTF-32) … \X0\
+				Some('X') if matches!(chars.peek(), Some('2' | '4')) => {
+					let width = if chars.next() == Some('2') { 4 } else { 8 };
+					chars.next_if_eq(&'\\');
+					let mut hex = String::new();
+					while let Some(h) = chars.next_if(|h| *h != '\\') {
+						hex.push(h);
+					}
+					// 終わりの \X0\
+					for _ in 0.."\\X0\\".len() {
+						chars.next();
+					}
+					let units: Vec<u32> = (0..hex.len() / width)
+						.filter_map(|i| {
+							u32::from_str_radix(&hex[i * width..(i + 1) * width], 16).ok()
+						})
+						.collect();
+					if width == 4 {
+						let units: Vec<u16> = units.iter().map(|&u| u as u16).collect();
+						out.push_str(&String::from_utf16_lossy(&units));
+					} else {
+						out.extend(units.into_iter().filter_map(char::from_u32));
+					}
+				}
+				// \X\hh (ISO 8859-1)
+				Some('X') => {
+					chars.next_if_eq(&'\\');
+					let hex: String = chars.by_ref().take(2).collect();
+					if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+						out.push(byte as char);
+					}
+				}
+				// \S\c (上位ビットを立てた文字)
+				Some('S') => {
+					chars.next_if_eq(&'\\');
+					if let Some(c) = chars.next() {
+						out.push(char::from_u32(c as u32 + 0x80).unwrap_or(c));
+					}
+				}
+				// \PA\ などのコードページ指定は読み飛ばす
+				Some('P') => {
+					chars.next();
+					chars.next_if_eq(&'\\');
+				}
+				Some(other) => {
+					out.push('\\');
+					out.push(other);
+				}
+				None => out.push('\\'),
+			},
+			_ => out.push(c),
+		}
+	}
+	Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// OCCT の出力と同じ形の小さな STEP
+	const SAMPLE: &str = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('Open CASCADE Model'),'2;1');
+FILE_NAME('Open CASCADE Shape Model','2026-01-01T00:00:00',('Author'),(
+    'Open CASCADE'),'Open CASCADE STEP processor 7.8','Open CASCADE 7.8'
+  ,'Unknown');
+FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));
+ENDSEC;
+DATA;
+#1 = APPLICATION_PROTOCOL_DEFINITION('international standard',
+  'automotive_design',2000,#2);
+#2 = APPLICATION_CONTEXT(
+  'core data for automotive mechanical design processes');
+#3 = PRODUCT('Open CASCADE STEP translator 7.8 1',
+  'Open CASCADE STEP translator 7.8 1','',(#4));
+#4 = PRODUCT_CONTEXT('',#2,'mechanical');
+#10 = EDGE_CURVE('',#11,#11,#12,.T.);
+#11 = VERTEX_POINT('',#13);
+#12 = SURFACE_CURVE('',#14,(#15),.PCURVE_S1.);
+#13 = CARTESIAN_POINT('',(25.4,-50.8,0.));
+#14 = CIRCLE('',#16,12.7);
+#15 = PCURVE('',#17,#18);
+#16 = AXIS2_PLACEMENT_3D('',#13,#19,#20);
+#17 = CYLINDRICAL_SURFACE('',#16,12.7);
+#18 = DEFINITIONAL_REPRESENTATION('',(#21),#22);
+#19 = DIRECTION('',(0.,0.,1.));
+#20 = DIRECTION('',(1.,0.,0.));
+#21 = LINE('',#23,#24);
+#22 = ( GEOMETRIC_REPRESENTATION_CONTEXT(2) PARAMETRIC_REPRESENTATION_CONTEXT() REPRESENTATION_CONTEXT('2D SPACE','') );
+#23 = CARTESIAN_POINT('',(0.,0.));
+#24 = VECTOR('',#19,1.);
+#25 = ADVANCED_FACE('',(),#17,.T.);
+#30 = ( LENGTH_UNIT() NAMED_UNIT(*) SI_UNIT(.MILLI.,.METRE.) );
+#31 = UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(2.54E-05),#30,
+  'distance_accuracy_value','confusion accuracy');
+ENDSEC;
+END-ISO-10303-21;
+";
+
+	fn apply(options: &StepOptions) -> String {
+		String::from_utf8(apply_options(SAMPLE.as_bytes(), options).unwrap()).unwrap()
+	}
+
+	#[test]
+	fn default_options_keep_output() {
+		assert_eq!(apply(&StepOptions::default()), SAMPLE);
+	}
+
+	#[test]
+	fn product_is_rewritten() {
+		let step = apply(&StepOptions {
+			schema: StepSchema::Ap242,
+			product_name: Some("制御盤 'A'".to_string()),
+			part_number: Some("LP-001".to_string()),
+			..Default::default()
+		});
+		// スキーマは OCCT が書いたまま
+		assert!(step.contains("FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'))"));
+		assert!(step.contains("#3=PRODUCT('LP-001','\\X2\\52365FA176E4\\X0\\ ''A''',''"));
+		assert!(step.contains("FILE_NAME('LP-001','2026-01-01T00:00:00'"));
+		assert!(step.contains("FILE_DESCRIPTION(('\\X2\\52365FA176E4\\X0\\ ''A'''),'2;1')"));
+		assert!(step.ends_with("ENDSEC;\nEND-ISO-10303-21;\n"));
+		assert_eq!(StepSchema::from_name("AP242"), Some(StepSchema::Ap242));
+		assert_eq!(StepSchema::from_name("ap203"), Some(StepSchema::Ap203));
+		assert_eq!(StepUnit::from_name("inch"), Some(StepUnit::Inch));
+		assert_eq!(StepUnit::from_name("cm"), None);
+	}
+
+	#[test]
+	fn step_string_round_trips() {
+		for s in ["plain", "it's", "a\\b", "部品 A", "𝔸"] {
+			assert_eq!(parse_step_string(&step_string(s)).as_deref(), Some(s));
+		}
+		assert_eq!(
+			parse_step_string("'\\X\\E9t\\X\\E9'").as_deref(),
+			Some("été")
+		);
+		assert_eq!(parse_step_string("$"), None);
+	}
+
+	#[test]
+	fn step_real_has_decimal_point() {
+		assert_eq!(step_real(1.0), "1.0");
+		assert_eq!(step_real(-0.0), "0.0");
+		assert_eq!(step_real(1e-7), "1.E-7");
+		assert_eq!(step_real(2.5e-7), "2.5E-7");
+	}
+}
@@ -0,0 +1,279 @@
+//! CPU ソフトウェアラスタライザによる PNG サムネイル。
+//!
+//! GPU のないサーバーでメール・一覧用の静止画を作るため、メッシュを面の色でシェーディングし、
+//! エッジの離散化線を重ねて描く。背景は透明。
+
+use super::{DEFAULT_RGB, MESH_TOLERANCE, shape_mesh};
+use chijin::Shape;
+use glam::{DVec2, DVec3};
+
+/// アンチエイリアスのための縦横の超解像倍率
+const SUPERSAMPLE: usize = 2;
+/// 画像の一辺に対してモデルが占める割合
+const FILL: f64 = 0.9;
+/// 環境光と拡散光の強さ
+const AMBIENT: f64 = 0.35;
+const DIFFUSE: f64 = 0.65;
+/// エッジの色
+const EDGE_RGB: [f64; 3] = [0.15, 0.15, 0.15];
+
+/// 既定の視点と一辺のピクセル数
+pub const THUMBNAIL_VIEW: &str = "iso";
+pub const THUMBNAIL_SIZE: i32 = 256;
+
+/// 視点名から視点方向（モデルから視点へ向かう単位ベクトル, Z 軸が上）を返す
+pub fn thumbnail_view(name: &str) -> Option<DVec3> {
+	match name {
+		"iso" => Some(DVec3::new(1.0, -1.0, 1.0).normalize()),
+		"front" => Some(DVec3::NEG_Y),
+		"top" => Some(DVec3::Z),
+		"right" => Some(DVec3::X),
+		_ => None,
+	}
+}
+
+/// Shape を toward 方向から見た size × size ピクセルの PNG (RGBA) に描画する。
+pub fn thumbnail_png(shape: &Shape, toward: DVec3, size: u32) -> Result<Vec<u8>, String> {
+	mesh_thumbnail_png(shape, &shape_mesh(shape)?, toward, size)
+}
+
+/// shape_mesh でメッシュ化済みの mesh を使って thumbnail_png と同じ PNG を描画する。
+pub fn mesh_thumbnail_png(
+	shape: &Shape,
+	mesh: &chijin::Mesh,
+	toward: DVec3,
+	size: u32,
+) -> Result<Vec<u8>, String> {
+	let colors: Vec<chijin::Rgb> = mesh
+		.face_ids
+		.iter()
+		.map(|id| {
+			shape
+				.colormap
+				.get(&chijin::TShapeId(*id))
+				.copied()
+				.unwrap_or(DEFAULT_RGB)
+		})
+		.collect();
+	let edges: Vec<Vec<DVec3>> = shape
+		.edges()
+		.map(|edge| edge.approximation_segments(MESH_TOLERANCE).collect())
+		.collect();
+	let rgba = render(mesh, &colors, &edges, toward, size as usize);
+	encode_png(&rgba, size)
+}
+
+/// 正投影カメラ。right / up は画面の右 / 上方向、toward は視点側を向く単位ベクトル。
+struct Camera {
+	right: DVec3,
+	up: DVec3,
+	toward: DVec3,
+	center: DVec2,
+	scale: f64,
+	half: f64,
+}
+
+impl Camera {
+	/// 全頂点が画像の FILL の範囲に収まるカメラ
+	fn fit(vertices: &[DVec3], toward: DVec3, pixels: usize) -> Self {
+		let hint = if toward.z.abs() > 0.9 {
+			DVec3::Y
+		} else {
+			DVec3::Z
+		};
+		let right = hint.cross(toward).normalize();
+		let up = toward.cross(right);
+		let (min, max) = vertices.iter().fold(
+			(DVec2::splat(f64::INFINITY), DVec2::splat(f64::NEG_INFINITY)),
+			|(a, b), p| {
+				let q = DVec2::new(p.dot(right), p.dot(up));
+				(a.min(q), b.max(q))
+			},
+		);
+		let extent = (max - min).max_element().max(1e-9);
+		Self {
+			right,
+			up,
+			toward,
+			center: (min + max) / 2.0,
+			scale: FILL * pixels as f64 / extent,
+			half: pixels as f64 / 2.0,
+		}
+	}
+
+	/// ピクセル座標（y 下向き）と奥行き（視点に近いほど大きい）
+	fn project(&self, p: DVec3) -> (DVec2, f64) {
+		let q = DVec2::new(p.dot(self.right), p.dot(self.up)) - self.center;
+		(
+			DVec2::new(self.half + q.x * self.scale, self.half - q.y * self.scale),
+			p.dot(self.toward),
+		)
+	}
+}
+
+/// 三角形ごとの色 colors でメッシュを描き、エッジを重ねた RGBA 画像を返す
+fn render(
+	mesh: &chijin::Mesh,
+	colors: &[chijin::Rgb],
+	edges: &[Vec<DVec3>],
+	toward: DVec3,
+	size: usize,
+) -> Vec<u8> {
+	let n = size * SUPERSAMPLE;
+	let camera = Camera::fit(&mesh.vertices, toward, n);
+	let light = (camera.toward + camera.up * 0.5 + camera.right * 0.3).normalize();
+	let mut depth = vec![f64::NEG_INFINITY; n * n];
+	let mut color = vec![[0.0f64; 4]; n * n];
+
+	for (t, tri) in mesh.indices.chunks_exact(3).enumerate() {
+		let projected = [0, 1, 2].map(|k| camera.project(mesh.vertices[tri[k]]));
+		let q = projected.map(|(q, _)| q);
+		let area = (q[1] - q[0]).perp_dot(q[2] - q[0]);
+		if area.abs() < 1e-12 {
+			continue;
+		}
+		let rgb = colors[t];
+		let lo = q[0].min(q[1]).min(q[2]).max(DVec2::ZERO);
+		let hi = q[0].max(q[1]).max(q[2]).min(DVec2::splat(n as f64 - 1.0));
+		if lo.x > hi.x || lo.y > hi.y {
+			continue;
+		}
+		for y in lo.y.floor() as usize..=hi.y.ceil() as usize {
+			for x in lo.x.floor() as usize..=hi.x.ceil() as usize {
+				let c = DVec2::new(x as f64 + 0.5, y as f64 + 0.5);
+				let w0 = (q[2] - q[1]).perp_dot(c - q[1]) / area;
+				let w1 = (q[0] - q[2]).perp_dot(c - q[2]) / area;
+				let w2 = 1.0 - w0 - w1;
+				if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+					continue;
+				}
+				let z = w0 * projected[0].1 + w1 * projected[1].1 + w2 * projected[2].1;
+				let i = y * n + x;
+				if z <= depth[i] {
+					continue;
+				}
+				depth[i] = z;
+				// 面の向きが不定でも裏面が暗くならないよう |n·l| で陰影をつける
+				let normal = (mesh.normals[tri[0]] * w0
+					+ mesh.normals[tri[1]] * w1
+					+ mesh.normals[tri[2]] * w2)
+					.normalize_or_zero();
+				let shade = AMBIENT + DIFFUSE * normal.dot(light).abs();
+				color[i] = [
+					rgb.r as f64 * shade,
+					rgb.g as f64 * shade,
+					rgb.b as f64 * shade,
+					1.0,
+				];
+			}
+		}
+	}
+
+	// エッジ: 面より少し手前にあれば描く（縮小後に約 1 ピクセル幅）
+	let bias = 2.0 / camera.scale;
+	for points in edges {
+		for w in points.windows(2) {
+			let (a, da) = camera.project(w[0]);
+			let (b, db) = camera.project(w[1]);
+			let steps = (a.distance(b).ceil() as usize).max(1);
+			for k in 0..=steps {
+				let s = k as f64 / steps as f64;
+				let p = a.lerp(b, s);
+				let z = da + (db - da) * s;
+				for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+					let (x, y) = (p.x as isize + dx, p.y as isize + dy);
+					if x < 0 || y < 0 || x as usize >= n || y as usize >= n {
+						continue;
+					}
+					let i = y as usize * n + x as usize;
+					if z + bias >= depth[i] {
+						color[i] = [EDGE_RGB[0], EDGE_RGB[1], EDGE_RGB[2], 1.0];
+					}
+				}
+			}
+		}
+	}
+
+	// 縮小: 透明な背景とのアルファ付き平均
+	let mut rgba = vec![0u8; size * size * 4];
+	for y in 0..size {
+		for x in 0..size {
+			let mut sum = [0.0f64; 4];
+			for sy in 0..SUPERSAMPLE {
+				for sx in 0..SUPERSAMPLE {
+					let c = color[(y * SUPERSAMPLE + sy) * n + x * SUPERSAMPLE + sx];
+					for k in 0..3 {
+						sum[k] += c[k] * c[3];
+					}
+					sum[3] += c[3];
+				}
+			}
+			let o = (y * size + x) * 4;
+			if sum[3] > 0.0 {
+				for k in 0..3 {
+					rgba[o + k] = ((sum[k] / sum[3]).clamp(0.0, 1.0) * 255.0).round() as u8;
+				}
+			}
+			rgba[o + 3] = ((sum[3] / (SUPERSAMPLE * SUPERSAMPLE) as f64) * 255.0).round() as u8;
+		}
+	}
+	rgba
+}
+
+fn encode_png(rgba: &[u8], size: u32) -> Result<Vec<u8>, String> {
+	let mut out = Vec::new();
+	let mut encoder = png::Encoder::new(&mut out, size, size);
+	encoder.set_color(png::ColorType::Rgba);
+	encoder.set_depth(png::BitDepth::Eight);
+	let mut writer = encoder
+		.write_header()
+		.map_err(|e| format!("PNG書き込み失敗: {e:?}"))?;
+	writer
+		.write_image_data(rgba)
+		.map_err(|e| format!("PNG書き込み失敗: {e:?}"))?;
+	writer
+		.finish()
+		.map_err(|e| format!("PNG書き込み失敗: {e:?}"))?;
+	Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn render_fills_center_and_leaves_corners_transparent() {
+		// 視点に正対する四角形（2 三角形）
+		let mesh = chijin::Mesh {
+			vertices: vec![
+				DVec3::new(0.0, 0.0, 0.0),
+				DVec3::new(10.0, 0.0, 0.0),
+				DVec3::new(10.0, 0.0, 10.0),
+				DVec3::new(0.0, 0.0, 10.0),
+			],
+			uvs: vec![DVec2::ZERO; 4],
+			normals: vec![DVec3::NEG_Y; 4],
+			indices: vec![0, 1, 2, 0, 2, 3],
+			face_ids: vec![1, 1],
+		};
+		let red = chijin::Rgb {
+			r: 1.0,
+			g: 0.0,
+			b: 0.0,
+		};
+		let size = 32;
+		let rgba = render(&mesh, &[red, red], &[], DVec3::NEG_Y, size);
+		assert_eq!(rgba.len(), size * size * 4);
+		let pixel = |x: usize, y: usize| &rgba[(y * size + x) * 4..(y * size + x) * 4 + 4];
+		let center = pixel(size / 2, size / 2);
+		assert_eq!(center[3], 255);
+		assert!(center[0] > 0 && center[1] == 0 && center[2] == 0);
+		assert_eq!(pixel(0, 0)[3], 0);
+	}
+
+	#[test]
+	fn png_has_signature() {
+		let png = encode_png(&[0u8; 4 * 4 * 4], 4).unwrap();
+		assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+	}
+}
@@ -0,0 +1,195 @@
+//! GLB のノードの木（アセンブリ → ソリッド）。
+//!
+//! chijin はソリッドの列挙や XDE のラベルを公開していないため、OCCT でコンパウンド・ソリッド・シェル・面の木を
+//! たどり（occt.rs の shape_parts）、面の並びを shape.faces() と突き合わせる。
+//! どちらも TopExp_Explorer と同じ深さ優先の順なので、i 番目の面どうしが対応する。
+//! 同じ部品を複数配置したアセンブリでは面 (TShape) を配置どうしで共有するので、面は TShape ではなく出現ごとに扱う。
+//! STEP の読み込み結果はフリーシェイプ（ルートの製品）を並べたコンパウンドで、
+//! アセンブリは構成部品を子に持つコンパウンドになるので、製品構造 (StepAssemblyNode) と子の数が合えば製品名を付ける。
+
+use crate::occt;
+use crate::openapi::StepAssemblyNode;
+use chijin::Shape;
+
+/// GLB のノード。ソリッド（コンパウンド直下のシェル・面も含む）はメッシュを持つ葉
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum PartNode {
+	Group {
+		name: Option<String>,
+		children: Vec<PartNode>,
+	},
+	Solid {
+		name: Option<String>,
+		/// ソリッドの番号（三角形の振り分け先）
+		solid: usize,
+	},
+}
+
+/// 面の出現ごとの (TShapeId, ソリッドの番号)。shape.faces() の順
+pub(crate) type FaceSolids = Vec<(u64, usize)>;
+
+/// Shape のノードの木（ルートの子の並び）と面の出現ごとのソリッドの番号。
+/// 木を取得できないか、面の数が shape.faces() と合わなければ None
+pub(crate) fn shape_parts(shape: &Shape) -> Option<(Vec<PartNode>, FaceSolids)> {
+	let parts = occt::shape_parts(shape).ok()?;
+	let ids: Vec<u64> = shape.faces().map(|face| face.tshape_id().0).collect();
+	if ids.len() != parts.face_solids.len() {
+		return None;
+	}
+	let faces = ids
+		.into_iter()
+		.zip(parts.face_solids.iter().map(|&solid| solid as usize))
+		.collect();
+	Some((parts_from_tree(&parts.parents, &parts.solids)?, faces))
+}
+
+/// 深さ優先の順のノードの親の番号とソリッドの番号から、ルートの子の並びを組み立てる。
+/// 子のないグループは取り除く。親が自分より後にあれば None
+fn parts_from_tree(parents: &[i32], solids: &[i32]) -> Option<Vec<PartNode>> {
+	let mut children: Vec<Vec<usize>> = vec![Vec::new(); parents.len()];
+	let mut roots = Vec::new();
+	for (index, &parent) in parents.iter().enumerate() {
+		match usize::try_from(parent) {
+			Ok(parent) if parent < index => children[parent].push(index),
+			Ok(_) => return None,
+			Err(_) => roots.push(index),
+		}
+	}
+	fn node(index: usize, children: &[Vec<usize>], solids: &[i32]) -> Option<PartNode> {
+		match usize::try_from(*solids.get(index)?) {
+			Ok(solid) => Some(PartNode::Solid { name: None, solid }),
+			Err(_) => {
+				let nodes: Vec<PartNode> = children[index]
+					.iter()
+					.filter_map(|&child| node(child, children, solids))
+					.collect();
+				(!nodes.is_empty()).then_some(PartNode::Group {
+					name: None,
+					children: nodes,
+				})
+			}
+		}
+	}
+	let nodes: Vec<PartNode> = roots
+		.iter()
+		.filter_map(|&root| node(root, &children, solids))
+		.collect();
+	// ルートのコンパウンドの子を並べる
+	Some(match nodes {
+		mut nodes if nodes.len() == 1 => match nodes.remove(0) {
+			PartNode::Group { children, .. } => children,
+			solid => vec![solid],
+		},
+		nodes => nodes,
+	})
+}
+
+/// ノードに製品名を付ける。ルートの製品とルートの子の数が合えば順に対応させ、
+/// 以下同様にアセンブリの構成部品とコンパウンドの子の数が合う間だけたどる
+pub(crate) fn name_parts(nodes: &mut [PartNode], products: &[StepAssemblyNode]) {
+	if nodes.len() != products.len() {
+		return;
+	}
+	for (node, product) in nodes.iter_mut().zip(products) {
+		let label = match (product.name.as_str(), product.part_number.as_str()) {
+			("", "") => None,
+			("", part_number) => Some(part_number.to_string()),
+			(name, _) => Some(name.to_string()),
+		};
+		match node {
+			PartNode::Group { name, children } => {
+				*name = label;
+				name_parts(children, &product.children);
+			}
+			PartNode::Solid { name, .. } => *name = label,
+		}
+	}
+}
+
+/// ソリッドの番号を振り直す。map[旧番号] が None のソリッドは取り除き、空になったグループも取り除く
+pub(crate) fn renumber(nodes: Vec<PartNode>, map: &[Option<usize>]) -> Vec<PartNode> {
+	nodes
+		.into_iter()
+		.filter_map(|node| match node {
+			PartNode::Group { name, children } => {
+				let children = renumber(children, map);
+				(!children.is_empty()).then_some(PartNode::Group { name, children })
+			}
+			PartNode::Solid { name, solid } => map
+				.get(solid)
+				.copied()
+				.flatten()
+				.map(|solid| PartNode::Solid { name, solid }),
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tree_is_built_from_parents() {
+		// ルートのコンパウンド → [ソリッド 0, 面のないコンパウンド, コンパウンド → [ソリッド 1, ソリッド 2]]
+		let parents = [-1, 0, 0, 0, 3, 3];
+		let solids = [-1, 0, -1, -1, 1, 2];
+		let solid = |solid| PartNode::Solid { name: None, solid };
+		assert_eq!(
+			parts_from_tree(&parents, &solids),
+			Some(vec![
+				solid(0),
+				PartNode::Group {
+					name: None,
+					children: vec![solid(1), solid(2)],
+				},
+			])
+		);
+		// ルートがソリッド
+		assert_eq!(parts_from_tree(&[-1], &[0]), Some(vec![solid(0)]));
+		// 親が後ろにある
+		assert_eq!(parts_from_tree(&[1, -1], &[0, -1]), None);
+	}
+
+	#[test]
+	fn product_names_follow_matching_children() {
+		let product = |name: &str, children: Vec<StepAssemblyNode>| StepAssemblyNode {
+			name: name.to_string(),
+			part_number: format!("{name}-PN"),
+			children,
+		};
+		let solid = |solid| PartNode::Solid { name: None, solid };
+		let mut nodes = vec![PartNode::Group {
+			name: None,
+			children: vec![solid(0), solid(1)],
+		}];
+		name_parts(
+			&mut nodes,
+			&[product(
+				"Assy",
+				vec![product("Plate", vec![]), product("", vec![])],
+			)],
+		);
+		let PartNode::Group { name, children } = &nodes[0] else {
+			panic!("グループになっていません");
+		};
+		assert_eq!(name.as_deref(), Some("Assy"));
+		assert_eq!(
+			children,
+			&vec![
+				PartNode::Solid {
+					name: Some("Plate".to_string()),
+					solid: 0
+				},
+				PartNode::Solid {
+					name: Some("-PN".to_string()),
+					solid: 1
+				},
+			]
+		);
+
+		// 子の数が合わなければ名前を付けない
+		let mut nodes = vec![solid(0), solid(1)];
+		name_parts(&mut nodes, &[product("Assy", vec![])]);
+		assert_eq!(nodes, vec![solid(0), solid(1)]);
+	}
+}
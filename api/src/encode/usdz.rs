@@ -0,0 +1,222 @@
+//! iOS の AR Quick Look 向け USDZ 出力。
+//!
+//! GLB と同じメッシュ・ソリッド分割・色グループから USDA を組み立て、
+//! 無圧縮かつ各ファイルを 64 バイト境界に揃えた ZIP（USDZ の要件）に格納する。
+
+use super::{ColorGroups, DEFAULT_RGB, color_groups, shape_mesh, split_solids};
+use chijin::Shape;
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+/// USDZ 内のルートレイヤーのファイル名（ZIP の先頭に置く）
+const ROOT_LAYER: &str = "model.usda";
+/// USDZ の仕様が要求するファイルデータの境界
+const USDZ_ALIGNMENT: u16 = 64;
+
+/// Shape を USDZ に変換する。ソリッドごとに Xform、色グループごとに Mesh を作り、
+/// colormap の色を UsdPreviewSurface の diffuseColor にする。
+pub fn usdz(shape: &Shape) -> Result<Vec<u8>, String> {
+	let mesh = shape_mesh(shape)?;
+	let solids: Vec<ColorGroups> = split_solids(&mesh)
+		.iter()
+		.map(|triangles| color_groups(shape, &mesh, triangles))
+		.collect();
+	let usda = write_usda(&mesh, &solids);
+	usdz_archive(&[(ROOT_LAYER, usda)])
+}
+
+/// USDA テキストを組み立てる。
+/// 単位は mm (metersPerUnit = 0.001)。モデルは Z 軸が上なので、ルートで X 軸まわりに -90° 回して Y 軸を上にする。
+fn write_usda(mesh: &chijin::Mesh, solids: &[ColorGroups]) -> Vec<u8> {
+	// マテリアル: 色ごとに1つ。色なしグループは既定色
+	let mut colors: Vec<chijin::Rgb> = Vec::new();
+	let mut material_of: Vec<Vec<usize>> = Vec::with_capacity(solids.len());
+	for groups in solids {
+		let mut materials = Vec::with_capacity(groups.len());
+		for (color, _) in groups {
+			let c = color.unwrap_or(DEFAULT_RGB);
+			let index = match colors
+				.iter()
+				.position(|x| (x.r, x.g, x.b) == (c.r, c.g, c.b))
+			{
+				Some(i) => i,
+				None => {
+					colors.push(c);
+					colors.len() - 1
+				}
+			};
+			materials.push(index);
+		}
+		material_of.push(materials);
+	}
+
+	let mut s = String::from(
+		"#usda 1.0\n(\n\
+		 \tdefaultPrim = \"model\"\n\
+		 \tmetersPerUnit = 0.001\n\
+		 \tupAxis = \"Y\"\n\
+		 \tdoc = \"lambda360\"\n)\n\n\
+		 def Xform \"model\" (\n\tkind = \"component\"\n)\n{\n\
+		 \tdouble3 xformOp:rotateXYZ = (-90, 0, 0)\n\
+		 \tuniform token[] xformOpOrder = [\"xformOp:rotateXYZ\"]\n\n\
+		 \tdef Scope \"Materials\"\n\t{\n",
+	);
+	for (i, c) in colors.iter().enumerate() {
+		writeln!(
+			s,
+			"\t\tdef Material \"color_{i}\"\n\t\t{{\n\
+			 \t\t\ttoken outputs:surface.connect = </model/Materials/color_{i}/PreviewSurface.outputs:surface>\n\n\
+			 \t\t\tdef Shader \"PreviewSurface\"\n\t\t\t{{\n\
+			 \t\t\t\tuniform token info:id = \"UsdPreviewSurface\"\n\
+			 \t\t\t\tcolor3f inputs:diffuseColor = ({}, {}, {})\n\
+			 \t\t\t\tfloat inputs:metallic = 0\n\
+			 \t\t\t\tfloat inputs:roughness = 0.5\n\
+			 \t\t\t\ttoken outputs:surface\n\
+			 \t\t\t}}\n\t\t}}",
+			c.r, c.g, c.b
+		)
+		.unwrap();
+	}
+	s.push_str("\t}\n");
+
+	for (solid, groups) in solids.iter().enumerate() {
+		writeln!(s, "\n\tdef Xform \"solid_{solid}\"\n\t{{").unwrap();
+		for (group, (_, indices)) in groups.iter().enumerate() {
+			// グループが使う頂点だけを詰め直す
+			let mut local: std::collections::HashMap<usize, usize> =
+				std::collections::HashMap::new();
+			let mut used: Vec<usize> = Vec::new();
+			let face_indices: Vec<usize> = indices
+				.iter()
+				.map(|&v| {
+					*local.entry(v).or_insert_with(|| {
+						used.push(v);
+						used.len() - 1
+					})
+				})
+				.collect();
+			let (min, max) = used.iter().fold(
+				(glam::DVec3::INFINITY, glam::DVec3::NEG_INFINITY),
+				|(a, b), &v| (a.min(mesh.vertices[v]), b.max(mesh.vertices[v])),
+			);
+			writeln!(
+				s,
+				"\t\tdef Mesh \"group_{group}\" (\n\t\t\tprepend apiSchemas = [\"MaterialBindingAPI\"]\n\t\t)\n\t\t{{"
+			)
+			.unwrap();
+			writeln!(s, "\t\t\tfloat3[] extent = [{}, {}]", vec3(min), vec3(max)).unwrap();
+			writeln!(
+				s,
+				"\t\t\tint[] faceVertexCounts = [{}]",
+				vec!["3"; face_indices.len() / 3].join(", ")
+			)
+			.unwrap();
+			writeln!(
+				s,
+				"\t\t\tint[] faceVertexIndices = [{}]",
+				join(face_indices.iter().map(|i| i.to_string()))
+			)
+			.unwrap();
+			writeln!(
+				s,
+				"\t\t\tpoint3f[] points = [{}]",
+				join(used.iter().map(|&v| vec3(mesh.vertices[v])))
+			)
+			.unwrap();
+			writeln!(
+				s,
+				"\t\t\tnormal3f[] normals = [{}] (\n\t\t\t\tinterpolation = \"vertex\"\n\t\t\t)",
+				join(used.iter().map(|&v| vec3(mesh.normals[v])))
+			)
+			.unwrap();
+			writeln!(
+				s,
+				"\t\t\trel material:binding = </model/Materials/color_{}>\n\
+				 \t\t\tuniform token subdivisionScheme = \"none\"\n\t\t}}",
+				material_of[solid][group]
+			)
+			.unwrap();
+		}
+		s.push_str("\t}\n");
+	}
+	s.push_str("}\n");
+	s.into_bytes()
+}
+
+fn vec3(v: glam::DVec3) -> String {
+	format!("({}, {}, {})", v.x as f32, v.y as f32, v.z as f32)
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+	items.collect::<Vec<_>>().join(", ")
+}
+
+/// (ファイル名, 中身) の組から USDZ（無圧縮・64 バイト境界の ZIP）を作る
+fn usdz_archive(entries: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, String> {
+	let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+	let options = zip::write::SimpleFileOptions::default()
+		.compression_method(zip::CompressionMethod::Stored)
+		.with_alignment(USDZ_ALIGNMENT);
+	for (name, data) in entries {
+		zip.start_file(*name, options)
+			.map_err(|e| format!("USDZ書き込み失敗: {e:?}"))?;
+		zip.write_all(data)
+			.map_err(|e| format!("USDZ書き込み失敗: {e:?}"))?;
+	}
+	let cursor = zip
+		.finish()
+		.map_err(|e| format!("USDZ書き込み失敗: {e:?}"))?;
+	Ok(cursor.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use glam::{DVec2, DVec3};
+
+	#[test]
+	fn archive_is_stored_and_aligned() {
+		let data =
+			usdz_archive(&[(ROOT_LAYER, b"#usda 1.0\n".to_vec()), ("b.txt", vec![1; 3])]).unwrap();
+		let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+		assert_eq!(archive.by_index(0).unwrap().name(), ROOT_LAYER);
+		for i in 0..archive.len() {
+			let file = archive.by_index(i).unwrap();
+			assert_eq!(file.compression(), zip::CompressionMethod::Stored);
+			assert_eq!(file.data_start() % USDZ_ALIGNMENT as u64, 0);
+		}
+	}
+
+	#[test]
+	fn usda_has_mesh_per_color_group() {
+		let mesh = chijin::Mesh {
+			vertices: vec![
+				DVec3::new(0.0, 0.0, 0.0),
+				DVec3::new(1.0, 0.0, 0.0),
+				DVec3::new(0.0, 1.0, 0.0),
+				DVec3::new(1.0, 1.0, 0.0),
+			],
+			uvs: vec![DVec2::ZERO; 4],
+			normals: vec![DVec3::Z; 4],
+			indices: vec![0, 1, 2, 1, 3, 2],
+			face_ids: vec![1, 2],
+		};
+		let red = chijin::Rgb {
+			r: 1.0,
+			g: 0.0,
+			b: 0.0,
+		};
+		let solids = vec![vec![(Some(red), vec![0, 1, 2]), (None, vec![1, 3, 2])]];
+		let usda = String::from_utf8(write_usda(&mesh, &solids)).unwrap();
+		assert!(usda.starts_with("#usda 1.0"));
+		assert_eq!(usda.matches("def Mesh").count(), 2);
+		assert_eq!(usda.matches("def Material").count(), 2);
+		assert!(usda.contains("color3f inputs:diffuseColor = (1, 0, 0)"));
+		// 2 つ目のグループは頂点 1, 3, 2 を 0, 1, 2 に詰め直す
+		assert!(usda.contains("point3f[] points = [(1, 0, 0), (1, 1, 0), (0, 1, 0)]"));
+		assert_eq!(
+			usda.matches("int[] faceVertexIndices = [0, 1, 2]").count(),
+			2
+		);
+	}
+}
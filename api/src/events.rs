@@ -0,0 +1,147 @@
+//! 変換進捗のライブ配信。
+//!
+//! write_status（step_pipeline の progress から呼ばれる）が publish した StepStatusBody を、
+//! 同じプロセスで `/step/{id}/events` を購読しているクライアントへそのまま流す。
+//! 変換が別のプロセスで動いていて push が来ないときは、bucket_temp の `_/{uuid}.log` を読み直して流す。
+
+use crate::openapi::StepStatusBody;
+use futures_util::Stream;
+use ngoni::s3::S3Storage;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use tokio::sync::broadcast;
+
+/// push が来ないときに `_/{uuid}.log` を読み直す間隔
+const FALLBACK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// uuid ごとの配信チャンネル。終了 (progress 100 以上) を publish したら取り除く
+static CHANNELS: LazyLock<Mutex<HashMap<String, broadcast::Sender<StepStatusBody>>>> =
+	LazyLock::new(Default::default);
+
+fn channel(uuid: &str) -> broadcast::Sender<StepStatusBody> {
+	let mut channels = CHANNELS.lock().unwrap_or_else(|e| e.into_inner());
+	channels
+		.entry(uuid.to_string())
+		.or_insert_with(|| broadcast::channel(16).0)
+		.clone()
+}
+
+/// 進捗を購読者へ送る。購読者がいなければ何もしない
+pub fn publish(uuid: &str, status: &StepStatusBody) {
+	let mut channels = CHANNELS.lock().unwrap_or_else(|e| e.into_inner());
+	let Some(sender) = channels.get(uuid) else {
+		return;
+	};
+	let _ = sender.send(status.clone());
+	if status.progress >= 100 {
+		channels.remove(uuid);
+	}
+}
+
+async fn read_log(bucket_temp: &S3Storage, uuid: &str) -> Option<StepStatusBody> {
+	let (_, data) = bucket_temp.read(&format!("_/{uuid}.log")).await.ok()?;
+	serde_json::from_slice(&data).ok()
+}
+
+struct State {
+	uuid: String,
+	bucket_temp: S3Storage,
+	receiver: broadcast::Receiver<StepStatusBody>,
+	/// 最後に送った進捗（同じものを二度送らない）
+	last: Option<(i64, i32, String)>,
+	/// 最初に保存済みのログを送ったか
+	started: bool,
+	finished: bool,
+}
+
+impl State {
+	/// まだ送っていない進捗なら返す
+	fn fresh(&mut self, status: StepStatusBody) -> Option<StepStatusBody> {
+		let key = (status.timestamp, status.progress, status.message.clone());
+		if self.last.as_ref() == Some(&key) {
+			return None;
+		}
+		self.last = Some(key);
+		self.finished = status.progress >= 100;
+		Some(status)
+	}
+}
+
+impl Drop for State {
+	fn drop(&mut self) {
+		// receiver は State より後に drop されるため、自分を除いて数える
+		let mut channels = CHANNELS.lock().unwrap_or_else(|e| e.into_inner());
+		if channels
+			.get(&self.uuid)
+			.is_some_and(|sender| sender.receiver_count() <= 1)
+		{
+			channels.remove(&self.uuid);
+		}
+	}
+}
+
+/// uuid の進捗を終了 (progress 100 以上) まで流す。
+/// 最初に保存済みのログを送り、その後は push された進捗を、push がない間は保存済みのログを送る。
+pub fn status_stream(uuid: String, bucket_temp: S3Storage) -> impl Stream<Item = StepStatusBody> {
+	let state = State {
+		receiver: channel(&uuid).subscribe(),
+		uuid,
+		bucket_temp,
+		last: None,
+		started: false,
+		finished: false,
+	};
+	futures_util::stream::unfold(state, |mut state| async move {
+		loop {
+			if state.finished {
+				return None;
+			}
+			let status = if !state.started {
+				state.started = true;
+				read_log(&state.bucket_temp, &state.uuid).await
+			} else {
+				match tokio::time::timeout(FALLBACK_INTERVAL, state.receiver.recv()).await {
+					Ok(Ok(status)) => Some(status),
+					// 取りこぼした・チャンネルが閉じた・push がない場合は保存済みのログを読む
+					Ok(Err(broadcast::error::RecvError::Lagged(_))) | Err(_) => {
+						read_log(&state.bucket_temp, &state.uuid).await
+					}
+					Ok(Err(broadcast::error::RecvError::Closed)) => {
+						state.receiver = channel(&state.uuid).subscribe();
+						read_log(&state.bucket_temp, &state.uuid).await
+					}
+				}
+			};
+			if let Some(status) = status.and_then(|s| state.fresh(s)) {
+				return Some((status, state));
+			}
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn status(progress: i32) -> StepStatusBody {
+		StepStatusBody {
+			timestamp: 1,
+			progress,
+			message: format!("{progress}"),
+			..Default::default()
+		}
+	}
+
+	#[tokio::test]
+	async fn publish_reaches_subscribers_and_closes_on_finish() {
+		let mut receiver = channel("job").subscribe();
+		publish("job", &status(20));
+		publish("job", &status(100));
+		assert_eq!(receiver.recv().await.unwrap().progress, 20);
+		assert_eq!(receiver.recv().await.unwrap().progress, 100);
+		assert!(!CHANNELS.lock().unwrap().contains_key("job"));
+		// 購読者がいなければチャンネルは作らない
+		publish("nobody", &status(20));
+		assert!(!CHANNELS.lock().unwrap().contains_key("nobody"));
+	}
+}
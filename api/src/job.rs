@@ -0,0 +1,207 @@
+//! STEP 変換ジョブのキュー。
+//!
+//! `/step/{id}/submit` で積んだジョブを run_job が step_pipeline で実行する。
+//! HTTP リクエストの中で変換しないので、クライアントの接続や Lambda のタイムアウトに左右されない。
+//! 進捗は step_pipeline と同じく bucket_temp の `_/{uuid}.log` (StepStatusBody) に書かれ、
+//! 結果の manifest は完了の進捗より先に `_/{uuid}.result.json` に保存される。
+//! キューの実装は JobQueue で差し替えられ、環境変数 STEP_QUEUE で選ぶ。
+//! - `lambda`: 自分自身の Lambda を非同期に呼び出す LambdaQueue。ジョブは `/step/job` で実行される
+//! - 未設定: 同一プロセス内の LocalQueue と worker_loop（開発用。Lambda では応答後に止まるので使えない）
+
+mod lambda;
+
+use crate::upload::{StepError, status_body, step_pipeline, write_status};
+pub use lambda::LambdaQueue;
+use ngoni::s3::S3Storage;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, mpsc};
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// 失敗したジョブを含めた最大試行回数
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// 変換ジョブ。id はアップロード ID（`_/{id}.step` の uuid）をそのまま使う
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StepJob {
+	pub id: String,
+	/// 1 始まりの試行回数
+	pub attempt: u32,
+}
+
+impl StepJob {
+	pub fn new(id: String) -> Self {
+		Self { id, attempt: 1 }
+	}
+
+	/// 再試行するジョブ。MAX_ATTEMPTS に達していれば None
+	pub fn retry(&self) -> Option<Self> {
+		(self.attempt < MAX_ATTEMPTS).then(|| Self {
+			id: self.id.clone(),
+			attempt: self.attempt + 1,
+		})
+	}
+}
+
+/// ジョブキューのバックエンド。積んだジョブはどこかで run_job に渡され、再試行は run_job の呼び出し側が submit し直す。
+pub trait JobQueue: Send + Sync {
+	/// ジョブを積む
+	fn submit(&self, job: StepJob) -> BoxFuture<'_, Result<(), String>>;
+}
+
+/// STEP_QUEUE で選んだキュー。LocalQueue のときはワーカーも起動する
+pub async fn queue_from_env(
+	bucket_temp: &S3Storage,
+	bucket_main: &S3Storage,
+) -> Result<Arc<dyn JobQueue>, String> {
+	match std::env::var("STEP_QUEUE").unwrap_or_default().as_str() {
+		"lambda" => Ok(Arc::new(LambdaQueue::from_env().await?)),
+		"" => {
+			let queue = Arc::new(LocalQueue::new());
+			tokio::spawn(worker_loop(
+				queue.clone(),
+				bucket_temp.clone(),
+				bucket_main.clone(),
+			));
+			Ok(queue)
+		}
+		other => Err(format!(
+			"STEP_QUEUE '{other}' には対応していません (lambda)"
+		)),
+	}
+}
+
+/// 同一プロセス内のキュー（開発用）。プロセスが終了すると積まれたジョブは失われる
+pub struct LocalQueue {
+	sender: mpsc::UnboundedSender<StepJob>,
+	receiver: Mutex<mpsc::UnboundedReceiver<StepJob>>,
+}
+
+impl LocalQueue {
+	pub fn new() -> Self {
+		let (sender, receiver) = mpsc::unbounded_channel();
+		Self {
+			sender,
+			receiver: Mutex::new(receiver),
+		}
+	}
+}
+
+impl Default for LocalQueue {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl LocalQueue {
+	/// 次のジョブが来るまで待って取り出す。キューが閉じられたら None
+	pub async fn receive(&self) -> Option<StepJob> {
+		self.receiver.lock().await.recv().await
+	}
+}
+
+impl JobQueue for LocalQueue {
+	fn submit(&self, job: StepJob) -> BoxFuture<'_, Result<(), String>> {
+		let r = self
+			.sender
+			.send(job)
+			.map_err(|e| format!("ジョブ投入失敗: {e:?}"));
+		Box::pin(async move { r })
+	}
+}
+
+/// 取り出されるまでの進捗 (progress 0) を書いてからジョブを積む。
+/// 先に積むと、すぐに始まったジョブの進捗をこの進捗で上書きしてしまう
+pub async fn submit(
+	queue: &dyn JobQueue,
+	bucket_temp: &S3Storage,
+	job: StepJob,
+) -> Result<(), String> {
+	write_status(
+		bucket_temp,
+		&job.id,
+		status_body(0, None, "順番待ち".to_string()),
+	)
+	.await;
+	queue.submit(job).await
+}
+
+/// ジョブを 1 回実行する。
+/// S3 の一時的な失敗など再試行できるときは進捗を再試行待ちにして、次の試行と失敗を返す。
+/// それ以外の失敗と最後の試行の失敗は progress 101 として書き、None を返す。
+pub async fn run_job(
+	job: &StepJob,
+	bucket_temp: &S3Storage,
+	bucket_main: &S3Storage,
+) -> Option<(StepJob, StepError)> {
+	let Err(e) = step_pipeline(&job.id, bucket_temp.clone(), bucket_main.clone()).await else {
+		return None;
+	};
+	let Some(retry) = job.retry().filter(|_| e.code.retryable()) else {
+		write_status(bucket_temp, &job.id, e.status()).await;
+		return None;
+	};
+	let message = format!("再試行待ち ({}/{MAX_ATTEMPTS}回目): {e}", retry.attempt);
+	write_status(bucket_temp, &job.id, status_body(0, Some(e.stage), message)).await;
+	Some((retry, e))
+}
+
+/// 失敗した job を再試行するまでの待ち時間（試行回数に比例）
+pub fn retry_delay(job: &StepJob) -> Duration {
+	Duration::from_secs(5 * job.attempt as u64)
+}
+
+/// retry_delay だけ待ってから再試行を積む。積めなければ元の失敗を progress 101 として書く
+pub async fn resubmit(
+	queue: &dyn JobQueue,
+	bucket_temp: &S3Storage,
+	job: &StepJob,
+	(retry, e): (StepJob, StepError),
+) {
+	tokio::time::sleep(retry_delay(job)).await;
+	if queue.submit(retry).await.is_err() {
+		write_status(bucket_temp, &job.id, e.status()).await;
+	}
+}
+
+/// LocalQueue からジョブを取り出して順に変換するワーカー。キューが閉じられるまで戻らない。
+/// 再試行は待つ間も次のジョブを処理できるよう別タスクで積み直す。
+pub async fn worker_loop(queue: Arc<LocalQueue>, bucket_temp: S3Storage, bucket_main: S3Storage) {
+	while let Some(job) = queue.receive().await {
+		let Some(retry) = run_job(&job, &bucket_temp, &bucket_main).await else {
+			continue;
+		};
+		let queue = queue.clone();
+		let bucket_temp = bucket_temp.clone();
+		tokio::spawn(async move { resubmit(queue.as_ref(), &bucket_temp, &job, retry).await });
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn retry_stops_at_max_attempts() {
+		let job = StepJob::new("a".to_string());
+		let second = job.retry().unwrap();
+		assert_eq!(second.attempt, 2);
+		let last = second.retry().unwrap();
+		assert_eq!(last.attempt, MAX_ATTEMPTS);
+		assert_eq!(last.retry(), None);
+		assert!(retry_delay(&second) > retry_delay(&job));
+	}
+
+	#[tokio::test]
+	async fn local_queue_is_fifo() {
+		let queue = LocalQueue::new();
+		for id in ["a", "b"] {
+			queue.submit(StepJob::new(id.to_string())).await.unwrap();
+		}
+		assert_eq!(queue.receive().await.unwrap().id, "a");
+		assert_eq!(queue.receive().await.unwrap().id, "b");
+	}
+}
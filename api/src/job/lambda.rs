@@ -0,0 +1,145 @@
+//! 自分自身の Lambda を非同期に呼び出すジョブキュー。
+//!
+//! submit は実行中の関数をジョブ `{id, attempt}` をイベントとして非同期に呼び出す (InvocationType=Event)。
+//! Lambda Web Adapter は HTTP 以外のイベントを AWS_LWA_PASS_THROUGH_PATH（`/api/step/job`）へ POST するので、
+//! ジョブは別の呼び出しとして、応答を返すまで止められずに実行される。
+//! Lambda の SDK は依存に含めず、Invoke API を reqwest で呼んで SigV4 で署名する。
+
+use super::{BoxFuture, JobQueue, StepJob};
+use aws_credential_types::Credentials;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sigv4::http_request::{SignableBody, SignableRequest, SigningSettings, sign};
+use aws_sigv4::sign::v4;
+use std::time::SystemTime;
+
+pub struct LambdaQueue {
+	client: reqwest::Client,
+	credentials: SharedCredentialsProvider,
+	region: String,
+	url: String,
+}
+
+impl LambdaQueue {
+	/// 実行中の関数 (AWS_LAMBDA_FUNCTION_NAME) を呼び出すキュー
+	pub async fn from_env() -> Result<Self, String> {
+		let function = std::env::var("AWS_LAMBDA_FUNCTION_NAME")
+			.map_err(|e| format!("AWS_LAMBDA_FUNCTION_NAME取得失敗: {e:?}"))?;
+		let config = ngoni::aws::config().await;
+		let region = config
+			.region()
+			.ok_or("AWS のリージョンが設定されていません")?
+			.to_string();
+		let credentials = config
+			.credentials_provider()
+			.ok_or("AWS の認証情報が設定されていません")?;
+		Ok(Self {
+			client: reqwest::Client::new(),
+			credentials,
+			url: invoke_url(&region, &function),
+			region,
+		})
+	}
+
+	async fn invoke(&self, job: &StepJob) -> Result<(), String> {
+		let body =
+			serde_json::to_vec(job).map_err(|e| format!("ジョブのシリアライズ失敗: {e:?}"))?;
+		let credentials = self
+			.credentials
+			.provide_credentials()
+			.await
+			.map_err(|e| format!("認証情報取得失敗: {e:?}"))?;
+		let headers = signed_headers(
+			&credentials,
+			&self.region,
+			&self.url,
+			&body,
+			SystemTime::now(),
+		)?;
+		let mut request = self.client.post(&self.url).body(body);
+		for (name, value) in headers {
+			request = request.header(name, value);
+		}
+		let response = request
+			.send()
+			.await
+			.map_err(|e| format!("Lambda呼び出し失敗: {e:?}"))?;
+		// 非同期呼び出しはキューに入ると 202
+		if response.status() != reqwest::StatusCode::ACCEPTED {
+			let status = response.status();
+			let text = response.text().await.unwrap_or_default();
+			return Err(format!("Lambda呼び出し失敗: {status} {text}"));
+		}
+		Ok(())
+	}
+}
+
+impl JobQueue for LambdaQueue {
+	fn submit(&self, job: StepJob) -> BoxFuture<'_, Result<(), String>> {
+		Box::pin(async move { self.invoke(&job).await })
+	}
+}
+
+/// Lambda の Invoke API の URL
+fn invoke_url(region: &str, function: &str) -> String {
+	format!("https://lambda.{region}.amazonaws.com/2015-03-31/functions/{function}/invocations")
+}
+
+/// 非同期呼び出しのリクエストに付けるヘッダ（SigV4 の署名を含む）
+fn signed_headers(
+	credentials: &Credentials,
+	region: &str,
+	url: &str,
+	body: &[u8],
+	time: SystemTime,
+) -> Result<Vec<(String, String)>, String> {
+	let headers = [
+		("content-type", "application/json"),
+		("x-amz-invocation-type", "Event"),
+	];
+	let identity = credentials.clone().into();
+	let params = v4::SigningParams::builder()
+		.identity(&identity)
+		.region(region)
+		.name("lambda")
+		.time(time)
+		.settings(SigningSettings::default())
+		.build()
+		.map_err(|e| format!("署名失敗: {e:?}"))?
+		.into();
+	let request = SignableRequest::new("POST", url, headers.into_iter(), SignableBody::Bytes(body))
+		.map_err(|e| format!("署名失敗: {e:?}"))?;
+	let (instructions, _) = sign(request, &params)
+		.map_err(|e| format!("署名失敗: {e:?}"))?
+		.into_parts();
+	Ok(headers
+		.into_iter()
+		.chain(instructions.headers())
+		.map(|(name, value)| (name.to_string(), value.to_string()))
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn invoke_is_signed_for_lambda() {
+		let credentials = Credentials::new("AKID", "SECRET", None, None, "test");
+		let url = invoke_url("ap-northeast-1", "stack-ApiFunction");
+		let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_790_000_000);
+		let headers = signed_headers(&credentials, "ap-northeast-1", &url, b"{}", time).unwrap();
+		let header = |name: &str| {
+			headers
+				.iter()
+				.find(|(n, _)| n.eq_ignore_ascii_case(name))
+				.map(|(_, v)| v.as_str())
+		};
+		assert_eq!(header("x-amz-invocation-type"), Some("Event"));
+		let authorization = header("authorization").unwrap();
+		assert!(authorization.starts_with(
+			"AWS4-HMAC-SHA256 Credential=AKID/20260921/ap-northeast-1/lambda/aws4_request"
+		));
+		assert!(authorization.contains("x-amz-invocation-type"));
+		assert!(header("x-amz-date").is_some());
+	}
+}
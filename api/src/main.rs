@@ -1,5 +1,8 @@
 mod content_hash;
 mod encode;
+mod events;
+mod job;
+mod occt;
 #[allow(dead_code)]
 #[allow(unused_variables)]
 #[allow(unused_imports)]
@@ -0,0 +1,129 @@
+//! chijin が公開していない OCCT の機能を cxx で直接呼ぶ（C++ は cpp/occt.cpp）。
+//!
+//! chijin の Shape は中の TopoDS_Shape を公開していないため、形状はバイナリ BRep (BinTools) で受け渡す。
+//! 面の並びは TopExp_Explorer の順で、BRep の読み書きでは変わらないので、面どうしは並びの番号で対応させる。
+
+use chijin::{Rgb, Shape};
+use std::io::Read;
+
+#[cxx::bridge]
+mod ffi {
+	/// 形状の木。ノードは深さ優先の順に並び、parents は親のノードの番号（ルートは -1）、
+	/// solids は葉（ソリッド・シェル・面）のソリッドの番号（コンパウンドは -1）。
+	/// face_solids は面の出現（TopExp_Explorer の順。同じ部品を複数配置すれば配置ごと）ごとのソリッドの番号
+	struct ShapeParts {
+		parents: Vec<i32>,
+		solids: Vec<i32>,
+		face_solids: Vec<u32>,
+	}
+
+	/// 色付きの形状。faces は色のある面の番号（TopExp_Explorer の順）、colors はその面の sRGB (r, g, b の 3 つずつ)
+	struct ColoredBrep {
+		brep: Vec<u8>,
+		faces: Vec<u32>,
+		colors: Vec<f32>,
+	}
+
+	unsafe extern "C++" {
+		include!("api/cpp/occt.h");
+
+		fn shape_parts(brep: &[u8]) -> Result<ShapeParts>;
+		/// path の IGES ファイルを読む
+		fn read_iges(path: &str) -> Result<ColoredBrep>;
+		/// 葉（ソリッド・シェル・面）ごとに tolerance (mm) 以内の隙間を縫い合わせ、閉じたシェルはソリッドにして ShapeFix で直す
+		fn heal(input: &ColoredBrep, tolerance: f64) -> Result<ColoredBrep>;
+		/// schema と unit は OCCT の write.step.schema / write.step.unit の値
+		fn write_step(input: &ColoredBrep, schema: &str, unit: &str) -> Result<Vec<u8>>;
+		/// 面をトリム曲面とする IGES (mm) を書く
+		fn write_iges(input: &ColoredBrep) -> Result<Vec<u8>>;
+	}
+}
+
+use ffi::ColoredBrep;
+pub(crate) use ffi::ShapeParts;
+
+/// Shape をバイナリ BRep にする
+fn brep(shape: &Shape) -> Result<Vec<u8>, String> {
+	let mut buf = Vec::new();
+	shape
+		.write_brep_bin(&mut buf)
+		.map_err(|e| format!("BRep書き込み失敗: {e:?}"))?;
+	Ok(buf)
+}
+
+/// Shape を面の色と一緒にバイナリ BRep にする
+fn colored_brep(shape: &Shape) -> Result<ColoredBrep, String> {
+	let mut faces = Vec::new();
+	let mut colors = Vec::new();
+	for (i, face) in shape.faces().enumerate() {
+		if let Some(rgb) = shape.colormap.get(&face.tshape_id()) {
+			faces.push(i as u32);
+			colors.extend([rgb.r, rgb.g, rgb.b]);
+		}
+	}
+	Ok(ColoredBrep {
+		brep: brep(shape)?,
+		faces,
+		colors,
+	})
+}
+
+/// 色付きのバイナリ BRep から Shape を作る
+fn from_colored(colored: &ColoredBrep) -> Result<Shape, String> {
+	let mut shape = Shape::read_brep_bin(&mut colored.brep.as_slice())
+		.map_err(|e| format!("BRep読み込み失敗: {e:?}"))?;
+	let faces: Vec<_> = shape.faces().map(|face| face.tshape_id()).collect();
+	for (&index, rgb) in colored.faces.iter().zip(colored.colors.chunks_exact(3)) {
+		if let Some(&id) = faces.get(index as usize) {
+			shape.colormap.insert(
+				id,
+				Rgb {
+					r: rgb[0],
+					g: rgb[1],
+					b: rgb[2],
+				},
+			);
+		}
+	}
+	Ok(shape)
+}
+
+/// Shape の木（コンパウンド → ソリッド）と、面の出現ごとのソリッドの番号
+pub(crate) fn shape_parts(shape: &Shape) -> Result<ShapeParts, String> {
+	ffi::shape_parts(&brep(shape)?).map_err(|e| format!("形状の木の取得失敗: {e}"))
+}
+
+/// Shape を色付きの STEP にする。schema と unit は OCCT の名前 (AP214IS, MM など)
+pub(crate) fn write_step(shape: &Shape, schema: &str, unit: &str) -> Result<Vec<u8>, String> {
+	ffi::write_step(&colored_brep(shape)?, schema, unit)
+		.map_err(|e| format!("STEP書き込み失敗: {e}"))
+}
+
+/// IGES を色付きで読む。OCCT の IGES 読み込みはファイルからしか読めないので、一時ファイルに書いてから読む
+pub(crate) fn read_iges(reader: &mut impl Read) -> Result<Shape, String> {
+	let path = std::env::temp_dir().join(format!("{}.igs", uuid::Uuid::now_v7()));
+	let result = std::fs::File::create(&path)
+		.and_then(|mut file| std::io::copy(reader, &mut file))
+		.map_err(|e| format!("IGES一時ファイル書き込み失敗: {e:?}"))
+		.and_then(|_| {
+			let path = path
+				.to_str()
+				.ok_or("一時ファイルのパスが UTF-8 ではありません")?;
+			ffi::read_iges(path).map_err(|e| format!("IGES読み込み失敗: {e}"))
+		});
+	let _ = std::fs::remove_file(&path);
+	from_colored(&result?)
+}
+
+/// Shape を縫合と ShapeFix で直す。tolerance (mm) より狭い隙間の辺を縫い合わせ、閉じたシェルはソリッドにする。
+/// コンパウンドの木と面の色はそのまま
+pub(crate) fn heal(shape: &Shape, tolerance: f64) -> Result<Shape, String> {
+	from_colored(
+		&ffi::heal(&colored_brep(shape)?, tolerance).map_err(|e| format!("形状の修復失敗: {e}"))?,
+	)
+}
+
+/// Shape を色付きの IGES にする。面は元の曲面のままトリム曲面として書く。単位は mm
+pub(crate) fn write_iges(shape: &Shape) -> Result<Vec<u8>, String> {
+	ffi::write_iges(&colored_brep(shape)?).map_err(|e| format!("IGES書き込み失敗: {e}"))
+}
@@ -40,10 +40,70 @@ pub trait ApiInterface {
 	) -> impl Future<Output = ShapeGltfBinaryResponse> + Send {
 		async { Default::default() }
 	}
+	// POST /shape/3mf
+	fn shape_three_mf(
+		&self,
+		_req: ShapeThreeMfRequest,
+	) -> impl Future<Output = ShapeThreeMfResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /shape/brep
+	fn shape_brep(&self, _req: ShapeBrepRequest) -> impl Future<Output = ShapeBrepResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /shape/drawing.svg
+	fn shape_drawing_svg(
+		&self,
+		_req: ShapeDrawingSvgRequest,
+	) -> impl Future<Output = ShapeDrawingSvgResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /shape/dxf
+	fn shape_dxf(&self, _req: ShapeDxfRequest) -> impl Future<Output = ShapeDxfResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /shape/iges
+	fn shape_iges(&self, _req: ShapeIgesRequest) -> impl Future<Output = ShapeIgesResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /shape/obj
+	fn shape_obj(&self, _req: ShapeObjRequest) -> impl Future<Output = ShapeObjResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /shape/output.glb
+	fn shape_output_glb(
+		&self,
+		_req: ShapeOutputGlbRequest,
+	) -> impl Future<Output = ShapeOutputGlbResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /shape/ply
+	fn shape_ply(&self, _req: ShapePlyRequest) -> impl Future<Output = ShapePlyResponse> + Send {
+		async { Default::default() }
+	}
 	// POST /shape/step
 	fn shape_step(&self, _req: ShapeStepRequest) -> impl Future<Output = ShapeStepResponse> + Send {
 		async { Default::default() }
 	}
+	// POST /shape/stl
+	fn shape_stl(&self, _req: ShapeStlRequest) -> impl Future<Output = ShapeStlResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /shape/thumbnail.png
+	fn shape_thumbnail_png(
+		&self,
+		_req: ShapeThumbnailPngRequest,
+	) -> impl Future<Output = ShapeThumbnailPngResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /shape/usdz
+	fn shape_usdz(&self, _req: ShapeUsdzRequest) -> impl Future<Output = ShapeUsdzResponse> + Send {
+		async { Default::default() }
+	}
+	// POST /step/job
+	fn step_job(&self, _req: StepJobRequest) -> impl Future<Output = StepJobResponse> + Send {
+		async { Default::default() }
+	}
 	// POST /step/test
 	fn step_test(&self, _req: StepTestRequest) -> impl Future<Output = StepTestResponse> + Send {
 		async { Default::default() }
@@ -55,6 +115,17 @@ pub trait ApiInterface {
 	) -> impl Future<Output = StepUploadUrlResponse> + Send {
 		async { Default::default() }
 	}
+	// GET /step/{content_hash}/meta
+	fn step_meta(&self, _req: StepMetaRequest) -> impl Future<Output = StepMetaResponse> + Send {
+		async { Default::default() }
+	}
+	// GET /step/{id}/events
+	fn step_events(
+		&self,
+		_req: StepEventsRequest,
+	) -> impl Future<Output = StepEventsResponse> + Send {
+		async { Default::default() }
+	}
 	// POST /step/{id}/execute
 	fn step_execute(
 		&self,
@@ -62,6 +133,13 @@ pub trait ApiInterface {
 	) -> impl Future<Output = StepExecuteResponse> + Send {
 		async { Default::default() }
 	}
+	// GET /step/{id}/result
+	fn step_result(
+		&self,
+		_req: StepResultRequest,
+	) -> impl Future<Output = StepResultResponse> + Send {
+		async { Default::default() }
+	}
 	// GET /step/{id}/status
 	fn step_status(
 		&self,
@@ -69,6 +147,13 @@ pub trait ApiInterface {
 	) -> impl Future<Output = StepStatusResponse> + Send {
 		async { Default::default() }
 	}
+	// POST /step/{id}/submit
+	fn step_submit(
+		&self,
+		_req: StepSubmitRequest,
+	) -> impl Future<Output = StepSubmitResponse> + Send {
+		async { Default::default() }
+	}
 	// GET /version
 	fn version(&self, _req: VersionRequest) -> impl Future<Output = VersionResponse> + Send {
 		async { Default::default() }
@@ -110,6 +195,7 @@ impl Default for ActionActionResponse {
 // Request type for shape_gltf_binary
 #[derive(Debug)]
 pub struct ShapeGltfBinaryRequest {
+	pub material: Option<String>,
 	pub body: Box<ShapeNode>,
 	pub request: axum::http::Request<axum::body::Body>,
 }
@@ -122,6 +208,7 @@ impl AsRef<axum::http::Request<axum::body::Body>> for ShapeGltfBinaryRequest {
 #[derive(Debug)]
 pub enum ShapeGltfBinaryResponse {
 	Status200(Vec<u8>),
+	Status400(String),
 	Status500(String),
 	Raw(axum::response::Response), // Variant for custom responses
 }
@@ -130,432 +217,1338 @@ impl Default for ShapeGltfBinaryResponse {
 		Self::Status200(Default::default())
 	}
 }
-// Request type for shape_step
+// Request type for shape_three_mf
 #[derive(Debug)]
-pub struct ShapeStepRequest {
+pub struct ShapeThreeMfRequest {
 	pub body: Box<ShapeNode>,
 	pub request: axum::http::Request<axum::body::Body>,
 }
-impl AsRef<axum::http::Request<axum::body::Body>> for ShapeStepRequest {
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeThreeMfRequest {
 	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
 		&self.request
 	}
 }
-// Response type for shape_step
+// Response type for shape_three_mf
 #[derive(Debug)]
-pub enum ShapeStepResponse {
+pub enum ShapeThreeMfResponse {
 	Status200(Vec<u8>),
 	Status500(String),
 	Raw(axum::response::Response), // Variant for custom responses
 }
-impl Default for ShapeStepResponse {
+impl Default for ShapeThreeMfResponse {
 	fn default() -> Self {
 		Self::Status200(Default::default())
 	}
 }
-// Request type for step_test
+// Request type for shape_brep
 #[derive(Debug)]
-pub struct StepTestRequest {
-	pub body: Vec<u8>,
+pub struct ShapeBrepRequest {
+	pub format: Option<String>,
+	pub body: Box<ShapeNode>,
 	pub request: axum::http::Request<axum::body::Body>,
 }
-impl AsRef<axum::http::Request<axum::body::Body>> for StepTestRequest {
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeBrepRequest {
 	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
 		&self.request
 	}
 }
-// Response type for step_test
+// Response type for shape_brep
 #[derive(Debug)]
-pub enum StepTestResponse {
-	Status200(String),
+pub enum ShapeBrepResponse {
+	Status200(Vec<u8>),
+	Status400(String),
 	Status500(String),
 	Raw(axum::response::Response), // Variant for custom responses
 }
-impl Default for StepTestResponse {
+impl Default for ShapeBrepResponse {
 	fn default() -> Self {
 		Self::Status200(Default::default())
 	}
 }
-// Request type for step_upload_url
+// Request type for shape_drawing_svg
 #[derive(Debug)]
-pub struct StepUploadUrlRequest {
+pub struct ShapeDrawingSvgRequest {
+	pub body: ShapeOutput,
 	pub request: axum::http::Request<axum::body::Body>,
 }
-impl AsRef<axum::http::Request<axum::body::Body>> for StepUploadUrlRequest {
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeDrawingSvgRequest {
 	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
 		&self.request
 	}
 }
-// Response type for step_upload_url
+// Response type for shape_drawing_svg
 #[derive(Debug)]
-pub enum StepUploadUrlResponse {
-	Status200(UploadUrlBody),
+pub enum ShapeDrawingSvgResponse {
+	Status200(Vec<u8>),
+	Status500(String),
 	Raw(axum::response::Response), // Variant for custom responses
 }
-impl Default for StepUploadUrlResponse {
+impl Default for ShapeDrawingSvgResponse {
 	fn default() -> Self {
 		Self::Status200(Default::default())
 	}
 }
-// Request type for step_execute
+// Request type for shape_dxf
 #[derive(Debug)]
-pub struct StepExecuteRequest {
-	pub id: Uuid,
+pub struct ShapeDxfRequest {
+	pub normal: Option<String>,
+	pub offset: Option<f64>,
+	pub body: Box<ShapeNode>,
 	pub request: axum::http::Request<axum::body::Body>,
 }
-impl AsRef<axum::http::Request<axum::body::Body>> for StepExecuteRequest {
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeDxfRequest {
 	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
 		&self.request
 	}
 }
-// Response type for step_execute
+// Response type for shape_dxf
 #[derive(Debug)]
-pub enum StepExecuteResponse {
-	Status200(String),
+pub enum ShapeDxfResponse {
+	Status200(Vec<u8>),
+	Status400(String),
 	Status500(String),
 	Raw(axum::response::Response), // Variant for custom responses
 }
-impl Default for StepExecuteResponse {
+impl Default for ShapeDxfResponse {
 	fn default() -> Self {
 		Self::Status200(Default::default())
 	}
 }
-// Request type for step_status
+// Request type for shape_iges
 #[derive(Debug)]
-pub struct StepStatusRequest {
-	pub id: Uuid,
+pub struct ShapeIgesRequest {
+	pub body: Box<ShapeNode>,
 	pub request: axum::http::Request<axum::body::Body>,
 }
-impl AsRef<axum::http::Request<axum::body::Body>> for StepStatusRequest {
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeIgesRequest {
 	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
 		&self.request
 	}
 }
-// Response type for step_status
+// Response type for shape_iges
 #[derive(Debug)]
-pub enum StepStatusResponse {
-	Status200(StepStatusBody),
-	Status404,
+pub enum ShapeIgesResponse {
+	Status200(Vec<u8>),
+	Status500(String),
 	Raw(axum::response::Response), // Variant for custom responses
 }
-impl Default for StepStatusResponse {
+impl Default for ShapeIgesResponse {
 	fn default() -> Self {
 		Self::Status200(Default::default())
 	}
 }
-// Request type for version
+// Request type for shape_obj
 #[derive(Debug)]
-pub struct VersionRequest {
+pub struct ShapeObjRequest {
+	pub body: Box<ShapeNode>,
 	pub request: axum::http::Request<axum::body::Body>,
 }
-impl AsRef<axum::http::Request<axum::body::Body>> for VersionRequest {
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeObjRequest {
 	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
 		&self.request
 	}
 }
-// Response type for version
+// Response type for shape_obj
 #[derive(Debug)]
-pub enum VersionResponse {
-	Status200(String),
+pub enum ShapeObjResponse {
+	Status200(Vec<u8>),
+	Status500(String),
 	Raw(axum::response::Response), // Variant for custom responses
 }
-impl Default for VersionResponse {
+impl Default for ShapeObjResponse {
 	fn default() -> Self {
 		Self::Status200(Default::default())
 	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct ActionOutput {
-	pub r#disable: Option<serde_json::Value>,
-	pub r#email_bcc: Vec<String>,
-	pub r#email_to: Vec<String>,
-	pub r#label: String,
-	pub r#slack: Vec<String>,
-	pub r#subject: String,
+// Request type for shape_output_glb
+#[derive(Debug)]
+pub struct ShapeOutputGlbRequest {
+	pub material: Option<String>,
+	pub body: ShapeOutput,
+	pub request: axum::http::Request<axum::body::Body>,
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct ActionRequest {
-	pub r#action: ActionOutput,
-	pub r#input: HashMap<String, Box<Input>>,
-	pub r#output: Vec<Box<Output>>,
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeOutputGlbRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
 }
-
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-#[serde(tag = "type")]
-pub enum Annotation {
-	#[serde(rename = "point")]
-	Point(PointAnnotation),
-	#[serde(rename = "distance")]
-	Distance(DistanceAnnotation),
+// Response type for shape_output_glb
+#[derive(Debug)]
+pub enum ShapeOutputGlbResponse {
+	Status200(Vec<u8>),
+	Status400(String),
+	Status500(String),
+	Raw(axum::response::Response), // Variant for custom responses
 }
-impl Default for Annotation {
+impl Default for ShapeOutputGlbResponse {
 	fn default() -> Self {
-		Self::Point(Default::default())
+		Self::Status200(Default::default())
 	}
 }
-
-pub type Axis = String;
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct BorderOutput {}
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct DistanceAnnotation {
-	pub r#end: Vec<f64>,
-	pub r#label: String,
-	pub r#start: Vec<f64>,
-}
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct ForbiddenResponse {}
-
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-#[serde(tag = "type")]
-pub enum Input {
-	#[serde(rename = "upload")]
-	Upload(UploadInput),
-	#[serde(rename = "text")]
-	Text(TextInput),
-	#[serde(rename = "number")]
-	Number(NumberInput),
-	#[serde(rename = "select")]
-	Select(SelectInput),
+// Request type for shape_ply
+#[derive(Debug)]
+pub struct ShapePlyRequest {
+	pub body: Box<ShapeNode>,
+	pub request: axum::http::Request<axum::body::Body>,
 }
-impl Default for Input {
-	fn default() -> Self {
-		Self::Upload(Default::default())
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapePlyRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
 	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct IntersectNode {
-	pub r#a: Box<ShapeNode>,
-	pub r#b: Box<ShapeNode>,
-}
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct MessageOutput {
-	pub r#label: String,
-	pub r#messageType: String,
+// Response type for shape_ply
+#[derive(Debug)]
+pub enum ShapePlyResponse {
+	Status200(Vec<u8>),
+	Status500(String),
+	Raw(axum::response::Response), // Variant for custom responses
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct NumberConstraintEnum {
-	pub r#enum: Vec<f64>,
+impl Default for ShapePlyResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct NumberConstraintRange {
-	pub r#max: Option<f64>,
-	pub r#min: Option<f64>,
-	pub r#step: Option<f64>,
+// Request type for shape_step
+#[derive(Debug)]
+pub struct ShapeStepRequest {
+	pub schema: Option<String>,
+	pub unit: Option<String>,
+	pub productName: Option<String>,
+	pub partNumber: Option<String>,
+	pub body: Box<ShapeNode>,
+	pub request: axum::http::Request<axum::body::Body>,
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct NumberInput {
-	pub r#constraint: Option<serde_json::Value>,
-	pub r#default: Option<f64>,
-	pub r#label: String,
-	pub r#unit: Option<String>,
-	pub r#value: f64,
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeStepRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
 }
-
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-#[serde(untagged)]
-pub enum NumberOrExpr {
-	Variant0(f64),
-	Variant1(String),
+// Response type for shape_step
+#[derive(Debug)]
+pub enum ShapeStepResponse {
+	Status200(Vec<u8>),
+	Status400(String),
+	Status500(String),
+	Raw(axum::response::Response), // Variant for custom responses
 }
-impl Default for NumberOrExpr {
+impl Default for ShapeStepResponse {
 	fn default() -> Self {
-		Self::Variant0(Default::default())
+		Self::Status200(Default::default())
 	}
 }
-
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-#[serde(tag = "type")]
-pub enum Output {
-	#[serde(rename = "shape")]
-	Shape(ShapeOutput),
-	#[serde(rename = "border")]
-	Border(BorderOutput),
-	#[serde(rename = "message")]
-	Message(MessageOutput),
-	#[serde(rename = "action")]
-	Action(ActionOutput),
+// Request type for shape_stl
+#[derive(Debug)]
+pub struct ShapeStlRequest {
+	pub ascii: Option<bool>,
+	pub body: Box<ShapeNode>,
+	pub request: axum::http::Request<axum::body::Body>,
 }
-impl Default for Output {
-	fn default() -> Self {
-		Self::Shape(Default::default())
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeStlRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
 	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct PointAnnotation {
-	pub r#label: String,
-	pub r#position: Vec<f64>,
+// Response type for shape_stl
+#[derive(Debug)]
+pub enum ShapeStlResponse {
+	Status200(Vec<u8>),
+	Status500(String),
+	Raw(axum::response::Response), // Variant for custom responses
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct RotateNode {
-	pub r#axis: Vec<NumberOrExpr>,
-	pub r#deg: NumberOrExpr,
-	pub r#shape: Box<ShapeNode>,
+impl Default for ShapeStlResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct ScaleNode {
-	pub r#factor: NumberOrExpr,
-	pub r#shape: Box<ShapeNode>,
+// Request type for shape_thumbnail_png
+#[derive(Debug)]
+pub struct ShapeThumbnailPngRequest {
+	pub view: Option<String>,
+	pub size: Option<i32>,
+	pub body: Box<ShapeNode>,
+	pub request: axum::http::Request<axum::body::Body>,
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct SelectInput {
-	pub r#default: Option<String>,
-	pub r#horizontal: Option<bool>,
-	pub r#label: String,
-	pub r#options: Vec<SelectOption>,
-	pub r#value: String,
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeThumbnailPngRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct SelectOption {
-	pub r#label: String,
-	pub r#value: String,
+// Response type for shape_thumbnail_png
+#[derive(Debug)]
+pub enum ShapeThumbnailPngResponse {
+	Status200(Vec<u8>),
+	Status400(String),
+	Status500(String),
+	Raw(axum::response::Response), // Variant for custom responses
 }
-
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-#[serde(tag = "op")]
-pub enum ShapeNode {
-	#[serde(rename = "step")]
-	Step(StepNode),
-	#[serde(rename = "union")]
-	Union(UnionShapeNode),
-	#[serde(rename = "intersect")]
-	Intersect(IntersectNode),
-	#[serde(rename = "subtract")]
-	Subtract(SubtractNode),
-	#[serde(rename = "scale")]
-	Scale(ScaleNode),
-	#[serde(rename = "translate")]
-	Translate(TranslateNode),
-	#[serde(rename = "rotate")]
-	Rotate(RotateNode),
-	#[serde(rename = "stretch")]
-	Stretch(StretchNode),
+impl Default for ShapeThumbnailPngResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
 }
-impl Default for ShapeNode {
+// Request type for shape_usdz
+#[derive(Debug)]
+pub struct ShapeUsdzRequest {
+	pub body: Box<ShapeNode>,
+	pub request: axum::http::Request<axum::body::Body>,
+}
+impl AsRef<axum::http::Request<axum::body::Body>> for ShapeUsdzRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
+}
+// Response type for shape_usdz
+#[derive(Debug)]
+pub enum ShapeUsdzResponse {
+	Status200(Vec<u8>),
+	Status500(String),
+	Raw(axum::response::Response), // Variant for custom responses
+}
+impl Default for ShapeUsdzResponse {
 	fn default() -> Self {
-		Self::Step(Default::default())
+		Self::Status200(Default::default())
 	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct ShapeNodeBase {
-	pub r#op: String,
+// Request type for step_job
+#[derive(Debug)]
+pub struct StepJobRequest {
+	pub body: StepJobEvent,
+	pub request: axum::http::Request<axum::body::Body>,
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct ShapeOutput {
-	pub r#annotations: Option<Vec<Box<Annotation>>>,
-	pub r#axisCenter: Option<Vec<Axis>>,
-	pub r#axisGround: Option<Axis>,
-	pub r#axisUp: Option<Axis>,
-	pub r#label: Option<String>,
-	pub r#shape: Box<ShapeNode>,
+impl AsRef<axum::http::Request<axum::body::Body>> for StepJobRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct StepNode {
-	pub r#content_hash: String,
+// Response type for step_job
+#[derive(Debug)]
+pub enum StepJobResponse {
+	Status204,
+	Raw(axum::response::Response), // Variant for custom responses
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct StepStatusBody {
-	pub r#message: String,
-	pub r#progress: i32,
-	pub r#timestamp: i64,
+impl Default for StepJobResponse {
+	fn default() -> Self {
+		Self::Status204
+	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct StretchNode {
-	pub r#cut: Vec<NumberOrExpr>,
-	pub r#delta: Vec<NumberOrExpr>,
-	pub r#shape: Box<ShapeNode>,
+// Request type for step_test
+#[derive(Debug)]
+pub struct StepTestRequest {
+	pub body: Vec<u8>,
+	pub request: axum::http::Request<axum::body::Body>,
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct SubtractNode {
-	pub r#a: Box<ShapeNode>,
-	pub r#b: Box<ShapeNode>,
+impl AsRef<axum::http::Request<axum::body::Body>> for StepTestRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct TextInput {
-	pub r#default: Option<String>,
-	pub r#label: String,
-	pub r#placeholder: Option<String>,
-	pub r#value: String,
-	pub r#variant: Option<String>,
+// Response type for step_test
+#[derive(Debug)]
+pub enum StepTestResponse {
+	Status200(String),
+	Status500(String),
+	Raw(axum::response::Response), // Variant for custom responses
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct TranslateNode {
-	pub r#shape: Box<ShapeNode>,
-	pub r#xyz: Vec<NumberOrExpr>,
+impl Default for StepTestResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
 }
-
-pub type Uuid = uuid::Uuid;
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct UnionShapeNode {
-	pub r#shapes: Vec<Box<ShapeNode>>,
+// Request type for step_upload_url
+#[derive(Debug)]
+pub struct StepUploadUrlRequest {
+	pub request: axum::http::Request<axum::body::Body>,
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct UploadInput {
-	pub r#accept: Option<String>,
-	pub r#label: String,
-	pub r#maxSize: Option<i64>,
+impl AsRef<axum::http::Request<axum::body::Body>> for StepUploadUrlRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
 }
-
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct UploadUrlBody {
-	pub r#id: Uuid,
-	pub r#url: String,
+// Response type for step_upload_url
+#[derive(Debug)]
+pub enum StepUploadUrlResponse {
+	Status200(UploadUrlBody),
+	Raw(axum::response::Response), // Variant for custom responses
 }
-
-use axum;
-use axum::extract::FromRequest;
-
-/// Helper function to generate text responses
-fn text_response(code: axum::http::StatusCode, body: String) -> axum::response::Response {
-	axum::response::Response::builder()
-		.status(code)
-		.header(axum::http::header::CONTENT_TYPE, "text/plain")
-		.body(axum::body::Body::from(body))
-		.unwrap()
+impl Default for StepUploadUrlResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
 }
-
-/// Returns axum::Router with root handlers for all operations registered
-pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
-	instance: std::sync::Arc<S>,
-) -> axum::Router {
-	let router = axum::Router::new();
+// Request type for step_meta
+#[derive(Debug)]
+pub struct StepMetaRequest {
+	pub content_hash: String,
+	pub request: axum::http::Request<axum::body::Body>,
+}
+impl AsRef<axum::http::Request<axum::body::Body>> for StepMetaRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
+}
+// Response type for step_meta
+#[derive(Debug)]
+pub enum StepMetaResponse {
+	Status200(StepMeta),
+	Status404,
+	Raw(axum::response::Response), // Variant for custom responses
+}
+impl Default for StepMetaResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
+}
+// Request type for step_events
+#[derive(Debug)]
+pub struct StepEventsRequest {
+	pub id: Uuid,
+	pub request: axum::http::Request<axum::body::Body>,
+}
+impl AsRef<axum::http::Request<axum::body::Body>> for StepEventsRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
+}
+// Response type for step_events
+#[derive(Debug)]
+pub enum StepEventsResponse {
+	Status200(String),
+	Raw(axum::response::Response), // Variant for custom responses
+}
+impl Default for StepEventsResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
+}
+// Request type for step_execute
+#[derive(Debug)]
+pub struct StepExecuteRequest {
+	pub id: Uuid,
+	pub request: axum::http::Request<axum::body::Body>,
+}
+impl AsRef<axum::http::Request<axum::body::Body>> for StepExecuteRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
+}
+// Response type for step_execute
+#[derive(Debug)]
+pub enum StepExecuteResponse {
+	Status200(StepManifest),
+	Status500(String),
+	Raw(axum::response::Response), // Variant for custom responses
+}
+impl Default for StepExecuteResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
+}
+// Request type for step_result
+#[derive(Debug)]
+pub struct StepResultRequest {
+	pub id: Uuid,
+	pub request: axum::http::Request<axum::body::Body>,
+}
+impl AsRef<axum::http::Request<axum::body::Body>> for StepResultRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
+}
+// Response type for step_result
+#[derive(Debug)]
+pub enum StepResultResponse {
+	Status200(StepManifest),
+	Status404,
+	Raw(axum::response::Response), // Variant for custom responses
+}
+impl Default for StepResultResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
+}
+// Request type for step_status
+#[derive(Debug)]
+pub struct StepStatusRequest {
+	pub id: Uuid,
+	pub request: axum::http::Request<axum::body::Body>,
+}
+impl AsRef<axum::http::Request<axum::body::Body>> for StepStatusRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
+}
+// Response type for step_status
+#[derive(Debug)]
+pub enum StepStatusResponse {
+	Status200(StepStatusBody),
+	Status404,
+	Raw(axum::response::Response), // Variant for custom responses
+}
+impl Default for StepStatusResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
+}
+// Request type for step_submit
+#[derive(Debug)]
+pub struct StepSubmitRequest {
+	pub id: Uuid,
+	pub request: axum::http::Request<axum::body::Body>,
+}
+impl AsRef<axum::http::Request<axum::body::Body>> for StepSubmitRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
+}
+// Response type for step_submit
+#[derive(Debug)]
+pub enum StepSubmitResponse {
+	Status202(String),
+	Status500(String),
+	Raw(axum::response::Response), // Variant for custom responses
+}
+impl Default for StepSubmitResponse {
+	fn default() -> Self {
+		Self::Status202(Default::default())
+	}
+}
+// Request type for version
+#[derive(Debug)]
+pub struct VersionRequest {
+	pub request: axum::http::Request<axum::body::Body>,
+}
+impl AsRef<axum::http::Request<axum::body::Body>> for VersionRequest {
+	fn as_ref(&self) -> &axum::http::Request<axum::body::Body> {
+		&self.request
+	}
+}
+// Response type for version
+#[derive(Debug)]
+pub enum VersionResponse {
+	Status200(String),
+	Raw(axum::response::Response), // Variant for custom responses
+}
+impl Default for VersionResponse {
+	fn default() -> Self {
+		Self::Status200(Default::default())
+	}
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ActionOutput {
+	pub r#disable: Option<serde_json::Value>,
+	pub r#email_bcc: Vec<String>,
+	pub r#email_to: Vec<String>,
+	pub r#label: String,
+	pub r#slack: Vec<String>,
+	pub r#subject: String,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ActionRequest {
+	pub r#action: ActionOutput,
+	pub r#input: HashMap<String, Box<Input>>,
+	pub r#output: Vec<Box<Output>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum Annotation {
+	#[serde(rename = "point")]
+	Point(PointAnnotation),
+	#[serde(rename = "distance")]
+	Distance(DistanceAnnotation),
+}
+impl Default for Annotation {
+	fn default() -> Self {
+		Self::Point(Default::default())
+	}
+}
+
+pub type Axis = String;
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BorderOutput {}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BoundingBox {
+	pub r#max: Vec<f64>,
+	pub r#min: Vec<f64>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct DistanceAnnotation {
+	pub r#end: Vec<f64>,
+	pub r#label: String,
+	pub r#start: Vec<f64>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ForbiddenResponse {}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum Input {
+	#[serde(rename = "upload")]
+	Upload(UploadInput),
+	#[serde(rename = "text")]
+	Text(TextInput),
+	#[serde(rename = "number")]
+	Number(NumberInput),
+	#[serde(rename = "select")]
+	Select(SelectInput),
+}
+impl Default for Input {
+	fn default() -> Self {
+		Self::Upload(Default::default())
+	}
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct IntersectNode {
+	pub r#a: Box<ShapeNode>,
+	pub r#b: Box<ShapeNode>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct MessageOutput {
+	pub r#label: String,
+	pub r#messageType: String,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct NumberConstraintEnum {
+	pub r#enum: Vec<f64>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct NumberConstraintRange {
+	pub r#max: Option<f64>,
+	pub r#min: Option<f64>,
+	pub r#step: Option<f64>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct NumberInput {
+	pub r#constraint: Option<serde_json::Value>,
+	pub r#default: Option<f64>,
+	pub r#label: String,
+	pub r#unit: Option<String>,
+	pub r#value: f64,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum NumberOrExpr {
+	Variant0(f64),
+	Variant1(String),
+}
+impl Default for NumberOrExpr {
+	fn default() -> Self {
+		Self::Variant0(Default::default())
+	}
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum Output {
+	#[serde(rename = "shape")]
+	Shape(ShapeOutput),
+	#[serde(rename = "border")]
+	Border(BorderOutput),
+	#[serde(rename = "message")]
+	Message(MessageOutput),
+	#[serde(rename = "action")]
+	Action(ActionOutput),
+}
+impl Default for Output {
+	fn default() -> Self {
+		Self::Shape(Default::default())
+	}
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct PointAnnotation {
+	pub r#label: String,
+	pub r#position: Vec<f64>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RotateNode {
+	pub r#axis: Vec<NumberOrExpr>,
+	pub r#deg: NumberOrExpr,
+	pub r#shape: Box<ShapeNode>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ScaleNode {
+	pub r#factor: NumberOrExpr,
+	pub r#shape: Box<ShapeNode>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SelectInput {
+	pub r#default: Option<String>,
+	pub r#horizontal: Option<bool>,
+	pub r#label: String,
+	pub r#options: Vec<SelectOption>,
+	pub r#value: String,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SelectOption {
+	pub r#label: String,
+	pub r#value: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "op")]
+pub enum ShapeNode {
+	#[serde(rename = "step")]
+	Step(StepNode),
+	#[serde(rename = "union")]
+	Union(UnionShapeNode),
+	#[serde(rename = "intersect")]
+	Intersect(IntersectNode),
+	#[serde(rename = "subtract")]
+	Subtract(SubtractNode),
+	#[serde(rename = "scale")]
+	Scale(ScaleNode),
+	#[serde(rename = "translate")]
+	Translate(TranslateNode),
+	#[serde(rename = "rotate")]
+	Rotate(RotateNode),
+	#[serde(rename = "stretch")]
+	Stretch(StretchNode),
+}
+impl Default for ShapeNode {
+	fn default() -> Self {
+		Self::Step(Default::default())
+	}
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ShapeNodeBase {
+	pub r#op: String,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ShapeOutput {
+	pub r#annotations: Option<Vec<Box<Annotation>>>,
+	pub r#axisCenter: Option<Vec<Axis>>,
+	pub r#axisGround: Option<Axis>,
+	pub r#axisUp: Option<Axis>,
+	pub r#label: Option<String>,
+	pub r#shape: Box<ShapeNode>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StepAssemblyNode {
+	pub r#children: Vec<StepAssemblyNode>,
+	pub r#name: String,
+	pub r#part_number: String,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StepCheckReport {
+	pub r#healed: Option<bool>,
+	pub r#non_manifold_edges: i32,
+	pub r#open_edges: i32,
+	pub r#self_intersections: Option<i32>,
+	pub r#tiny_edges: i32,
+	pub r#warnings: Vec<String>,
+}
+
+pub type StepErrorCode = String;
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StepJobEvent {
+	pub r#attempt: i32,
+	pub r#id: Uuid,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StepManifest {
+	pub r#content_hash: String,
+	pub r#parts: Vec<StepManifestPart>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StepManifestPart {
+	pub r#bounding_box: Option<BoundingBox>,
+	pub r#content_hash: String,
+	pub r#name: String,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StepMeta {
+	pub r#assembly: Vec<StepAssemblyNode>,
+	pub r#author: Vec<String>,
+	pub r#file_name: Option<String>,
+	pub r#length_unit: Option<String>,
+	pub r#mm_per_unit: Option<f64>,
+	pub r#organization: Vec<String>,
+	pub r#originating_system: Option<String>,
+	pub r#preprocessor_version: Option<String>,
+	pub r#schema: Vec<String>,
+	pub r#time_stamp: Option<String>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StepNode {
+	pub r#content_hash: String,
+}
+
+pub type StepStage = String;
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StepStatusBody {
+	pub r#error_code: Option<StepErrorCode>,
+	pub r#error_message: Option<String>,
+	pub r#message: String,
+	pub r#progress: i32,
+	pub r#stage: Option<StepStage>,
+	pub r#timestamp: i64,
+	pub r#warnings: Option<Vec<String>>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct StretchNode {
+	pub r#cut: Vec<NumberOrExpr>,
+	pub r#delta: Vec<NumberOrExpr>,
+	pub r#shape: Box<ShapeNode>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SubtractNode {
+	pub r#a: Box<ShapeNode>,
+	pub r#b: Box<ShapeNode>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TextInput {
+	pub r#default: Option<String>,
+	pub r#label: String,
+	pub r#placeholder: Option<String>,
+	pub r#value: String,
+	pub r#variant: Option<String>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TranslateNode {
+	pub r#shape: Box<ShapeNode>,
+	pub r#xyz: Vec<NumberOrExpr>,
+}
+
+pub type Uuid = uuid::Uuid;
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct UnionShapeNode {
+	pub r#shapes: Vec<Box<ShapeNode>>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct UploadInput {
+	pub r#accept: Option<String>,
+	pub r#label: String,
+	pub r#maxSize: Option<i64>,
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct UploadUrlBody {
+	pub r#id: Uuid,
+	pub r#url: String,
+}
+
+use axum;
+use axum::extract::FromRequest;
+
+/// Helper function to generate text responses
+fn text_response(code: axum::http::StatusCode, body: String) -> axum::response::Response {
+	axum::response::Response::builder()
+		.status(code)
+		.header(axum::http::header::CONTENT_TYPE, "text/plain")
+		.body(axum::body::Body::from(body))
+		.unwrap()
+}
+
+/// Returns axum::Router with root handlers for all operations registered
+pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
+	instance: std::sync::Arc<S>,
+) -> axum::Router {
+	let router = axum::Router::new();
+	let i = instance.clone();
+	let router = router.route(
+		"/action",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::action_action(
+					i.as_ref(),
+					ActionActionRequest {
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ActionActionResponse::Status204 => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(204).unwrap())
+						.body(axum::body::Body::empty())
+						.unwrap(),
+					ActionActionResponse::Status400(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(400).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ActionActionResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ActionActionResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_gltf_binary(
+					i.as_ref(),
+					ShapeGltfBinaryRequest {
+						r#material: {
+							let v = query.get("material").and_then(|v| v.parse().ok());
+							v
+						},
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapeGltfBinaryResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "model/gltf-binary")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeGltfBinaryResponse::Status400(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(400).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeGltfBinaryResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeGltfBinaryResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape/3mf",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_three_mf(
+					i.as_ref(),
+					ShapeThreeMfRequest {
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapeThreeMfResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "model/3mf")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeThreeMfResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeThreeMfResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape/brep",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_brep(
+					i.as_ref(),
+					ShapeBrepRequest {
+						r#format: {
+							let v = query.get("format").and_then(|v| v.parse().ok());
+							v
+						},
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapeBrepResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "application/octet-stream")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeBrepResponse::Status400(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(400).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeBrepResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeBrepResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape/drawing.svg",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_drawing_svg(
+					i.as_ref(),
+					ShapeDrawingSvgRequest {
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapeDrawingSvgResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "image/svg+xml")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeDrawingSvgResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeDrawingSvgResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape/dxf",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_dxf(
+					i.as_ref(),
+					ShapeDxfRequest {
+						r#normal: {
+							let v = query.get("normal").and_then(|v| v.parse().ok());
+							v
+						},
+						r#offset: {
+							let v = query.get("offset").and_then(|v| v.parse().ok());
+							v
+						},
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapeDxfResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "image/vnd.dxf")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeDxfResponse::Status400(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(400).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeDxfResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeDxfResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape/iges",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_iges(
+					i.as_ref(),
+					ShapeIgesRequest {
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapeIgesResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "model/iges")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeIgesResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeIgesResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape/obj",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_obj(
+					i.as_ref(),
+					ShapeObjRequest {
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapeObjResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "application/zip")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeObjResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeObjResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape/output.glb",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_output_glb(
+					i.as_ref(),
+					ShapeOutputGlbRequest {
+						r#material: {
+							let v = query.get("material").and_then(|v| v.parse().ok());
+							v
+						},
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapeOutputGlbResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "model/gltf-binary")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeOutputGlbResponse::Status400(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(400).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeOutputGlbResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeOutputGlbResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape/ply",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_ply(
+					i.as_ref(),
+					ShapePlyRequest {
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapePlyResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "model/x-ply")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapePlyResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapePlyResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
 	let i = instance.clone();
 	let router = router.route(
-		"/action",
+		"/shape/step",
 		axum::routing::post(
 			|path: axum::extract::Path<HashMap<String, String>>,
 			 query: axum::extract::Query<HashMap<String, String>>,
 			 header: axum::http::HeaderMap,
 			 request: axum::http::Request<axum::body::Body>| async move {
 				let (parts, body) = request.into_parts();
-				let ret = S::action_action(
+				let ret = S::shape_step(
 					i.as_ref(),
-					ActionActionRequest {
+					ShapeStepRequest {
+						r#schema: {
+							let v = query.get("schema").and_then(|v| v.parse().ok());
+							v
+						},
+						r#unit: {
+							let v = query.get("unit").and_then(|v| v.parse().ok());
+							v
+						},
+						r#productName: {
+							let v = query.get("productName").and_then(|v| v.parse().ok());
+							v
+						},
+						r#partNumber: {
+							let v = query.get("partNumber").and_then(|v| v.parse().ok());
+							v
+						},
 						body: match axum::body::to_bytes(body, usize::MAX)
 							.await
 							.map_err(|v| format!("{v:?}"))
@@ -569,37 +1562,42 @@ pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
 				)
 				.await;
 				match ret {
-					ActionActionResponse::Status204 => axum::response::Response::builder()
-						.status(axum::http::StatusCode::from_u16(204).unwrap())
-						.body(axum::body::Body::empty())
+					ShapeStepResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "model/step")
+						.body(axum::body::Body::from(v))
 						.unwrap(),
-					ActionActionResponse::Status400(v) => axum::response::Response::builder()
+					ShapeStepResponse::Status400(v) => axum::response::Response::builder()
 						.status(axum::http::StatusCode::from_u16(400).unwrap())
 						.header(axum::http::header::CONTENT_TYPE, "text/plain")
 						.body(axum::body::Body::from(v))
 						.unwrap(),
-					ActionActionResponse::Status500(v) => axum::response::Response::builder()
+					ShapeStepResponse::Status500(v) => axum::response::Response::builder()
 						.status(axum::http::StatusCode::from_u16(500).unwrap())
 						.header(axum::http::header::CONTENT_TYPE, "text/plain")
 						.body(axum::body::Body::from(v))
 						.unwrap(),
-					ActionActionResponse::Raw(v) => v,
+					ShapeStepResponse::Raw(v) => v,
 				}
 			},
 		),
 	);
 	let i = instance.clone();
 	let router = router.route(
-		"/shape",
+		"/shape/stl",
 		axum::routing::post(
 			|path: axum::extract::Path<HashMap<String, String>>,
 			 query: axum::extract::Query<HashMap<String, String>>,
 			 header: axum::http::HeaderMap,
 			 request: axum::http::Request<axum::body::Body>| async move {
 				let (parts, body) = request.into_parts();
-				let ret = S::shape_gltf_binary(
+				let ret = S::shape_stl(
 					i.as_ref(),
-					ShapeGltfBinaryRequest {
+					ShapeStlRequest {
+						r#ascii: {
+							let v = query.get("ascii").and_then(|v| v.parse().ok());
+							v
+						},
 						body: match axum::body::to_bytes(body, usize::MAX)
 							.await
 							.map_err(|v| format!("{v:?}"))
@@ -613,33 +1611,41 @@ pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
 				)
 				.await;
 				match ret {
-					ShapeGltfBinaryResponse::Status200(v) => axum::response::Response::builder()
+					ShapeStlResponse::Status200(v) => axum::response::Response::builder()
 						.status(axum::http::StatusCode::from_u16(200).unwrap())
-						.header(axum::http::header::CONTENT_TYPE, "model/gltf-binary")
+						.header(axum::http::header::CONTENT_TYPE, "model/stl")
 						.body(axum::body::Body::from(v))
 						.unwrap(),
-					ShapeGltfBinaryResponse::Status500(v) => axum::response::Response::builder()
+					ShapeStlResponse::Status500(v) => axum::response::Response::builder()
 						.status(axum::http::StatusCode::from_u16(500).unwrap())
 						.header(axum::http::header::CONTENT_TYPE, "text/plain")
 						.body(axum::body::Body::from(v))
 						.unwrap(),
-					ShapeGltfBinaryResponse::Raw(v) => v,
+					ShapeStlResponse::Raw(v) => v,
 				}
 			},
 		),
 	);
 	let i = instance.clone();
 	let router = router.route(
-		"/shape/step",
+		"/shape/thumbnail.png",
 		axum::routing::post(
 			|path: axum::extract::Path<HashMap<String, String>>,
 			 query: axum::extract::Query<HashMap<String, String>>,
 			 header: axum::http::HeaderMap,
 			 request: axum::http::Request<axum::body::Body>| async move {
 				let (parts, body) = request.into_parts();
-				let ret = S::shape_step(
+				let ret = S::shape_thumbnail_png(
 					i.as_ref(),
-					ShapeStepRequest {
+					ShapeThumbnailPngRequest {
+						r#view: {
+							let v = query.get("view").and_then(|v| v.parse().ok());
+							v
+						},
+						r#size: {
+							let v = query.get("size").and_then(|v| v.parse().ok());
+							v
+						},
 						body: match axum::body::to_bytes(body, usize::MAX)
 							.await
 							.map_err(|v| format!("{v:?}"))
@@ -653,17 +1659,96 @@ pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
 				)
 				.await;
 				match ret {
-					ShapeStepResponse::Status200(v) => axum::response::Response::builder()
+					ShapeThumbnailPngResponse::Status200(v) => axum::response::Response::builder()
 						.status(axum::http::StatusCode::from_u16(200).unwrap())
-						.header(axum::http::header::CONTENT_TYPE, "model/step")
+						.header(axum::http::header::CONTENT_TYPE, "image/png")
 						.body(axum::body::Body::from(v))
 						.unwrap(),
-					ShapeStepResponse::Status500(v) => axum::response::Response::builder()
+					ShapeThumbnailPngResponse::Status400(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(400).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeThumbnailPngResponse::Status500(v) => axum::response::Response::builder()
 						.status(axum::http::StatusCode::from_u16(500).unwrap())
 						.header(axum::http::header::CONTENT_TYPE, "text/plain")
 						.body(axum::body::Body::from(v))
 						.unwrap(),
-					ShapeStepResponse::Raw(v) => v,
+					ShapeThumbnailPngResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/shape/usdz",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::shape_usdz(
+					i.as_ref(),
+					ShapeUsdzRequest {
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					ShapeUsdzResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "model/vnd.usdz+zip")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeUsdzResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					ShapeUsdzResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/step/job",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::step_job(
+					i.as_ref(),
+					StepJobRequest {
+						body: match axum::body::to_bytes(body, usize::MAX)
+							.await
+							.map_err(|v| format!("{v:?}"))
+							.and_then(|v| serde_json::from_slice(&v).map_err(|v| v.to_string()))
+						{
+							Ok(v) => v,
+							Err(v) => return text_response(axum::http::StatusCode::BAD_REQUEST, v),
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					StepJobResponse::Status204 => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(204).unwrap())
+						.body(axum::body::Body::empty())
+						.unwrap(),
+					StepJobResponse::Raw(v) => v,
 				}
 			},
 		),
@@ -739,6 +1824,90 @@ pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
 		),
 	);
 	let i = instance.clone();
+	let router = router.route(
+		"/step/{content_hash}/meta",
+		axum::routing::get(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::step_meta(
+					i.as_ref(),
+					StepMetaRequest {
+						r#content_hash: {
+							let v = path.get("content_hash").and_then(|v| v.parse().ok());
+							match v {
+								Some(v) => v,
+								None => {
+									return text_response(
+										axum::http::StatusCode::from_u16(400).unwrap(),
+										format!("parse error: content_hash in path={:?}", path),
+									);
+								}
+							}
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					StepMetaResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "application/json")
+						.body(axum::body::Body::from(
+							serde_json::to_vec_pretty(&v).expect("error serialize response json"),
+						))
+						.unwrap(),
+					StepMetaResponse::Status404 => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(404).unwrap())
+						.body(axum::body::Body::empty())
+						.unwrap(),
+					StepMetaResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
+	let router = router.route(
+		"/step/{id}/events",
+		axum::routing::get(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::step_events(
+					i.as_ref(),
+					StepEventsRequest {
+						r#id: {
+							let v = path.get("id").and_then(|v| v.parse().ok());
+							match v {
+								Some(v) => v,
+								None => {
+									return text_response(
+										axum::http::StatusCode::from_u16(400).unwrap(),
+										format!("parse error: id in path={:?}", path),
+									);
+								}
+							}
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					StepEventsResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/event-stream")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					StepEventsResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
 	let router = router.route(
 		"/step/{id}/execute",
 		axum::routing::post(
@@ -769,8 +1938,10 @@ pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
 				match ret {
 					StepExecuteResponse::Status200(v) => axum::response::Response::builder()
 						.status(axum::http::StatusCode::from_u16(200).unwrap())
-						.header(axum::http::header::CONTENT_TYPE, "text/plain")
-						.body(axum::body::Body::from(v))
+						.header(axum::http::header::CONTENT_TYPE, "application/json")
+						.body(axum::body::Body::from(
+							serde_json::to_vec_pretty(&v).expect("error serialize response json"),
+						))
 						.unwrap(),
 					StepExecuteResponse::Status500(v) => axum::response::Response::builder()
 						.status(axum::http::StatusCode::from_u16(500).unwrap())
@@ -783,6 +1954,51 @@ pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
 		),
 	);
 	let i = instance.clone();
+	let router = router.route(
+		"/step/{id}/result",
+		axum::routing::get(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::step_result(
+					i.as_ref(),
+					StepResultRequest {
+						r#id: {
+							let v = path.get("id").and_then(|v| v.parse().ok());
+							match v {
+								Some(v) => v,
+								None => {
+									return text_response(
+										axum::http::StatusCode::from_u16(400).unwrap(),
+										format!("parse error: id in path={:?}", path),
+									);
+								}
+							}
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					StepResultResponse::Status200(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(200).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "application/json")
+						.body(axum::body::Body::from(
+							serde_json::to_vec_pretty(&v).expect("error serialize response json"),
+						))
+						.unwrap(),
+					StepResultResponse::Status404 => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(404).unwrap())
+						.body(axum::body::Body::empty())
+						.unwrap(),
+					StepResultResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
 	let router = router.route(
 		"/step/{id}/status",
 		axum::routing::get(
@@ -828,6 +2044,50 @@ pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
 		),
 	);
 	let i = instance.clone();
+	let router = router.route(
+		"/step/{id}/submit",
+		axum::routing::post(
+			|path: axum::extract::Path<HashMap<String, String>>,
+			 query: axum::extract::Query<HashMap<String, String>>,
+			 header: axum::http::HeaderMap,
+			 request: axum::http::Request<axum::body::Body>| async move {
+				let (parts, body) = request.into_parts();
+				let ret = S::step_submit(
+					i.as_ref(),
+					StepSubmitRequest {
+						r#id: {
+							let v = path.get("id").and_then(|v| v.parse().ok());
+							match v {
+								Some(v) => v,
+								None => {
+									return text_response(
+										axum::http::StatusCode::from_u16(400).unwrap(),
+										format!("parse error: id in path={:?}", path),
+									);
+								}
+							}
+						},
+						request: axum::http::Request::from_parts(parts.clone(), Default::default()),
+					},
+				)
+				.await;
+				match ret {
+					StepSubmitResponse::Status202(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(202).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					StepSubmitResponse::Status500(v) => axum::response::Response::builder()
+						.status(axum::http::StatusCode::from_u16(500).unwrap())
+						.header(axum::http::header::CONTENT_TYPE, "text/plain")
+						.body(axum::body::Body::from(v))
+						.unwrap(),
+					StepSubmitResponse::Raw(v) => v,
+				}
+			},
+		),
+	);
+	let i = instance.clone();
 	let router = router.route(
 		"/version",
 		axum::routing::get(
@@ -855,7 +2115,7 @@ pub fn axum_router_operations<S: ApiInterface + Sync + Send + 'static>(
 		),
 	);
 	let router = router.route("/openapi.json", axum::routing::get(|| async move{
-			r###"{"components":{"schemas":{"ActionOutput":{"properties":{"disable":{"anyOf":[{"type":"boolean"},{"type":"string"}],"description":"false=有効, true=無効, 文字列=無効理由 (例: \"幅が大きすぎます\")"},"email_bcc":{"items":{"type":"string"},"type":"array"},"email_to":{"items":{"type":"string"},"type":"array"},"label":{"type":"string"},"slack":{"items":{"type":"string"},"type":"array"},"subject":{"type":"string"},"type":{"enum":["action"],"type":"string"}},"required":["type","label","subject","email_to","email_bcc","slack"],"type":"object"},"ActionRequest":{"properties":{"action":{"$ref":"#/components/schemas/ActionOutput"},"input":{"additionalProperties":{"$ref":"#/components/schemas/Input"},"description":"Input の定義辞書（キーはフォームのフィールド名）","type":"object"},"output":{"description":"ボタン押下時点で lambda が返した出力（メール本文の生成に使う）","items":{"$ref":"#/components/schemas/Output"},"type":"array"}},"required":["input","output","action"],"type":"object"},"Annotation":{"anyOf":[{"$ref":"#/components/schemas/PointAnnotation"},{"$ref":"#/components/schemas/DistanceAnnotation"}]},"Axis":{"enum":["X","Y","Z"],"type":"string"},"BorderOutput":{"properties":{"type":{"enum":["border"],"type":"string"}},"required":["type"],"type":"object"},"DistanceAnnotation":{"properties":{"end":{"description":"計測終了点 [x, y, z] (mm)","items":{"format":"double","type":"number"},"type":"array"},"label":{"type":"string"},"start":{"description":"計測開始点 [x, y, z] (mm)","items":{"format":"double","type":"number"},"type":"array"},"type":{"enum":["distance"],"type":"string"}},"required":["type","start","end","label"],"type":"object"},"ForbiddenResponse":{"type":"object"},"Input":{"anyOf":[{"$ref":"#/components/schemas/UploadInput"},{"$ref":"#/components/schemas/TextInput"},{"$ref":"#/components/schemas/NumberInput"},{"$ref":"#/components/schemas/SelectInput"}]},"IntersectNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"ブーリアン共通部分 (BRepAlgoAPI_Common)","properties":{"a":{"$ref":"#/components/schemas/ShapeNode"},"b":{"$ref":"#/components/schemas/ShapeNode"},"op":{"enum":["intersect"],"type":"string"}},"required":["op","a","b"],"type":"object"},"MessageOutput":{"properties":{"label":{"type":"string"},"messageType":{"enum":["warning","error","text","info"],"type":"string"},"type":{"enum":["message"],"type":"string"}},"required":["type","label","messageType"],"type":"object"},"NumberConstraintEnum":{"properties":{"enum":{"items":{"format":"double","type":"number"},"type":"array"}},"required":["enum"],"type":"object"},"NumberConstraintRange":{"properties":{"max":{"format":"double","type":"number"},"min":{"format":"double","type":"number"},"step":{"format":"double","type":"number"}},"type":"object"},"NumberInput":{"properties":{"constraint":{"anyOf":[{"$ref":"#/components/schemas/NumberConstraintRange"},{"$ref":"#/components/schemas/NumberConstraintEnum"}]},"default":{"format":"double","type":"number"},"label":{"type":"string"},"type":{"enum":["number"],"type":"string"},"unit":{"type":"string"},"value":{"format":"double","type":"number"}},"required":["type","label","value"],"type":"object"},"NumberOrExpr":{"anyOf":[{"format":"double","type":"number"},{"type":"string"}],"description":"数値定数または $式 (例: 100.0, \"$width\", \"$width * 0.5 + 50\")"},"Output":{"anyOf":[{"$ref":"#/components/schemas/ShapeOutput"},{"$ref":"#/components/schemas/BorderOutput"},{"$ref":"#/components/schemas/MessageOutput"},{"$ref":"#/components/schemas/ActionOutput"}]},"PointAnnotation":{"properties":{"label":{"type":"string"},"position":{"description":"モデル座標系の点 [x, y, z] (mm)","items":{"format":"double","type":"number"},"type":"array"},"type":{"enum":["point"],"type":"string"}},"required":["type","position","label"],"type":"object"},"RotateNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"回転","properties":{"axis":{"description":"回転軸ベクトル [ax, ay, az]","items":{"$ref":"#/components/schemas/NumberOrExpr"},"type":"array"},"deg":{"allOf":[{"$ref":"#/components/schemas/NumberOrExpr"}],"description":"回転角度 (度)"},"op":{"enum":["rotate"],"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"}},"required":["op","shape","axis","deg"],"type":"object"},"ScaleNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"一様拡大縮小","properties":{"factor":{"$ref":"#/components/schemas/NumberOrExpr"},"op":{"enum":["scale"],"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"}},"required":["op","shape","factor"],"type":"object"},"SelectInput":{"properties":{"default":{"type":"string"},"horizontal":{"description":"trueにすると選択肢を横並びにする（デフォルト: 縦並び）","type":"boolean"},"label":{"type":"string"},"options":{"items":{"$ref":"#/components/schemas/SelectOption"},"type":"array"},"type":{"enum":["select"],"type":"string"},"value":{"type":"string"}},"required":["type","label","options","value"],"type":"object"},"SelectOption":{"properties":{"label":{"type":"string"},"value":{"type":"string"}},"required":["value","label"],"type":"object"},"ShapeNode":{"anyOf":[{"$ref":"#/components/schemas/StepNode"},{"$ref":"#/components/schemas/UnionShapeNode"},{"$ref":"#/components/schemas/IntersectNode"},{"$ref":"#/components/schemas/SubtractNode"},{"$ref":"#/components/schemas/ScaleNode"},{"$ref":"#/components/schemas/TranslateNode"},{"$ref":"#/components/schemas/RotateNode"},{"$ref":"#/components/schemas/StretchNode"}],"description":"★ここが主役：discriminated union を \"ShapeNode\" として定義\nこれが OpenAPI で oneOf + discriminator になりやすい"},"ShapeNodeBase":{"description":"形状演算ノードの共通フィールド（任意）\n※これは OpenAPI の oneOf 生成のために必須ではないが、共通項を置きたい場合に便利","properties":{"op":{"type":"string"}},"required":["op"],"type":"object"},"ShapeOutput":{"properties":{"annotations":{"items":{"$ref":"#/components/schemas/Annotation"},"type":"array"},"axisCenter":{"description":"中心揃えする軸の一覧","items":{"$ref":"#/components/schemas/Axis"},"type":"array"},"axisGround":{"allOf":[{"$ref":"#/components/schemas/Axis"}],"description":"バウンディングボックスの最小値をゼロに揃える軸"},"axisUp":{"allOf":[{"$ref":"#/components/schemas/Axis"}],"description":"モデル座標系の上方向軸"},"label":{"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"},"type":{"enum":["shape"],"type":"string"}},"required":["type","shape"],"type":"object"},"StepNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"STEPファイルの読み込み","properties":{"content_hash":{"description":"STEPファイルのsha256ハッシュ値 (hex64)","type":"string"},"op":{"enum":["step"],"type":"string"}},"required":["op","content_hash"],"type":"object"},"StepStatusBody":{"properties":{"message":{"type":"string"},"progress":{"format":"int32","type":"integer"},"timestamp":{"format":"int64","type":"integer"}},"required":["timestamp","progress","message"],"type":"object"},"StretchNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"伸縮: 切断面で形状を分割して指定方向に伸ばす","properties":{"cut":{"description":"切断面の座標 [cx, cy, cz] (mm)","items":{"$ref":"#/components/schemas/NumberOrExpr"},"type":"array"},"delta":{"description":"各軸方向の伸縮量 [dx, dy, dz] (mm)","items":{"$ref":"#/components/schemas/NumberOrExpr"},"type":"array"},"op":{"enum":["stretch"],"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"}},"required":["op","shape","cut","delta"],"type":"object"},"SubtractNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"ブーリアン差演算: a から b をくり抜く (BRepAlgoAPI_Cut)","properties":{"a":{"$ref":"#/components/schemas/ShapeNode"},"b":{"$ref":"#/components/schemas/ShapeNode"},"op":{"enum":["subtract"],"type":"string"}},"required":["op","a","b"],"type":"object"},"TextInput":{"properties":{"default":{"type":"string"},"label":{"type":"string"},"placeholder":{"type":"string"},"type":{"enum":["text"],"type":"string"},"value":{"type":"string"},"variant":{"enum":["text","area","email"],"type":"string"}},"required":["type","label","value"],"type":"object"},"TranslateNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"平行移動","properties":{"op":{"enum":["translate"],"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"},"xyz":{"description":"移動量 [x, y, z] (mm)","items":{"$ref":"#/components/schemas/NumberOrExpr"},"type":"array"}},"required":["op","shape","xyz"],"type":"object"},"UUID":{"format":"uuid","type":"string"},"UnionShapeNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"ブーリアン合体 (BRepAlgoAPI_Fuse)","properties":{"op":{"enum":["union"],"type":"string"},"shapes":{"items":{"$ref":"#/components/schemas/ShapeNode"},"type":"array"}},"required":["op","shapes"],"type":"object"},"UploadInput":{"properties":{"accept":{"type":"string"},"label":{"type":"string"},"maxSize":{"format":"int64","type":"integer"},"type":{"enum":["upload"],"type":"string"}},"required":["type","label"],"type":"object"},"UploadUrlBody":{"properties":{"id":{"$ref":"#/components/schemas/UUID"},"url":{"type":"string"}},"required":["id","url"],"type":"object"}}},"info":{"title":"Lambda360 API","version":"0.0.0"},"openapi":"3.0.0","paths":{"/action":{"post":{"description":"inputの値とbuttonの宛先を受け取ってメール・Slack送信を行う","operationId":"Action_action","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ActionRequest"}}},"required":true},"responses":{"204":{"description":"There is no content to send for this request, but the headers may be useful. "},"400":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The server could not understand the request due to invalid syntax."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape":{"post":{"description":"ShapeNode を受け取り、演算結果を GLB (GLTF Binary) として返す","operationId":"Shape_gltf_binary","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"model/gltf-binary":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/step":{"post":{"operationId":"Shape_step","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"model/step":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/step/test":{"post":{"description":"ローカルテスト用（--features debug 時のみ有効）：\nファイルを受け取り、upload_url → S3アップロード → execute → status確認 の\n一連のフローを実行してcontent_hashを返します。","operationId":"Step_test","requestBody":{"content":{"application/octet-stream":{"schema":{"format":"binary","type":"string"}}},"required":true},"responses":{"200":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/step/upload":{"post":{"description":"アップロード用のURLとIDを取得します。\nフロントエンドはこのURLに対して実際のファイルをアップロードします。","operationId":"Step_upload_url","responses":{"200":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/UploadUrlBody"}}},"description":"The request has succeeded."}}}},"/step/{id}/execute":{"post":{"description":"指定した ID のファイルの変換処理（STEP -\u003e BREP）を実行します。\nダウンロード・変換・アップロードがすべて完了したときに 200 を返します。\n失敗した場合は 500 とエラーメッセージを返します。\n進捗は処理中も /step/{id}/status で確認できます。\nレスポンスボディは変換後ファイルの content_hash です。","operationId":"Step_execute","parameters":[{"in":"path","name":"id","required":true,"schema":{"$ref":"#/components/schemas/UUID"},"style":"simple"}],"responses":{"200":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/step/{id}/status":{"get":{"description":"変換処理の最新進捗を返します。\n- progress 100: 正常終了\n- progress 101以上: 異常終了\n変換がまだ開始されていない場合は 404 を返します。","operationId":"Step_status","parameters":[{"in":"path","name":"id","required":true,"schema":{"$ref":"#/components/schemas/UUID"},"style":"simple"}],"responses":{"200":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/StepStatusBody"}}},"description":"The request has succeeded."},"404":{"description":"The server cannot find the requested resource."}}}},"/version":{"get":{"description":"このAPIサーバーのバージョンと使用しているS3バケット名を返します。","operationId":"version","responses":{"200":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The request has succeeded."}}}}},"servers":[{"description":"Main server","url":"/api","variables":{}}]}"###
+			r###"{"components":{"schemas":{"ActionOutput":{"properties":{"disable":{"anyOf":[{"type":"boolean"},{"type":"string"}],"description":"false=有効, true=無効, 文字列=無効理由 (例: \"幅が大きすぎます\")"},"email_bcc":{"items":{"type":"string"},"type":"array"},"email_to":{"items":{"type":"string"},"type":"array"},"label":{"type":"string"},"slack":{"items":{"type":"string"},"type":"array"},"subject":{"type":"string"},"type":{"enum":["action"],"type":"string"}},"required":["type","label","subject","email_to","email_bcc","slack"],"type":"object"},"ActionRequest":{"properties":{"action":{"$ref":"#/components/schemas/ActionOutput"},"input":{"additionalProperties":{"$ref":"#/components/schemas/Input"},"description":"Input の定義辞書（キーはフォームのフィールド名）","type":"object"},"output":{"description":"ボタン押下時点で lambda が返した出力（メール本文の生成に使う）","items":{"$ref":"#/components/schemas/Output"},"type":"array"}},"required":["input","output","action"],"type":"object"},"Annotation":{"anyOf":[{"$ref":"#/components/schemas/PointAnnotation"},{"$ref":"#/components/schemas/DistanceAnnotation"}]},"Axis":{"enum":["X","Y","Z"],"type":"string"},"BorderOutput":{"properties":{"type":{"enum":["border"],"type":"string"}},"required":["type"],"type":"object"},"BoundingBox":{"description":"軸平行バウンディングボックス (mm)","properties":{"max":{"description":"[x, y, z]","items":{"format":"double","type":"number"},"type":"array"},"min":{"description":"[x, y, z]","items":{"format":"double","type":"number"},"type":"array"}},"required":["min","max"],"type":"object"},"DistanceAnnotation":{"properties":{"end":{"description":"計測終了点 [x, y, z] (mm)","items":{"format":"double","type":"number"},"type":"array"},"label":{"type":"string"},"start":{"description":"計測開始点 [x, y, z] (mm)","items":{"format":"double","type":"number"},"type":"array"},"type":{"enum":["distance"],"type":"string"}},"required":["type","start","end","label"],"type":"object"},"ForbiddenResponse":{"type":"object"},"Input":{"anyOf":[{"$ref":"#/components/schemas/UploadInput"},{"$ref":"#/components/schemas/TextInput"},{"$ref":"#/components/schemas/NumberInput"},{"$ref":"#/components/schemas/SelectInput"}]},"IntersectNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"ブーリアン共通部分 (BRepAlgoAPI_Common)","properties":{"a":{"$ref":"#/components/schemas/ShapeNode"},"b":{"$ref":"#/components/schemas/ShapeNode"},"op":{"enum":["intersect"],"type":"string"}},"required":["op","a","b"],"type":"object"},"MessageOutput":{"properties":{"label":{"type":"string"},"messageType":{"enum":["warning","error","text","info"],"type":"string"},"type":{"enum":["message"],"type":"string"}},"required":["type","label","messageType"],"type":"object"},"NumberConstraintEnum":{"properties":{"enum":{"items":{"format":"double","type":"number"},"type":"array"}},"required":["enum"],"type":"object"},"NumberConstraintRange":{"properties":{"max":{"format":"double","type":"number"},"min":{"format":"double","type":"number"},"step":{"format":"double","type":"number"}},"type":"object"},"NumberInput":{"properties":{"constraint":{"anyOf":[{"$ref":"#/components/schemas/NumberConstraintRange"},{"$ref":"#/components/schemas/NumberConstraintEnum"}]},"default":{"format":"double","type":"number"},"label":{"type":"string"},"type":{"enum":["number"],"type":"string"},"unit":{"type":"string"},"value":{"format":"double","type":"number"}},"required":["type","label","value"],"type":"object"},"NumberOrExpr":{"anyOf":[{"format":"double","type":"number"},{"type":"string"}],"description":"数値定数または $式 (例: 100.0, \"$width\", \"$width * 0.5 + 50\")"},"Output":{"anyOf":[{"$ref":"#/components/schemas/ShapeOutput"},{"$ref":"#/components/schemas/BorderOutput"},{"$ref":"#/components/schemas/MessageOutput"},{"$ref":"#/components/schemas/ActionOutput"}]},"PointAnnotation":{"properties":{"label":{"type":"string"},"position":{"description":"モデル座標系の点 [x, y, z] (mm)","items":{"format":"double","type":"number"},"type":"array"},"type":{"enum":["point"],"type":"string"}},"required":["type","position","label"],"type":"object"},"RotateNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"回転","properties":{"axis":{"description":"回転軸ベクトル [ax, ay, az]","items":{"$ref":"#/components/schemas/NumberOrExpr"},"type":"array"},"deg":{"allOf":[{"$ref":"#/components/schemas/NumberOrExpr"}],"description":"回転角度 (度)"},"op":{"enum":["rotate"],"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"}},"required":["op","shape","axis","deg"],"type":"object"},"ScaleNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"一様拡大縮小","properties":{"factor":{"$ref":"#/components/schemas/NumberOrExpr"},"op":{"enum":["scale"],"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"}},"required":["op","shape","factor"],"type":"object"},"SelectInput":{"properties":{"default":{"type":"string"},"horizontal":{"description":"trueにすると選択肢を横並びにする（デフォルト: 縦並び）","type":"boolean"},"label":{"type":"string"},"options":{"items":{"$ref":"#/components/schemas/SelectOption"},"type":"array"},"type":{"enum":["select"],"type":"string"},"value":{"type":"string"}},"required":["type","label","options","value"],"type":"object"},"SelectOption":{"properties":{"label":{"type":"string"},"value":{"type":"string"}},"required":["value","label"],"type":"object"},"ShapeNode":{"anyOf":[{"$ref":"#/components/schemas/StepNode"},{"$ref":"#/components/schemas/UnionShapeNode"},{"$ref":"#/components/schemas/IntersectNode"},{"$ref":"#/components/schemas/SubtractNode"},{"$ref":"#/components/schemas/ScaleNode"},{"$ref":"#/components/schemas/TranslateNode"},{"$ref":"#/components/schemas/RotateNode"},{"$ref":"#/components/schemas/StretchNode"}],"description":"★ここが主役：discriminated union を \"ShapeNode\" として定義\nこれが OpenAPI で oneOf + discriminator になりやすい"},"ShapeNodeBase":{"description":"形状演算ノードの共通フィールド（任意）\n※これは OpenAPI の oneOf 生成のために必須ではないが、共通項を置きたい場合に便利","properties":{"op":{"type":"string"}},"required":["op"],"type":"object"},"ShapeOutput":{"properties":{"annotations":{"items":{"$ref":"#/components/schemas/Annotation"},"type":"array"},"axisCenter":{"description":"中心揃えする軸の一覧","items":{"$ref":"#/components/schemas/Axis"},"type":"array"},"axisGround":{"allOf":[{"$ref":"#/components/schemas/Axis"}],"description":"バウンディングボックスの最小値をゼロに揃える軸"},"axisUp":{"allOf":[{"$ref":"#/components/schemas/Axis"}],"description":"モデル座標系の上方向軸"},"label":{"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"},"type":{"enum":["shape"],"type":"string"}},"required":["type","shape"],"type":"object"},"StepAssemblyNode":{"description":"STEP の製品構造の 1 ノード（PRODUCT_DEFINITION）","properties":{"children":{"description":"NEXT_ASSEMBLY_USAGE_OCCURRENCE で配置された子。同じ部品を複数配置したときは配置の数だけ並ぶ","items":{"$ref":"#/components/schemas/StepAssemblyNode"},"type":"array"},"name":{"description":"PRODUCT の name","type":"string"},"part_number":{"description":"PRODUCT の id（品番）","type":"string"}},"required":["part_number","name","children"],"type":"object"},"StepCheckReport":{"description":"変換した形状の検査結果（bucket_main の {content_hash}.check.json）","properties":{"healed":{"description":"修復 (STEP_HEAL=1。隙間の縫合と ShapeFix) した形状を保存したか。修復を試みていなければ省略","type":"boolean"},"non_manifold_edges":{"description":"3 つ以上の三角形で共有される辺の数（非多様体）","format":"int32","type":"integer"},"open_edges":{"description":"1 つの三角形にしか使われない辺の数（開いたシェル）","format":"int32","type":"integer"},"self_intersections":{"description":"交差している三角形の組の数。形状が複雑で検査しなかったときは省略","format":"int32","type":"integer"},"tiny_edges":{"description":"0.01 mm 未満のエッジの数","format":"int32","type":"integer"},"warnings":{"description":"問題の説明（日本語）。問題がなければ空","items":{"type":"string"},"type":"array"}},"required":["open_edges","non_manifold_edges","tiny_edges","warnings"],"type":"object"},"StepErrorCode":{"description":"変換失敗の種類","enum":["download_failed","parse_failed","brep_write_failed","upload_failed","too_large","unsupported_format"],"type":"string"},"StepJobEvent":{"description":"変換ジョブ（/step/job のイベント）","properties":{"attempt":{"description":"1 始まりの試行回数","format":"int32","type":"integer"},"id":{"allOf":[{"$ref":"#/components/schemas/UUID"}],"description":"アップロード ID"}},"required":["id","attempt"],"type":"object"},"StepManifest":{"description":"execute の結果","properties":{"content_hash":{"description":"アップロードされたファイル（単一ファイルまたは ZIP）の content_hash","type":"string"},"parts":{"description":"変換したファイルの一覧。単一ファイルのアップロードでは 1 件","items":{"$ref":"#/components/schemas/StepManifestPart"},"type":"array"}},"required":["content_hash","parts"],"type":"object"},"StepManifestPart":{"description":"変換した 1 ファイル分の情報","properties":{"bounding_box":{"allOf":[{"$ref":"#/components/schemas/BoundingBox"}],"description":"形状が空のときは省略"},"content_hash":{"description":"StepNode の content_hash に指定する値","type":"string"},"name":{"description":"ZIP 内のファイル名（単一ファイルのアップロードでは空文字）","type":"string"}},"required":["name","content_hash"],"type":"object"},"StepMeta":{"description":"アップロードされた STEP のヘッダと製品構造","properties":{"assembly":{"description":"製品構造の根。アセンブリでなければ部品が 1 件","items":{"$ref":"#/components/schemas/StepAssemblyNode"},"type":"array"},"author":{"description":"FILE_NAME の author","items":{"type":"string"},"type":"array"},"file_name":{"description":"FILE_NAME の name（CAD 上のファイル名）","type":"string"},"length_unit":{"description":"元ファイルの長さ単位（mm / cm / m / inch など）。見つからなければ省略","type":"string"},"mm_per_unit":{"description":"length_unit の 1 単位あたりの mm（inch なら 25.4）。保存した BRep は mm に換算済み","format":"double","type":"number"},"organization":{"description":"FILE_NAME の organization","items":{"type":"string"},"type":"array"},"originating_system":{"description":"FILE_NAME の originating_system（作成した CAD システム）","type":"string"},"preprocessor_version":{"description":"FILE_NAME の preprocessor_version（STEP を書き出したトランスレータ）","type":"string"},"schema":{"description":"FILE_SCHEMA（AP203 / AP214 / AP242 など）","items":{"type":"string"},"type":"array"},"time_stamp":{"description":"FILE_NAME の time_stamp","type":"string"}},"required":["author","organization","schema","assembly"],"type":"object"},"StepNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"STEPファイルの読み込み","properties":{"content_hash":{"description":"STEPファイルのsha256ハッシュ値 (hex64)","type":"string"},"op":{"enum":["step"],"type":"string"}},"required":["op","content_hash"],"type":"object"},"StepStage":{"description":"変換処理の段階","enum":["download","detect","read","check","write","upload","preview","cached"],"type":"string"},"StepStatusBody":{"properties":{"error_code":{"allOf":[{"$ref":"#/components/schemas/StepErrorCode"}],"description":"失敗時 (progress 101 以上) のみ"},"error_message":{"description":"失敗時 (progress 101 以上) のみ。英語の説明","type":"string"},"message":{"description":"進捗・失敗の詳細（日本語）","type":"string"},"progress":{"format":"int32","type":"integer"},"stage":{"allOf":[{"$ref":"#/components/schemas/StepStage"}],"description":"処理中（失敗時は失敗した）段階"},"timestamp":{"format":"int64","type":"integer"},"warnings":{"description":"完了時 (progress 100) のみ。形状検査で見つかった問題（日本語）。問題がなければ省略","items":{"type":"string"},"type":"array"}},"required":["timestamp","progress","message"],"type":"object"},"StretchNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"伸縮: 切断面で形状を分割して指定方向に伸ばす","properties":{"cut":{"description":"切断面の座標 [cx, cy, cz] (mm)","items":{"$ref":"#/components/schemas/NumberOrExpr"},"type":"array"},"delta":{"description":"各軸方向の伸縮量 [dx, dy, dz] (mm)","items":{"$ref":"#/components/schemas/NumberOrExpr"},"type":"array"},"op":{"enum":["stretch"],"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"}},"required":["op","shape","cut","delta"],"type":"object"},"SubtractNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"ブーリアン差演算: a から b をくり抜く (BRepAlgoAPI_Cut)","properties":{"a":{"$ref":"#/components/schemas/ShapeNode"},"b":{"$ref":"#/components/schemas/ShapeNode"},"op":{"enum":["subtract"],"type":"string"}},"required":["op","a","b"],"type":"object"},"TextInput":{"properties":{"default":{"type":"string"},"label":{"type":"string"},"placeholder":{"type":"string"},"type":{"enum":["text"],"type":"string"},"value":{"type":"string"},"variant":{"enum":["text","area","email"],"type":"string"}},"required":["type","label","value"],"type":"object"},"TranslateNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"平行移動","properties":{"op":{"enum":["translate"],"type":"string"},"shape":{"$ref":"#/components/schemas/ShapeNode"},"xyz":{"description":"移動量 [x, y, z] (mm)","items":{"$ref":"#/components/schemas/NumberOrExpr"},"type":"array"}},"required":["op","shape","xyz"],"type":"object"},"UUID":{"format":"uuid","type":"string"},"UnionShapeNode":{"allOf":[{"$ref":"#/components/schemas/ShapeNodeBase"}],"description":"ブーリアン合体 (BRepAlgoAPI_Fuse)","properties":{"op":{"enum":["union"],"type":"string"},"shapes":{"items":{"$ref":"#/components/schemas/ShapeNode"},"type":"array"}},"required":["op","shapes"],"type":"object"},"UploadInput":{"properties":{"accept":{"type":"string"},"label":{"type":"string"},"maxSize":{"format":"int64","type":"integer"},"type":{"enum":["upload"],"type":"string"}},"required":["type","label"],"type":"object"},"UploadUrlBody":{"properties":{"id":{"$ref":"#/components/schemas/UUID"},"url":{"type":"string"}},"required":["id","url"],"type":"object"}}},"info":{"title":"Lambda360 API","version":"0.0.0"},"openapi":"3.0.0","paths":{"/action":{"post":{"description":"inputの値とbuttonの宛先を受け取ってメール・Slack送信を行う","operationId":"Action_action","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ActionRequest"}}},"required":true},"responses":{"204":{"description":"There is no content to send for this request, but the headers may be useful. "},"400":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The server could not understand the request due to invalid syntax."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape":{"post":{"description":"ShapeNode を受け取り、演算結果を GLB (GLTF Binary) として返す。\nソリッドごとのノードをアセンブリの木の通りに並べ、StepNode 単体なら STEP の製品名をノード名にする。\nmaterial に材質 SelectInput の value（stainless, aluminium, anodized_black など）を渡すと\n仕上げの PBR マテリアルで描く。該当するプリセットがなければ 400 を返す。\nmaterial なしの GLB は変換時に生成したキャッシュ（STEP_PREVIEW=1）があればそれを返す。","operationId":"Shape_gltf_binary","parameters":[{"explode":false,"in":"query","name":"material","schema":{"type":"string"},"style":"form"}],"requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"model/gltf-binary":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"400":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The server could not understand the request due to invalid syntax."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/3mf":{"post":{"description":"ShapeNode を受け取り、演算結果を面の色と単位 (mm) 付きの 3MF として返す","operationId":"Shape_three_mf","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"model/3mf":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/brep":{"post":{"description":"ShapeNode を受け取り、演算結果を BRep として返す。\n- format=color (既定): 色付き BRep (CHJC形式)\n- format=text: OCCT 標準のテキスト BRep（色なし）","operationId":"Shape_brep","parameters":[{"explode":false,"in":"query","name":"format","schema":{"enum":["color","text"],"type":"string"},"style":"form"}],"requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"application/octet-stream":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"400":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The server could not understand the request due to invalid syntax."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/drawing.svg":{"post":{"description":"ShapeOutput を受け取り、第三角法の三面図（正面図・平面図・右側面図）を SVG として返す。\n隠線は除去し、外形寸法と DistanceAnnotation を寸法線として描く。","operationId":"Shape_drawing_svg","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeOutput"}}},"required":true},"responses":{"200":{"content":{"image/svg+xml":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/dxf":{"post":{"description":"ShapeNode を受け取り、平面の輪郭を DXF (R12 ASCII, mm) として返す。\noffset を指定すると平面 p・normal = offset の断面を、省略すると法線が normal の平面のうち\nnormal 方向に最も遠い面の外形線を出力する。平面や断面が形状に当たらず輪郭がないときは 400 を返す。","operationId":"Shape_dxf","parameters":[{"explode":false,"in":"query","name":"normal","schema":{"enum":["X","Y","Z","-X","-Y","-Z"],"type":"string"},"style":"form"},{"explode":false,"in":"query","name":"offset","schema":{"format":"double","type":"number"},"style":"form"}],"requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"image/vnd.dxf":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"400":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The server could not understand the request due to invalid syntax."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/iges":{"post":{"description":"ShapeNode を受け取り、演算結果を IGES (面はトリム曲面、色付き, mm) として返す","operationId":"Shape_iges","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"model/iges":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/obj":{"post":{"description":"ShapeNode を受け取り、演算結果を OBJ + MTL (色) を含む ZIP として返す","operationId":"Shape_obj","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"application/zip":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/output.glb":{"post":{"description":"ShapeOutput を受け取り、GLB として返す。\nlabel と annotations を名前付きの空ノードとして、バウンディングボックス・体積・単位 (mm)・\nShapeNode のハッシュを extras として埋め込む。material は /shape と同じ。","operationId":"Shape_output_glb","parameters":[{"explode":false,"in":"query","name":"material","schema":{"type":"string"},"style":"form"}],"requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeOutput"}}},"required":true},"responses":{"200":{"content":{"model/gltf-binary":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"400":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The server could not understand the request due to invalid syntax."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/ply":{"post":{"description":"ShapeNode を受け取り、演算結果を頂点色付きバイナリ PLY として返す","operationId":"Shape_ply","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"model/x-ply":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/step":{"post":{"description":"ShapeNode を受け取り、演算結果を色付き STEP として返す。\n- schema: アプリケーションプロトコル (既定: ap214)\n- unit: 長さ単位 (既定: mm)\n- productName: PRODUCT の名前とファイル記述 (フォームの label など)\n- partNumber: PRODUCT の id とファイル名 (品番)","operationId":"Shape_step","parameters":[{"explode":false,"in":"query","name":"schema","schema":{"enum":["ap203","ap214","ap242"],"type":"string"},"style":"form"},{"explode":false,"in":"query","name":"unit","schema":{"enum":["mm","inch"],"type":"string"},"style":"form"},{"explode":false,"in":"query","name":"productName","schema":{"type":"string"},"style":"form"},{"explode":false,"in":"query","name":"partNumber","schema":{"type":"string"},"style":"form"}],"requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"model/step":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"400":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The server could not understand the request due to invalid syntax."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/stl":{"post":{"description":"ShapeNode を受け取り、演算結果を STL として返す（既定はバイナリ、ascii=true でテキスト）","operationId":"Shape_stl","parameters":[{"explode":false,"in":"query","name":"ascii","schema":{"type":"boolean"},"style":"form"}],"requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"model/stl":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/thumbnail.png":{"post":{"description":"ShapeNode を受け取り、サーバー側でレンダリングした PNG サムネイル（背景透明）を返す。\nview: 視点 (既定 iso)、size: 一辺のピクセル数 (16〜1024, 既定 256)。結果はキャッシュされる。","operationId":"Shape_thumbnail_png","parameters":[{"explode":false,"in":"query","name":"view","schema":{"enum":["iso","front","top","right"],"type":"string"},"style":"form"},{"explode":false,"in":"query","name":"size","schema":{"format":"int32","type":"integer"},"style":"form"}],"requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"image/png":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"400":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The server could not understand the request due to invalid syntax."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/shape/usdz":{"post":{"description":"ShapeNode を受け取り、演算結果を面の色付きの USDZ (iOS AR Quick Look 用, mm) として返す","operationId":"Shape_usdz","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/ShapeNode"}}},"required":true},"responses":{"200":{"content":{"model/vnd.usdz+zip":{"schema":{"format":"binary","type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/step/job":{"post":{"description":"変換ジョブを 1 回実行します。STEP_QUEUE=lambda のとき、submit は自分自身の Lambda をジョブをイベントとして非同期に呼び出し、\nLambda Web Adapter がそのイベントをこのパスへ POST します（AWS_LWA_PASS_THROUGH_PATH=/api/step/job）。\n変換の成否は /step/{id}/status と /step/{id}/result に書き、常に 204 を返します。再試行は待ってから submit し直します。","operationId":"Step_job","requestBody":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/StepJobEvent"}}},"required":true},"responses":{"204":{"description":"There is no content to send for this request, but the headers may be useful. "}}}},"/step/test":{"post":{"description":"ローカルテスト用（--features debug 時のみ有効）：\nファイルを受け取り、upload_url → S3アップロード → execute → status確認 の\n一連のフローを実行してcontent_hashを返します。","operationId":"Step_test","requestBody":{"content":{"application/octet-stream":{"schema":{"format":"binary","type":"string"}}},"required":true},"responses":{"200":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/step/upload":{"post":{"description":"アップロード用のURLとIDを取得します。\nフロントエンドはこのURLに対して実際のファイルをアップロードします。","operationId":"Step_upload_url","responses":{"200":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/UploadUrlBody"}}},"description":"The request has succeeded."}}}},"/step/{content_hash}/meta":{"get":{"description":"変換した STEP のヘッダと製品構造（作成した CAD システム・作成者・長さ単位・アセンブリ構造）を返します。\ncontent_hash は execute / result の manifest の値です。STEP 以外のファイルや、メタデータを保存する前に変換したファイルは 404 を返します。","operationId":"Step_meta","parameters":[{"in":"path","name":"content_hash","required":true,"schema":{"type":"string"},"style":"simple"}],"responses":{"200":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/StepMeta"}}},"description":"The request has succeeded."},"404":{"description":"The server cannot find the requested resource."}}}},"/step/{id}/events":{"get":{"description":"変換処理の進捗を Server-Sent Events (text/event-stream) で配信します。\n各イベントの data は StepStatusBody の JSON で、progress が 100 以上になったら終了します。\n接続時にまず保存済みの最新進捗を送ります。変換が別のサーバーで動いている場合は保存済みの進捗を数秒ごとに読み直して送ります。","operationId":"Step_events","parameters":[{"in":"path","name":"id","required":true,"schema":{"$ref":"#/components/schemas/UUID"},"style":"simple"}],"responses":{"200":{"content":{"text/event-stream":{"schema":{"type":"string"}}},"description":"The request has succeeded."}}}},"/step/{id}/execute":{"post":{"description":"指定した ID のファイルの変換処理（STEP / IGES / BRep / STL / 3MF -\u003e BREP）を実行します。\n形式はファイルの中身から判定します。STL / 3MF は三角形ごとの平面を面とするソリッドになります。\n3MF 以外の ZIP は複数ファイルのアーカイブとして扱い、対応形式のメンバーをそれぞれ変換します。\nZIP のメンバーは 1 つずつ展開し、展開後のサイズの合計が 256 MB を超えると too_large で失敗します。\nダウンロード・変換・アップロードがすべて完了したときに 200 を返します。\n失敗した場合は 500 とエラーメッセージを返します。\n進捗は処理中も /step/{id}/status で確認できます。\nレスポンスボディは変換したファイルごとの content_hash とバウンディングボックスの一覧です。","operationId":"Step_execute","parameters":[{"in":"path","name":"id","required":true,"schema":{"$ref":"#/components/schemas/UUID"},"style":"simple"}],"responses":{"200":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/StepManifest"}}},"description":"The request has succeeded."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/step/{id}/result":{"get":{"description":"submit したジョブの結果（execute のレスポンスと同じ manifest）を返します。\n結果は完了の進捗より先に書かれるので、/step/{id}/status が progress 100 なら必ず返せます。\nまだ完了していない、または失敗した場合は 404 を返します。","operationId":"Step_result","parameters":[{"in":"path","name":"id","required":true,"schema":{"$ref":"#/components/schemas/UUID"},"style":"simple"}],"responses":{"200":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/StepManifest"}}},"description":"The request has succeeded."},"404":{"description":"The server cannot find the requested resource."}}}},"/step/{id}/status":{"get":{"description":"変換処理の最新進捗を返します。\n- progress 100: 正常終了\n- progress 101以上: 異常終了（error_code・stage・error_message に失敗の種類と段階、英語の説明）\n変換がまだ開始されていない場合は 404 を返します。","operationId":"Step_status","parameters":[{"in":"path","name":"id","required":true,"schema":{"$ref":"#/components/schemas/UUID"},"style":"simple"}],"responses":{"200":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/StepStatusBody"}}},"description":"The request has succeeded."},"404":{"description":"The server cannot find the requested resource."}}}},"/step/{id}/submit":{"post":{"description":"指定した ID のファイルの変換処理をジョブキューに積み、すぐに 202 とジョブ ID を返します。\nジョブ ID はアップロード ID と同じです。変換は execute と同じ内容で、ダウンロード・アップロードの一時的な失敗は最大 3 回まで試行します。\n進捗は /step/{id}/status、完了後の結果は /step/{id}/result で確認できます。","operationId":"Step_submit","parameters":[{"in":"path","name":"id","required":true,"schema":{"$ref":"#/components/schemas/UUID"},"style":"simple"}],"responses":{"202":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The request has been accepted for processing, but processing has not yet completed."},"500":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"Server error"}}}},"/version":{"get":{"description":"このAPIサーバーのバージョンと使用しているS3バケット名を返します。","operationId":"version","responses":{"200":{"content":{"text/plain":{"schema":{"type":"string"}}},"description":"The request has succeeded."}}}}},"servers":[{"description":"Main server","url":"/api","variables":{}}]}"###
 		}))
 		.route("/ui", axum::routing::get(|| async move{
 			axum::response::Html(r###"
@@ -906,16 +2166,48 @@ impl ApiInterface for TestServer {
 	// async fn action_action(&self, _req: ActionActionRequest) -> ActionActionResponse{Default::default()}
 	// POST /shape
 	// async fn shape_gltf_binary(&self, _req: ShapeGltfBinaryRequest) -> ShapeGltfBinaryResponse{Default::default()}
+	// POST /shape/3mf
+	// async fn shape_three_mf(&self, _req: ShapeThreeMfRequest) -> ShapeThreeMfResponse{Default::default()}
+	// POST /shape/brep
+	// async fn shape_brep(&self, _req: ShapeBrepRequest) -> ShapeBrepResponse{Default::default()}
+	// POST /shape/drawing.svg
+	// async fn shape_drawing_svg(&self, _req: ShapeDrawingSvgRequest) -> ShapeDrawingSvgResponse{Default::default()}
+	// POST /shape/dxf
+	// async fn shape_dxf(&self, _req: ShapeDxfRequest) -> ShapeDxfResponse{Default::default()}
+	// POST /shape/iges
+	// async fn shape_iges(&self, _req: ShapeIgesRequest) -> ShapeIgesResponse{Default::default()}
+	// POST /shape/obj
+	// async fn shape_obj(&self, _req: ShapeObjRequest) -> ShapeObjResponse{Default::default()}
+	// POST /shape/output.glb
+	// async fn shape_output_glb(&self, _req: ShapeOutputGlbRequest) -> ShapeOutputGlbResponse{Default::default()}
+	// POST /shape/ply
+	// async fn shape_ply(&self, _req: ShapePlyRequest) -> ShapePlyResponse{Default::default()}
 	// POST /shape/step
 	// async fn shape_step(&self, _req: ShapeStepRequest) -> ShapeStepResponse{Default::default()}
+	// POST /shape/stl
+	// async fn shape_stl(&self, _req: ShapeStlRequest) -> ShapeStlResponse{Default::default()}
+	// POST /shape/thumbnail.png
+	// async fn shape_thumbnail_png(&self, _req: ShapeThumbnailPngRequest) -> ShapeThumbnailPngResponse{Default::default()}
+	// POST /shape/usdz
+	// async fn shape_usdz(&self, _req: ShapeUsdzRequest) -> ShapeUsdzResponse{Default::default()}
+	// POST /step/job
+	// async fn step_job(&self, _req: StepJobRequest) -> StepJobResponse{Default::default()}
 	// POST /step/test
 	// async fn step_test(&self, _req: StepTestRequest) -> StepTestResponse{Default::default()}
 	// POST /step/upload
 	// async fn step_upload_url(&self, _req: StepUploadUrlRequest) -> StepUploadUrlResponse{Default::default()}
+	// GET /step/{content_hash}/meta
+	// async fn step_meta(&self, _req: StepMetaRequest) -> StepMetaResponse{Default::default()}
+	// GET /step/{id}/events
+	// async fn step_events(&self, _req: StepEventsRequest) -> StepEventsResponse{Default::default()}
 	// POST /step/{id}/execute
 	// async fn step_execute(&self, _req: StepExecuteRequest) -> StepExecuteResponse{Default::default()}
+	// GET /step/{id}/result
+	// async fn step_result(&self, _req: StepResultRequest) -> StepResultResponse{Default::default()}
 	// GET /step/{id}/status
 	// async fn step_status(&self, _req: StepStatusRequest) -> StepStatusResponse{Default::default()}
+	// POST /step/{id}/submit
+	// async fn step_submit(&self, _req: StepSubmitRequest) -> StepSubmitResponse{Default::default()}
 	// GET /version
 	// async fn version(&self, _req: VersionRequest) -> VersionResponse{Default::default()}
 }
@@ -1,27 +1,44 @@
-use crate::encode::{gltf_binary, shape_to_step};
+use crate::encode::{
+	GlbInfo, GlbPlan, MaterialPreset, StepOptions, StepSchema, StepUnit, THUMBNAIL_SIZE,
+	THUMBNAIL_VIEW, drawing_svg, dxf, material_names, material_preset, obj_archive, ply,
+	shape_to_brep_text, shape_to_iges, shape_to_step, stl, three_mf, thumbnail_png, thumbnail_view,
+	usdz,
+};
+use crate::events::status_stream;
+use crate::job::{JobQueue, StepJob, queue_from_env, resubmit, run_job, submit};
 use crate::openapi::*;
-use crate::shape::resolve_shape;
-use crate::upload::step_pipeline;
+use crate::shape::{
+	DEFAULT_GLB, cached_encoded, compute_shape_hash, resolve_brep_color, resolve_encoded,
+	resolve_shape, step_products, thumbnail_name,
+};
+use crate::upload::{result_key, step_pipeline, write_status};
 use ngoni;
+use std::sync::Arc;
 
 pub struct Server {
 	bucket_temp: ngoni::s3::S3Storage,
 	bucket_main: ngoni::s3::S3Storage,
+	queue: Arc<dyn JobQueue>,
 }
 
 impl Server {
 	pub async fn new() -> Result<Self, String> {
+		let bucket_temp = ngoni::s3::S3Storage::new(
+			&std::env::var("BUCKET_TEMP")
+				.unwrap_or("lambda360form-temp3a4f7567-8r5qy5tygu2i".to_string()),
+		)
+		.await;
+		let bucket_main = ngoni::s3::S3Storage::new(
+			&std::env::var("BUCKET_MAIN")
+				.unwrap_or("lambda360form-main7ad10839-84xkdgyv7kph".to_string()),
+		)
+		.await;
+		// 変換ジョブのキュー（STEP_QUEUE=lambda なら Lambda の非同期呼び出し、未設定なら同一プロセス内）
+		let queue = queue_from_env(&bucket_temp, &bucket_main).await?;
 		Ok(Self {
-			bucket_temp: ngoni::s3::S3Storage::new(
-				&std::env::var("BUCKET_TEMP")
-					.unwrap_or("lambda360form-temp3a4f7567-8r5qy5tygu2i".to_string()),
-			)
-			.await,
-			bucket_main: ngoni::s3::S3Storage::new(
-				&std::env::var("BUCKET_MAIN")
-					.unwrap_or("lambda360form-main7ad10839-84xkdgyv7kph".to_string()),
-			)
-			.await,
+			bucket_temp,
+			bucket_main,
+			queue,
 		})
 	}
 }
@@ -108,7 +125,7 @@ impl ApiInterface for Server {
 		}
 
 		// 3. execute
-		let StepExecuteResponse::Status200(content_hash) = self
+		let StepExecuteResponse::Status200(manifest) = self
 			.step_execute(StepExecuteRequest {
 				id,
 				request: axum::http::Request::new(axum::body::Body::empty()),
@@ -133,7 +150,7 @@ impl ApiInterface for Server {
 			err!(format!("異常終了: progress={}", status.progress));
 		}
 
-		StepTestResponse::Status200(content_hash)
+		StepTestResponse::Status200(manifest.content_hash)
 	}
 
 	async fn step_execute(&self, req: StepExecuteRequest) -> StepExecuteResponse {
@@ -144,8 +161,79 @@ impl ApiInterface for Server {
 		)
 		.await
 		{
-			Ok(content_hash) => StepExecuteResponse::Status200(content_hash),
-			Err(msg) => StepExecuteResponse::Status500(msg),
+			Ok(manifest) => StepExecuteResponse::Status200(manifest),
+			Err(e) => {
+				write_status(&self.bucket_temp, &req.id.to_string(), e.status()).await;
+				StepExecuteResponse::Status500(e.message)
+			}
+		}
+	}
+
+	async fn step_submit(&self, req: StepSubmitRequest) -> StepSubmitResponse {
+		let id = req.id.to_string();
+		match submit(
+			self.queue.as_ref(),
+			&self.bucket_temp,
+			StepJob::new(id.clone()),
+		)
+		.await
+		{
+			Ok(()) => StepSubmitResponse::Status202(id),
+			Err(msg) => StepSubmitResponse::Status500(msg),
+		}
+	}
+
+	async fn step_job(&self, req: StepJobRequest) -> StepJobResponse {
+		let job = StepJob {
+			id: req.body.id.to_string(),
+			attempt: req.body.attempt.max(1) as u32,
+		};
+		// 呼び出しが終わると Lambda は止まるので、再試行の待ちと投入もこの中で済ませる
+		if let Some(retry) = run_job(&job, &self.bucket_temp, &self.bucket_main).await {
+			resubmit(self.queue.as_ref(), &self.bucket_temp, &job, retry).await;
+		}
+		StepJobResponse::Status204
+	}
+
+	async fn step_result(&self, req: StepResultRequest) -> StepResultResponse {
+		let key = result_key(&req.id.to_string());
+		let Ok((_, data)) = self.bucket_temp.read(&key).await else {
+			return StepResultResponse::Status404;
+		};
+		match serde_json::from_slice::<StepManifest>(&data) {
+			Ok(manifest) => StepResultResponse::Status200(manifest),
+			Err(_) => StepResultResponse::Status404,
+		}
+	}
+
+	async fn step_events(&self, req: StepEventsRequest) -> StepEventsResponse {
+		use axum::response::IntoResponse;
+		use axum::response::sse::{Event, KeepAlive, Sse};
+		use futures_util::StreamExt;
+		let stream = status_stream(req.id.to_string(), self.bucket_temp.clone()).map(|status| {
+			Event::default()
+				.json_data(status)
+				.map_err(|e| format!("{e:?}"))
+		});
+		StepEventsResponse::Raw(
+			Sse::new(stream)
+				.keep_alive(KeepAlive::default())
+				.into_response(),
+		)
+	}
+
+	async fn step_meta(&self, req: StepMetaRequest) -> StepMetaResponse {
+		// content_hash は SHA-256 の 16 進表記。それ以外のキーは読まない
+		let hash = &req.content_hash;
+		if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+			return StepMetaResponse::Status404;
+		}
+		let Ok((_, data)) = self.bucket_main.read(&format!("{hash}.json")).await else {
+			return StepMetaResponse::Status404;
+		};
+		match serde_json::from_slice::<StepMeta>(&data) {
+			Ok(meta) => StepMetaResponse::Status200(meta),
+			Err(_) => StepMetaResponse::Status404,
 		}
 	}
 
@@ -161,9 +249,23 @@ impl ApiInterface for Server {
 	}
 
 	async fn shape_step(&self, req: ShapeStepRequest) -> ShapeStepResponse {
+		let schema = req.schema.as_deref().unwrap_or("ap214");
+		let Some(schema) = StepSchema::from_name(schema) else {
+			return ShapeStepResponse::Status400(format!("unknown schema '{schema}'"));
+		};
+		let unit = req.unit.as_deref().unwrap_or("mm");
+		let Some(unit) = StepUnit::from_name(unit) else {
+			return ShapeStepResponse::Status400(format!("unknown unit '{unit}'"));
+		};
+		let options = StepOptions {
+			schema,
+			unit,
+			product_name: req.productName.filter(|s| !s.is_empty()),
+			part_number: req.partNumber.filter(|s| !s.is_empty()),
+		};
 		let result = resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
 			.await
-			.and_then(|shape| shape_to_step(&shape));
+			.and_then(|shape| shape_to_step(&shape, &options));
 
 		match result {
 			Ok(data) => ShapeStepResponse::Status200(data),
@@ -171,20 +273,311 @@ impl ApiInterface for Server {
 		}
 	}
 
+	async fn shape_iges(&self, req: ShapeIgesRequest) -> ShapeIgesResponse {
+		let result = resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
+			.await
+			.and_then(|shape| shape_to_iges(&shape));
+
+		match result {
+			Ok(data) => ShapeIgesResponse::Status200(data),
+			Err(e) => ShapeIgesResponse::Status500(e),
+		}
+	}
+
+	async fn shape_brep(&self, req: ShapeBrepRequest) -> ShapeBrepResponse {
+		let result = match req.format.as_deref() {
+			None | Some("color") => {
+				resolve_brep_color(&req.body, &self.bucket_main, &self.bucket_temp).await
+			}
+			Some("text") => resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
+				.await
+				.and_then(|shape| shape_to_brep_text(&shape)),
+			Some(other) => {
+				return ShapeBrepResponse::Status400(format!("unknown BRep format '{other}'"));
+			}
+		};
+
+		match result {
+			Ok(data) => ShapeBrepResponse::Status200(data),
+			Err(e) => ShapeBrepResponse::Status500(e),
+		}
+	}
+
+	async fn shape_dxf(&self, req: ShapeDxfRequest) -> ShapeDxfResponse {
+		let normal = match req.normal.as_deref() {
+			None | Some("Z") => glam::DVec3::Z,
+			Some("X") => glam::DVec3::X,
+			Some("Y") => glam::DVec3::Y,
+			Some("-X") => glam::DVec3::NEG_X,
+			Some("-Y") => glam::DVec3::NEG_Y,
+			Some("-Z") => glam::DVec3::NEG_Z,
+			Some(other) => return ShapeDxfResponse::Status400(format!("unknown normal '{other}'")),
+		};
+		let result = resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
+			.await
+			.and_then(|shape| dxf(&shape, normal, req.offset));
+
+		match result {
+			Ok(Some(data)) => ShapeDxfResponse::Status200(data),
+			Ok(None) => ShapeDxfResponse::Status400(
+				"輪郭がありません（平面が形状に当たっていません）".to_string(),
+			),
+			Err(e) => ShapeDxfResponse::Status500(e),
+		}
+	}
+
+	async fn shape_stl(&self, req: ShapeStlRequest) -> ShapeStlResponse {
+		let ascii = req.ascii.unwrap_or(false);
+		let result = resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
+			.await
+			.and_then(|shape| stl(&shape, ascii));
+
+		match result {
+			Ok(data) => ShapeStlResponse::Status200(data),
+			Err(e) => ShapeStlResponse::Status500(e),
+		}
+	}
+
+	async fn shape_obj(&self, req: ShapeObjRequest) -> ShapeObjResponse {
+		let result = resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
+			.await
+			.and_then(|shape| obj_archive(&shape));
+
+		match result {
+			Ok(data) => ShapeObjResponse::Status200(data),
+			Err(e) => ShapeObjResponse::Status500(e),
+		}
+	}
+
+	async fn shape_ply(&self, req: ShapePlyRequest) -> ShapePlyResponse {
+		let result = resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
+			.await
+			.and_then(|shape| ply(&shape));
+
+		match result {
+			Ok(data) => ShapePlyResponse::Status200(data),
+			Err(e) => ShapePlyResponse::Status500(e),
+		}
+	}
+
+	async fn shape_three_mf(&self, req: ShapeThreeMfRequest) -> ShapeThreeMfResponse {
+		let result = resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
+			.await
+			.and_then(|shape| three_mf(&shape));
+
+		match result {
+			Ok(data) => ShapeThreeMfResponse::Status200(data),
+			Err(e) => ShapeThreeMfResponse::Status500(e),
+		}
+	}
+
+	async fn shape_usdz(&self, req: ShapeUsdzRequest) -> ShapeUsdzResponse {
+		let result = resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
+			.await
+			.and_then(|shape| usdz(&shape));
+
+		match result {
+			Ok(data) => ShapeUsdzResponse::Status200(data),
+			Err(e) => ShapeUsdzResponse::Status500(e),
+		}
+	}
+
+	async fn shape_thumbnail_png(
+		&self,
+		req: ShapeThumbnailPngRequest,
+	) -> ShapeThumbnailPngResponse {
+		let view = req.view.as_deref().unwrap_or(THUMBNAIL_VIEW);
+		let Some(toward) = thumbnail_view(view) else {
+			return ShapeThumbnailPngResponse::Status400(format!("unknown view '{view}'"));
+		};
+		let size = req.size.unwrap_or(THUMBNAIL_SIZE);
+		if !(16..=1024).contains(&size) {
+			return ShapeThumbnailPngResponse::Status400(format!(
+				"size must be between 16 and 1024, got {size}"
+			));
+		}
+		let result = resolve_encoded(
+			&req.body,
+			&thumbnail_name(view, size),
+			"image/png",
+			&self.bucket_main,
+			&self.bucket_temp,
+			|shape| thumbnail_png(shape, toward, size as u32),
+		)
+		.await;
+
+		match result {
+			Ok(png) => ShapeThumbnailPngResponse::Status200(png),
+			Err(e) => ShapeThumbnailPngResponse::Status500(e),
+		}
+	}
+
+	async fn shape_drawing_svg(&self, req: ShapeDrawingSvgRequest) -> ShapeDrawingSvgResponse {
+		let output = &req.body;
+		let result = resolve_shape(&output.shape, &self.bucket_main, &self.bucket_temp)
+			.await
+			.and_then(|shape| {
+				drawing_svg(
+					&shape,
+					output.annotations.as_deref().unwrap_or_default(),
+					output.label.as_deref(),
+					output.axisUp.as_deref(),
+				)
+			});
+
+		match result {
+			Ok(svg) => ShapeDrawingSvgResponse::Status200(svg),
+			Err(e) => ShapeDrawingSvgResponse::Status500(e),
+		}
+	}
+
+	async fn shape_output_glb(&self, req: ShapeOutputGlbRequest) -> ShapeOutputGlbResponse {
+		let output = &req.body;
+		let material = match material_query(req.material.as_deref()) {
+			Ok(material) => material,
+			Err(e) => return ShapeOutputGlbResponse::Status400(e),
+		};
+		let products = step_products(&output.shape, &self.bucket_main).await;
+		let result = match compute_shape_hash(&output.shape) {
+			Ok(shape_hash) => resolve_shape(&output.shape, &self.bucket_main, &self.bucket_temp)
+				.await
+				.and_then(|shape| {
+					let info = GlbInfo {
+						label: output.label.as_deref(),
+						annotations: output.annotations.as_deref().unwrap_or_default(),
+						shape_hash,
+					};
+					GlbPlan::new(&shape, material, Some(&info), &products)
+				}),
+			Err(e) => Err(e),
+		};
+
+		match result {
+			Ok(plan) => ShapeOutputGlbResponse::Raw(glb_response(plan)),
+			Err(e) => ShapeOutputGlbResponse::Status500(e),
+		}
+	}
+
 	async fn shape_gltf_binary(&self, req: ShapeGltfBinaryRequest) -> ShapeGltfBinaryResponse {
+		let material = match material_query(req.material.as_deref()) {
+			Ok(material) => material,
+			Err(e) => return ShapeGltfBinaryResponse::Status400(e),
+		};
+		// 変換時に生成した既定の GLB があればメッシュ化を省く
+		if material.is_none()
+			&& let Some(glb) = cached_encoded(&req.body, DEFAULT_GLB, &self.bucket_temp).await
+		{
+			return ShapeGltfBinaryResponse::Raw(glb_bytes_response(glb));
+		}
+		let products = step_products(&req.body, &self.bucket_main).await;
 		let result = resolve_shape(&req.body, &self.bucket_main, &self.bucket_temp)
 			.await
-			.and_then(|shape| gltf_binary(&shape));
+			.and_then(|shape| GlbPlan::new(&shape, material, None, &products));
 
 		match result {
-			Ok(glb) => ShapeGltfBinaryResponse::Status200(glb),
+			Ok(plan) => ShapeGltfBinaryResponse::Raw(glb_response(plan)),
 			Err(e) => ShapeGltfBinaryResponse::Status500(e),
 		}
 	}
 }
 
+/// material クエリのプリセット。指定がなければ None、該当しなければ受け付ける名前を添えた 400 のメッセージ
+fn material_query(value: Option<&str>) -> Result<Option<&'static MaterialPreset>, String> {
+	let Some(value) = value else {
+		return Ok(None);
+	};
+	material_preset(value).map(Some).ok_or_else(|| {
+		format!(
+			"unknown material '{value}' (accepted: {})",
+			material_names().join(", ")
+		)
+	})
+}
+
+/// キャッシュ済みの GLB のバイト列をそのまま返す
+fn glb_bytes_response(glb: Vec<u8>) -> axum::response::Response {
+	axum::response::Response::builder()
+		.status(axum::http::StatusCode::OK)
+		.header(axum::http::header::CONTENT_TYPE, "model/gltf-binary")
+		.header(axum::http::header::CONTENT_LENGTH, glb.len())
+		.body(axum::body::Body::from(glb))
+		.unwrap()
+}
+
+/// GLB をレスポンスボディへ GLB_CHUNK_SIZE ずつ流す。
+/// レイアウトは GlbPlan の時点で確定しているので Content-Length を付けられ、ここで失敗するのは I/O だけ。
+fn glb_response(plan: GlbPlan) -> axum::response::Response {
+	let (tx, rx) = tokio::sync::mpsc::channel::<std::io::Result<Vec<u8>>>(4);
+	let length = plan.byte_len();
+	tokio::task::spawn_blocking(move || send_chunks(tx, |out| plan.write_to(out)));
+	let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+		rx.recv().await.map(|chunk| (chunk, rx))
+	});
+	axum::response::Response::builder()
+		.status(axum::http::StatusCode::OK)
+		.header(axum::http::header::CONTENT_TYPE, "model/gltf-binary")
+		.header(axum::http::header::CONTENT_LENGTH, length)
+		.body(axum::body::Body::from_stream(stream))
+		.unwrap()
+}
+
+/// write が書いたバイト列を GLB_CHUNK_SIZE ずつ tx へ送る。
+/// 失敗したらバッファに残ったバイト列は送らずに捨て、エラーをチャンネルの最後に送る
+fn send_chunks(
+	tx: tokio::sync::mpsc::Sender<std::io::Result<Vec<u8>>>,
+	write: impl FnOnce(&mut std::io::BufWriter<ChannelWriter>) -> std::io::Result<()>,
+) {
+	let mut out = std::io::BufWriter::with_capacity(GLB_CHUNK_SIZE, ChannelWriter(tx));
+	if let Err(e) = write(&mut out).and_then(|()| std::io::Write::flush(&mut out)) {
+		let (ChannelWriter(tx), _) = out.into_parts();
+		let _ = tx.blocking_send(Err(e));
+	}
+}
+
+/// GLB ストリーミングでレスポンスへ送る 1 チャンクのバイト数
+const GLB_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 書き込まれたバイト列をチャンネルへ送る Write。クライアントが切断したら BrokenPipe で書き込みを止める。
+struct ChannelWriter(tokio::sync::mpsc::Sender<std::io::Result<Vec<u8>>>);
+
+impl std::io::Write for ChannelWriter {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.0
+			.blocking_send(Ok(buf.to_vec()))
+			.map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
 #[cfg(test)]
 mod tests {
+	use super::*;
+
+	#[test]
+	fn encode_error_is_the_last_chunk() {
+		let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+		send_chunks(tx, |out| {
+			std::io::Write::write_all(out, &vec![1; GLB_CHUNK_SIZE - 5])?;
+			std::io::Write::write_all(out, &[2; 10])?;
+			Err(std::io::Error::other("encode"))
+		});
+		let mut chunks = Vec::new();
+		while let Ok(chunk) = rx.try_recv() {
+			chunks.push(chunk);
+		}
+		// バッファに残った 10 バイトはエラーの前にも後にも送らない
+		let sent: usize = chunks
+			.iter()
+			.filter_map(|c| c.as_ref().ok())
+			.map(Vec::len)
+			.sum();
+		assert_eq!(sent, GLB_CHUNK_SIZE - 5);
+		assert!(chunks.last().unwrap().is_err());
+	}
 
 	fn online_url() -> String {
 		std::env::var("ONLINE_URL").unwrap_or("https://d3l2x153v6axn.cloudfront.net".to_string())
@@ -1,3 +1,4 @@
+use crate::encode::shape_to_brep_color;
 use crate::openapi::*;
 use chijin::{Shape, utils::stretch_vector};
 use glam::DVec3;
@@ -28,7 +29,7 @@ fn resolve_vec3(v: &[NumberOrExpr]) -> Result<[f64; 3], String> {
 }
 
 /// ShapeNodeからキャッシュのキーとなるSHA256ハッシュを計算する。
-fn compute_shape_hash(node: &ShapeNode) -> Result<String, String> {
+pub(crate) fn compute_shape_hash(node: &ShapeNode) -> Result<String, String> {
 	let json_str = serde_json_canonicalizer::to_string(node).map_err(|e| e.to_string())?;
 	let mut h = Sha256::new();
 	h.update(json_str.as_bytes());
@@ -145,10 +146,7 @@ pub async fn resolve_shape(
 
 	// 結果をbucket_tempにキャッシュ（write_brep_color: 色メタデータ付きBRep）
 	let hash = compute_shape_hash(node)?;
-	let mut brep_buf = Vec::new();
-	shape
-		.write_brep_color(&mut brep_buf)
-		.map_err(|e| format!("Failed to write BRep: {:?}", e))?;
+	let brep_buf = shape_to_brep_color(&shape)?;
 	bucket_temp
 		.write(
 			&hash,
@@ -163,6 +161,94 @@ pub async fn resolve_shape(
 	Ok(shape)
 }
 
+/// ShapeNode が StepNode 単体なら、変換時に保存した STEP の製品構造（GLB のノード名に使う）。
+/// 演算した形状や STEP 以外の変換結果は空
+pub async fn step_products(
+	node: &ShapeNode,
+	bucket_main: &ngoni::s3::S3Storage,
+) -> Vec<StepAssemblyNode> {
+	let ShapeNode::Step(step) = node else {
+		return Vec::new();
+	};
+	let Ok((_meta, data)) = bucket_main
+		.read(&format!("{}.json", step.content_hash))
+		.await
+	else {
+		return Vec::new();
+	};
+	serde_json::from_slice::<StepMeta>(&data)
+		.map(|meta| meta.assembly)
+		.unwrap_or_default()
+}
+
+/// ShapeNodeを評価して色付きBRep (CHJC形式) のバイト列を返す。
+/// resolve_shape がbucket_tempにキャッシュしたBRepがあればパースせずにそのまま返す。
+pub async fn resolve_brep_color(
+	node: &ShapeNode,
+	bucket_main: &ngoni::s3::S3Storage,
+	bucket_temp: &ngoni::s3::S3Storage,
+) -> Result<Vec<u8>, String> {
+	let hash = compute_shape_hash(node)?;
+	if let Ok((_meta, data)) = bucket_temp.read(&hash).await {
+		return Ok(data);
+	}
+	let shape = resolve_shape(node, bucket_main, bucket_temp).await?;
+	shape_to_brep_color(&shape)
+}
+
+/// ShapeNodeを評価し、encode で変換したバイト列を返す。
+/// 変換結果はbucket_tempの `{hash}.{name}` にキャッシュし、ヒット時は評価も変換も省く。
+pub async fn resolve_encoded(
+	node: &ShapeNode,
+	name: &str,
+	content_type: &str,
+	bucket_main: &ngoni::s3::S3Storage,
+	bucket_temp: &ngoni::s3::S3Storage,
+	encode: impl FnOnce(&Shape) -> Result<Vec<u8>, String>,
+) -> Result<Vec<u8>, String> {
+	if let Some(data) = cached_encoded(node, name, bucket_temp).await {
+		return Ok(data);
+	}
+	let shape = resolve_shape(node, bucket_main, bucket_temp).await?;
+	let data = encode(&shape)?;
+	cache_encoded(node, name, content_type, data.clone(), bucket_temp).await?;
+	Ok(data)
+}
+
+/// 既定の GLB（material・info なし）のキャッシュ名。bucket_temp の `{hash}.glb` になる
+pub(crate) const DEFAULT_GLB: &str = "glb";
+
+/// `/shape/thumbnail.png` のキャッシュ名
+pub(crate) fn thumbnail_name(view: &str, size: i32) -> String {
+	format!("thumbnail_{view}_{size}.png")
+}
+
+/// bucket_tempの `{hash}.{name}` にキャッシュされた変換結果。ミスはNone。
+pub async fn cached_encoded(
+	node: &ShapeNode,
+	name: &str,
+	bucket_temp: &ngoni::s3::S3Storage,
+) -> Option<Vec<u8>> {
+	let key = format!("{}.{name}", compute_shape_hash(node).ok()?);
+	let (_meta, data) = bucket_temp.read(&key).await.ok()?;
+	Some(data)
+}
+
+/// 変換結果をbucket_tempの `{hash}.{name}` にキャッシュする。
+pub async fn cache_encoded(
+	node: &ShapeNode,
+	name: &str,
+	content_type: &str,
+	data: Vec<u8>,
+	bucket_temp: &ngoni::s3::S3Storage,
+) -> Result<(), String> {
+	let key = format!("{}.{name}", compute_shape_hash(node)?);
+	bucket_temp
+		.write(&key, data, Some(content_type.to_string()), None, None)
+		.await
+		.map_err(|e| format!("Failed to cache {}: {}", name, e))
+}
+
 /// ShapeNodeを再帰的に評価してShapeを返す。
 /// shapes から StepNode の Shape を取り出す（remove）ため &mut を要求する。
 pub(crate) fn eval_shape(
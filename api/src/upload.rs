@@ -1,146 +1,738 @@
+mod check;
+mod error;
+mod format;
+mod mesh;
+mod metadata;
+mod read_progress;
+
 use crate::content_hash::content_hash as compute_hash;
-use crate::openapi::StepStatusBody;
+use crate::encode::{
+	THUMBNAIL_SIZE, THUMBNAIL_VIEW, bounding_box, mesh_thumbnail_png, shape_mesh, shape_to_glb,
+	thumbnail_view,
+};
+use crate::events;
+use crate::openapi::{
+	BoundingBox, ShapeNode, StepAssemblyNode, StepCheckReport, StepManifest, StepManifestPart,
+	StepMeta, StepNode, StepStatusBody,
+};
+use crate::shape::{DEFAULT_GLB, cache_encoded, thumbnail_name};
 use chijin::Shape;
+use error::MAX_UPLOAD_SIZE;
+pub use error::{StepError, StepErrorCode, StepStage};
+use format::{InputFormat, read_shape};
 use ngoni::s3::S3Storage;
+use read_progress::ReadProgress;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
-/// STEPファイルを変換してBRepをbucket_mainに保存するパイプライン
+/// 進捗 (progress, stage, message) を bucket_temp のログに書くコールバック
+type Progress =
+	Arc<dyn Fn(u32, StepStage, String) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// 現在時刻の進捗
+pub fn status_body(progress: u32, stage: Option<StepStage>, message: String) -> StepStatusBody {
+	StepStatusBody {
+		timestamp: std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_secs() as i64,
+		progress: progress as i32,
+		message,
+		stage: stage.map(|s| s.as_str().to_string()),
+		error_code: None,
+		error_message: None,
+		warnings: None,
+	}
+}
+
+/// 完了 (progress 100) の進捗。stage は最後の段階（変換済みなら Cached）。形状検査の警告があれば warnings に付ける
+fn finished_status(stage: StepStage, message: &str, warnings: Vec<String>) -> StepStatusBody {
+	if warnings.is_empty() {
+		return status_body(100, Some(stage), message.to_string());
+	}
+	StepStatusBody {
+		warnings: Some(warnings.clone()),
+		..status_body(
+			100,
+			Some(stage),
+			format!("{message}（警告 {} 件）", warnings.len()),
+		)
+	}
+}
+
+/// 進捗を bucket_temp の `_/{uuid}.log` に StepStatusBody として書き、
+/// 同じプロセスで `/step/{id}/events` を購読しているクライアントへ送る（失敗は無視する）
+pub async fn write_status(bucket_temp: &S3Storage, uuid: &str, status: StepStatusBody) {
+	events::publish(uuid, &status);
+	let Ok(json) = serde_json::to_string(&status) else {
+		return;
+	};
+	let _ = bucket_temp
+		.write_bytes(&format!("_/{uuid}.log"), json.into_bytes())
+		.await;
+}
+
+/// 変換結果の manifest を置く bucket_temp のキー（`/step/{id}/result` が読む）
+pub fn result_key(uuid: &str) -> String {
+	format!("_/{uuid}.result.json")
+}
+
+/// 完了したときに bucket_temp へ書く (キー, 中身) を書く順に並べる。
+/// 結果を進捗より先に書くので、進捗が 100 になった時点で結果も読める
+fn finished_objects(
+	uuid: &str,
+	manifest: &StepManifest,
+	status: &StepStatusBody,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+	let result =
+		serde_json::to_vec(manifest).map_err(|e| format!("結果のシリアライズ失敗: {e:?}"))?;
+	let log = serde_json::to_vec(status).map_err(|e| format!("進捗のシリアライズ失敗: {e:?}"))?;
+	Ok(vec![
+		(result_key(uuid), result),
+		(format!("_/{uuid}.log"), log),
+	])
+}
+
+/// 結果の manifest と完了 (progress 100) の進捗を書く。
+/// 途中の進捗と違って失敗は無視せず、アップロードの失敗として返す（再試行すれば書き直される）
+async fn finish(
+	bucket_temp: &S3Storage,
+	uuid: &str,
+	manifest: &StepManifest,
+	status: StepStatusBody,
+) -> Result<(), StepError> {
+	let failed = |message| StepError::new(StepErrorCode::UploadFailed, StepStage::Upload, message);
+	for (key, body) in finished_objects(uuid, manifest, &status).map_err(failed)? {
+		bucket_temp
+			.write_bytes(&key, body)
+			.await
+			.map_err(|e| format!("アップロード失敗: {e:?}"))
+			.map_err(failed)?;
+	}
+	events::publish(uuid, &status);
+	Ok(())
+}
+
+/// アップロードされたファイルを変換してBRepをbucket_mainに保存するパイプライン
+///
+/// 1. bucket_temp から `{uuid}.step` を読み込む（中身は STEP / IGES / BRep / STL / 3MF / ZIP のいずれか）
+/// 2. content_hash を計算して形式を判定する。bucket_main に変換済みの結果があればここで progress 100 とする
+/// 3. BRep に変換（progress 1〜89）。変換した形状を検査し、環境変数 STEP_HEAL=1 なら修復（縫合と ShapeFix）も試みる
+///    （形状は一度だけメッシュ化し、そのメッシュを検査・バウンディングボックス・プレビューに使う）
+/// 4. bucket_main に元ファイル `{content_hash}.{拡張子}`・BRep `{content_hash}`・manifest をアップロード
+///    （検査結果 `{content_hash}.check.json` と、STEP はヘッダと製品構造・元の長さ単位 `{content_hash}.json` も保存する。BRep は常に mm。
+///    ZIP はメンバーごとに変換・保存し、ZIP 自体と manifest も保存する）
+/// 5. 環境変数 STEP_PREVIEW=1 なら既定の GLB と PNG サムネイルを生成して bucket_temp にキャッシュする（progress 91〜99。ZIP は除く）
+/// 6. 結果の manifest を `_/{uuid}.result.json` に書いてから、検査の警告を付けて progress 100 とし、Ok(manifest) を返す
 ///
-/// 1. bucket_temp から `{uuid}.step` を読み込む
-/// 2. content_hash を計算して STEP -> BRep 変換（progress 1〜89）
-/// 3. bucket_main に `{content_hash}.step` / `{content_hash}.brep` をアップロード
-/// 4. Ok(content_hash) を返す
+/// 失敗したときの進捗 (progress 101) は書かない。再試行するかどうかを決める呼び出し側が
+/// `StepError::status` を write_status で書く。
 pub async fn step_pipeline(
 	uuid: &str,
 	bucket_temp: S3Storage,
 	bucket_main: S3Storage,
-) -> Result<String, String> {
+) -> Result<StepManifest, StepError> {
+	use StepErrorCode::*;
 	let step_key = format!("_/{uuid}.step");
-	let log_key = format!("_/{uuid}.log");
 
-	let progress: Arc<
-		dyn Fn(u32, String) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync,
-	> = Arc::new({
+	let progress: Progress = Arc::new({
 		let bucket_log = bucket_temp.clone();
-		move |p: u32, msg: String| {
-			let Ok(json) = serde_json::to_string(&StepStatusBody {
-				timestamp: std::time::SystemTime::now()
-					.duration_since(std::time::UNIX_EPOCH)
-					.unwrap_or_default()
-					.as_secs() as i64,
-				progress: p as i32,
-				message: msg,
-			}) else {
-				return Box::pin(async {});
-			};
+		let uuid = uuid.to_string();
+		move |p: u32, stage: StepStage, msg: String| {
 			let bucket = bucket_log.clone();
-			let key = log_key.clone();
-			Box::pin(async move {
-				let _ = bucket.write_bytes(&key, json.into_bytes()).await;
-			})
+			let uuid = uuid.clone();
+			Box::pin(
+				async move { write_status(&bucket, &uuid, status_body(p, Some(stage), msg)).await },
+			)
 		}
 	});
 
 	// ダウンロード
-	progress(1, "ダウンロード中".to_string()).await;
-	let (_, step_data) = {
-		let r = bucket_temp
-			.read(&step_key)
-			.await
-			.map_err(|e| format!("ダウンロード失敗: {e:?}"));
-		if let Err(ref e) = r {
-			progress(101, e.clone()).await;
+	progress(1, StepStage::Download, "ダウンロード中".to_string()).await;
+	let size = bucket_temp
+		.head(&step_key)
+		.await
+		.map_err(|e| format!("ダウンロード失敗: {e:?}"))
+		.map_err(StepError::of(DownloadFailed, StepStage::Download))?
+		.content_length;
+	if size > MAX_UPLOAD_SIZE {
+		return Err(StepError::new(
+			TooLarge,
+			StepStage::Download,
+			format!(
+				"ファイルが大きすぎます ({} MB。上限 {} MB)",
+				size / 1024 / 1024,
+				MAX_UPLOAD_SIZE / 1024 / 1024
+			),
+		));
+	}
+	let (_, step_data) = bucket_temp
+		.read(&step_key)
+		.await
+		.map_err(|e| format!("ダウンロード失敗: {e:?}"))
+		.map_err(StepError::of(DownloadFailed, StepStage::Download))?;
+
+	let content_hash = compute_hash(&step_data);
+	let format = InputFormat::detect(&step_data)
+		.map_err(StepError::of(UnsupportedFormat, StepStage::Detect))?;
+	let label = format.label();
+
+	// 同じファイルが変換済みなら読み込みを省略する
+	if let Some(manifest) = converted_manifest(&bucket_main, &content_hash, format).await {
+		let mut warnings = Vec::new();
+		for part in &manifest.parts {
+			let prefix = match part.name.as_str() {
+				"" => String::new(),
+				name => format!("{name}: "),
+			};
+			let part_warnings = stored_warnings(&bucket_main, part).await;
+			warnings.extend(part_warnings.into_iter().map(|w| format!("{prefix}{w}")));
+		}
+		let status = finished_status(StepStage::Cached, "変換済み", warnings);
+		finish(&bucket_temp, uuid, &manifest, status).await?;
+		return Ok(manifest);
+	}
+
+	if format == InputFormat::Archive {
+		let (manifest, warnings) =
+			archive_pipeline(content_hash, step_data, &progress, &bucket_main).await?;
+		let status = finished_status(StepStage::Upload, "完了", warnings);
+		finish(&bucket_temp, uuid, &manifest, status).await?;
+		return Ok(manifest);
+	}
+
+	// STEP は読み込む前に長さ単位を調べる（形状は OCCT が mm に換算して読み込む）
+	let (step_data, meta) = read_meta(format, step_data).await?;
+	let message = format!("{label}ファイル読み込み中{}", unit_note(meta.as_ref()));
+	progress(20, StepStage::Read, format!("{message} (0秒)")).await;
+	let shape = read_with_progress(&progress, format, step_data.clone(), (20, 70), message).await?;
+
+	progress(70, StepStage::Check, "形状チェック中".to_string()).await;
+	let (shape, mesh, report) = check_part(shape).await?;
+
+	progress(75, StepStage::Write, "BRepバイナリ書き込み中".to_string()).await;
+	let (shape, brep_data) = encode_part(shape).await?;
+	let bounding_box = mesh_bounding_box(&mesh);
+
+	// アップロード
+	progress(90, StepStage::Upload, "アップロード中".to_string()).await;
+	let manifest = single_manifest(content_hash, bounding_box);
+	let products = meta
+		.as_ref()
+		.map(|meta| meta.assembly.clone())
+		.unwrap_or_default();
+	store_part(
+		&bucket_main,
+		&manifest,
+		format,
+		step_data,
+		brep_data,
+		meta,
+		&report,
+	)
+	.await?;
+
+	preview_part(
+		shape,
+		mesh,
+		products,
+		&manifest.content_hash,
+		&progress,
+		&bucket_temp,
+	)
+	.await;
+	let status = finished_status(StepStage::Upload, "完了", report.warnings);
+	finish(&bucket_temp, uuid, &manifest, status).await?;
+	Ok(manifest)
+}
+
+/// ZIP アーカイブのメンバーを 1 つずつ展開・変換して `{メンバーの content_hash}` に保存する。
+/// 展開したサイズの合計が MAX_UPLOAD_SIZE を超えれば TooLarge で失敗する。
+/// progress 20〜89 をメンバー数で等分し、最後に元の ZIP と manifest を保存する。
+/// 検査の警告はメンバー名を付けて返す（progress 100 は呼び出し側が書く）。
+async fn archive_pipeline(
+	content_hash: String,
+	zip_data: Vec<u8>,
+	progress: &Progress,
+	bucket_main: &S3Storage,
+) -> Result<(StepManifest, Vec<String>), StepError> {
+	use StepErrorCode::*;
+	// メンバーは 1 つずつ展開して変換し、展開したものを同時にメモリに持たない
+	let members = format::ArchiveMembers::new(&zip_data, MAX_UPLOAD_SIZE as u64)?;
+	let count = members.len() as u32;
+	let mut parts = Vec::new();
+	let mut warnings = Vec::new();
+	for member in members {
+		let format::ArchiveMember {
+			position,
+			name,
+			format,
+			data,
+		} = member?;
+		let i = position as u32;
+		let start = 20 + 70 * i / count;
+		let end = 20 + 70 * (i + 1) / count;
+		let label = format.label();
+		let message = format!("{name} ({}/{count}) {label}ファイル読み込み中", i + 1);
+		progress(start, StepStage::Read, format!("{message} (0秒)")).await;
+		let part_hash = compute_hash(&data);
+		let (converted, part_warnings) = async {
+			// 別のアップロードで変換済みのメンバーは読み込みを省略する
+			if let Some(converted) = converted_manifest(bucket_main, &part_hash, format).await {
+				let warnings = match converted.parts.first() {
+					Some(part) => stored_warnings(bucket_main, part).await,
+					None => Vec::new(),
+				};
+				return Ok((converted, warnings));
+			}
+			let (data, meta) = read_meta(format, data).await?;
+			let message = format!("{message}{}", unit_note(meta.as_ref()));
+			let shape =
+				read_with_progress(progress, format, data.clone(), (start, end), message).await?;
+			progress(
+				end.saturating_sub(1).max(start),
+				StepStage::Check,
+				format!("{name} ({}/{count}) 形状チェック中", i + 1),
+			)
+			.await;
+			let (shape, mesh, report) = check_part(shape).await?;
+			let (_, brep_data) = encode_part(shape).await?;
+			let converted = single_manifest(part_hash.clone(), mesh_bounding_box(&mesh));
+			store_part(
+				bucket_main,
+				&converted,
+				format,
+				data,
+				brep_data,
+				meta,
+				&report,
+			)
+			.await?;
+			Ok((converted, report.warnings))
 		}
-		r?
+		.await
+		.map_err(|e: StepError| e.in_part(&name))?;
+		warnings.extend(part_warnings.into_iter().map(|w| format!("{name}: {w}")));
+		parts.push(StepManifestPart {
+			name,
+			content_hash: part_hash,
+			..converted.parts.into_iter().next().unwrap_or_default()
+		});
+	}
+
+	if parts.is_empty() {
+		return Err(StepError::new(
+			UnsupportedFormat,
+			StepStage::Detect,
+			"ZIP 内に変換できるファイルがありません".to_string(),
+		));
+	}
+
+	progress(90, StepStage::Upload, "アップロード中".to_string()).await;
+	let manifest = StepManifest {
+		content_hash: content_hash.clone(),
+		parts,
 	};
+	// manifest は変換済み判定に使うので ZIP の保存が済んでから書く
+	bucket_main
+		.write(
+			&format!("{content_hash}.zip"),
+			zip_data,
+			Some(InputFormat::Archive.content_type().to_string()),
+			None,
+			None,
+		)
+		.await
+		.map_err(|e| format!("ZIPアップロード失敗: {e:?}"))
+		.map_err(StepError::of(UploadFailed, StepStage::Upload))?;
+	write_json(
+		bucket_main,
+		&format!("{content_hash}.manifest.json"),
+		&manifest,
+		"manifest",
+	)
+	.await?;
+	Ok((manifest, warnings))
+}
 
-	let content_hash = compute_hash(&step_data);
+/// STEP ならヘッダと製品構造（長さ単位を含む）を spawn_blocking で読む。読めなければ None。
+/// data は読み終えたらそのまま返す
+async fn read_meta(
+	format: InputFormat,
+	data: Vec<u8>,
+) -> Result<(Vec<u8>, Option<StepMeta>), StepError> {
+	if format != InputFormat::Step {
+		return Ok((data, None));
+	}
+	tokio::task::spawn_blocking(move || {
+		let meta = metadata::step_meta(&data).ok();
+		(data, meta)
+	})
+	.await
+	.map_err(|e| format!("spawn_blocking失敗: {e:?}"))
+	.map_err(StepError::of(StepErrorCode::ParseFailed, StepStage::Read))
+}
 
-	progress(20, "STEPファイル読み込み中 (0秒)".to_string()).await;
+/// mm 以外の長さ単位のとき進捗に添える換算の説明
+fn unit_note(meta: Option<&StepMeta>) -> String {
+	match meta.and_then(|m| Some((m.length_unit.as_deref()?, m.mm_per_unit?))) {
+		Some((unit, mm)) if mm != 1.0 => format!(" ({unit}→mm換算)"),
+		_ => String::new(),
+	}
+}
 
-	// Shape::read_step 実行中、5秒ごとに進捗を更新するタスク
+/// read_shape を spawn_blocking で実行する。
+/// 実行中は 2 秒ごとに進捗を start から end 未満の範囲で書く（ReadSnapshot::status）。前半 (start〜split) は
+/// OCCT が入力を読み進めた割合とエンティティ数で進め、読み終えた後の形状変換は進み具合がわからないので
+/// split で止め、「進み具合は不明」と経過秒数を添える。
+async fn read_with_progress(
+	progress: &Progress,
+	format: InputFormat,
+	data: Vec<u8>,
+	(start, end): (u32, u32),
+	message: String,
+) -> Result<Shape, StepError> {
+	let read_progress = Arc::new(ReadProgress::default());
 	let task_heartbeat = tokio::spawn({
 		let progress = progress.clone();
+		let read_progress = read_progress.clone();
 		async move {
-			let start = tokio::time::Instant::now();
-			let mut tick = 0u32;
+			let last = end.saturating_sub(1).max(start);
+			let begin = tokio::time::Instant::now();
 			let mut iv = tokio::time::interval_at(
-				tokio::time::Instant::now() + std::time::Duration::from_secs(5),
-				std::time::Duration::from_secs(5),
+				tokio::time::Instant::now() + std::time::Duration::from_secs(2),
+				std::time::Duration::from_secs(2),
 			);
 			loop {
 				iv.tick().await;
-				tick += 1;
-				let elapsed = start.elapsed().as_secs();
-				let p = (20 + tick * 1).min(69);
-				progress(p, format!("STEPファイル読み込み中 ({elapsed}秒)")).await;
+				let elapsed = begin.elapsed().as_secs();
+				let (p, detail) = read_progress.snapshot().status((start, end));
+				progress(
+					p.min(last),
+					StepStage::Read,
+					format!("{message} {detail} ({elapsed}秒)"),
+				)
+				.await;
 			}
 		}
 	});
 
-	// Shape::read_step はブロッキングなので spawn_blocking へ
-	// chijin API: read_step(&mut impl Read) — Cursor でインメモリストリームを橋渡し
-	let step_data_clone = step_data.clone();
-	let task_read_step = tokio::task::spawn_blocking(move || {
-		Shape::read_step_with_colors(&mut std::io::Cursor::new(step_data_clone))
-	});
-	let shape_result = task_read_step
+	// 読み込みはブロッキングなので spawn_blocking へ
+	let task_read = tokio::task::spawn_blocking(move || read_shape(format, &data, &read_progress));
+	let shape_result = task_read
 		.await
 		.map_err(|e| format!("spawn_blocking失敗: {e:?}"))
-		.and_then(|r| r.map_err(|e| format!("STEP読み込み失敗: {e:?}")));
+		.and_then(|r| r);
 	task_heartbeat.abort();
-	let shape = {
-		if let Err(ref e) = shape_result {
-			progress(101, e.clone()).await;
-		}
-		shape_result?
+	shape_result.map_err(StepError::of(StepErrorCode::ParseFailed, StepStage::Read))
+}
+
+/// 形状を spawn_blocking でメッシュ化して検査し、メッシュとともに返す。メッシュはバウンディングボックスと
+/// プレビューにそのまま使い、同じ形状を何度もメッシュ化しない。
+/// 環境変数 STEP_HEAL=1 のときは、問題があれば修復して検査し直す（check_and_heal）
+async fn check_part(shape: Shape) -> Result<(Shape, PartMesh, StepCheckReport), StepError> {
+	let heal = std::env::var("STEP_HEAL").is_ok_and(|v| v == "1");
+	tokio::task::spawn_blocking(move || check_and_heal(shape, heal))
+		.await
+		.map_err(|e| format!("spawn_blocking失敗: {e:?}"))
+		.map_err(StepError::of(StepErrorCode::ParseFailed, StepStage::Check))
+}
+
+/// 形状をメッシュ化して検査する（ブロッキング）。heal なら、問題があるときに check::SEW_TOLERANCE 以内の隙間を
+/// 縫い合わせ、閉じたシェルをソリッドにして ShapeFix で直し、問題が減っていれば修復後の形状を使う
+fn check_and_heal(shape: Shape, heal: bool) -> (Shape, PartMesh, StepCheckReport) {
+	let mesh = shape_mesh(&shape);
+	let report = check::check_shape(&shape, mesh.as_ref());
+	if !heal || check::problem_count(&report) == 0 {
+		return (shape, mesh, report);
+	}
+	let Ok(healed) = crate::occt::heal(&shape, check::SEW_TOLERANCE) else {
+		return (shape, mesh, report);
+	};
+	let healed_mesh = shape_mesh(&healed);
+	match check::prefer_healed(&report, check::check_shape(&healed, healed_mesh.as_ref())) {
+		Some(healed_report) => (healed, healed_mesh, healed_report),
+		None => (shape, mesh, report),
+	}
+}
+
+/// 変換済みのファイルの保存済みの検査の警告。検査結果がなければ空
+async fn stored_warnings(bucket_main: &S3Storage, part: &StepManifestPart) -> Vec<String> {
+	let Ok((_, data)) = bucket_main
+		.read(&format!("{}.check.json", part.content_hash))
+		.await
+	else {
+		return Vec::new();
 	};
+	serde_json::from_slice::<StepCheckReport>(&data)
+		.map(|report| report.warnings)
+		.unwrap_or_default()
+}
+
+/// 変換した形状のメッシュ。メッシュ化できなかったときはそのエラー
+type PartMesh = Result<chijin::Mesh, String>;
+
+/// Shape を CHJC 形式（色メタデータ付きBRep）に書き出す。Shape はそのまま返す
+async fn encode_part(shape: Shape) -> Result<(Shape, Vec<u8>), StepError> {
+	tokio::task::spawn_blocking(move || {
+		let mut brep_data: Vec<u8> = Vec::new();
+		shape
+			.write_brep_color(&mut brep_data)
+			.map_err(|e| format!("BRep書き込み失敗: {e:?}"))?;
+		Ok((shape, brep_data))
+	})
+	.await
+	.map_err(|e| format!("spawn_blocking失敗: {e:?}"))
+	.and_then(|r| r)
+	.map_err(StepError::of(
+		StepErrorCode::BrepWriteFailed,
+		StepStage::Write,
+	))
+}
+
+/// メッシュのバウンディングボックス。メッシュ化できない形状はバウンディングボックスなしとする
+fn mesh_bounding_box(mesh: &PartMesh) -> Option<BoundingBox> {
+	let (min, max) = bounding_box(mesh.as_ref().ok()?)?;
+	Some(BoundingBox {
+		min: min.to_array().to_vec(),
+		max: max.to_array().to_vec(),
+	})
+}
 
-	progress(70, "BRepバイナリ書き込み中".to_string()).await;
+/// 環境変数 STEP_PREVIEW=1 のとき、StepNode 単体の `/shape` と `/shape/thumbnail.png`（既定の視点・サイズ）の結果を
+/// 先に生成して bucket_temp にキャッシュし、最初のリクエストがメッシュ化を待たないようにする。
+/// GLB のノード名には STEP の製品構造 products を使う。
+/// 失敗しても変換は成功とし、進捗のメッセージに書くだけにする（リクエスト時に改めて生成される）
+async fn preview_part(
+	shape: Shape,
+	mesh: PartMesh,
+	products: Vec<StepAssemblyNode>,
+	content_hash: &str,
+	progress: &Progress,
+	bucket_temp: &S3Storage,
+) {
+	if !std::env::var("STEP_PREVIEW").is_ok_and(|v| v == "1") {
+		return;
+	}
+	let node = ShapeNode::Step(StepNode {
+		content_hash: content_hash.to_string(),
+	});
+	if let Err(e) = write_previews(shape, mesh, products, &node, progress, bucket_temp).await {
+		progress(99, StepStage::Preview, format!("プレビュー生成失敗: {e}")).await;
+	}
+}
 
-	// chijin API: write_brep_color(&mut impl Write) — CHJC形式（色メタデータ付きBRep）
-	let mut brep_data: Vec<u8> = Vec::new();
-	let r = shape
-		.write_brep_color(&mut brep_data)
-		.map_err(|e| format!("BRep書き込み失敗: {e:?}"));
-	if let Err(ref e) = r {
-		progress(101, e.clone()).await;
+/// 既定の GLB `{shape_hash}.glb` と PNG サムネイル `{shape_hash}.thumbnail_{view}_{size}.png` を
+/// 検査でメッシュ化した mesh から作り、bucket_temp に保存する
+async fn write_previews(
+	shape: Shape,
+	mesh: PartMesh,
+	products: Vec<StepAssemblyNode>,
+	node: &ShapeNode,
+	progress: &Progress,
+	bucket_temp: &S3Storage,
+) -> Result<(), String> {
+	let mesh = mesh?;
+	// GLB はメッシュを持っていくので、先にサムネイルを描く
+	progress(91, StepStage::Preview, "サムネイル生成中".to_string()).await;
+	let (shape, mesh, png) = tokio::task::spawn_blocking(move || {
+		let png = thumbnail_view(THUMBNAIL_VIEW)
+			.ok_or_else(|| format!("unknown view '{THUMBNAIL_VIEW}'"))
+			.and_then(|toward| mesh_thumbnail_png(&shape, &mesh, toward, THUMBNAIL_SIZE as u32));
+		(shape, mesh, png)
+	})
+	.await
+	.map_err(|e| format!("spawn_blocking失敗: {e:?}"))?;
+	cache_encoded(
+		node,
+		&thumbnail_name(THUMBNAIL_VIEW, THUMBNAIL_SIZE),
+		"image/png",
+		png?,
+		bucket_temp,
+	)
+	.await?;
+
+	progress(95, StepStage::Preview, "GLB生成中".to_string()).await;
+	let glb = tokio::task::spawn_blocking(move || shape_to_glb(&shape, mesh, &products))
+		.await
+		.map_err(|e| format!("spawn_blocking失敗: {e:?}"))??;
+	cache_encoded(node, DEFAULT_GLB, "model/gltf-binary", glb, bucket_temp).await
+}
+
+/// 単一ファイルの manifest
+fn single_manifest(content_hash: String, bounding_box: Option<BoundingBox>) -> StepManifest {
+	StepManifest {
+		content_hash: content_hash.clone(),
+		parts: vec![StepManifestPart {
+			name: String::new(),
+			content_hash,
+			bounding_box,
+		}],
 	}
-	r?;
+}
 
-	// アップロード
-	progress(90, "アップロード中".to_string()).await;
-	let step_dst_key = format!("{content_hash}.step");
-	let brep_key = format!("{content_hash}");
+/// `{content_hash}` が変換済みなら保存済みの manifest を返す。
+/// manifest のない古い変換結果は BRep の有無だけで判定し、バウンディングボックスなしとする。
+async fn converted_manifest(
+	bucket_main: &S3Storage,
+	content_hash: &str,
+	format: InputFormat,
+) -> Option<StepManifest> {
+	if let Ok((_, data)) = bucket_main
+		.read(&format!("{content_hash}.manifest.json"))
+		.await && let Ok(manifest) = serde_json::from_slice(&data)
+	{
+		return Some(manifest);
+	}
+	if format == InputFormat::Archive {
+		return None;
+	}
+	bucket_main.head(content_hash).await.ok()?;
+	Some(single_manifest(content_hash.to_string(), None))
+}
+
+/// bucket_main に元ファイル `{content_hash}.{拡張子}`・BRep `{content_hash}`・検査結果 `{content_hash}.check.json`・
+/// STEP のメタデータ `{content_hash}.json` を保存し、
+/// すべて揃ってから manifest `{content_hash}.manifest.json` を保存する（変換済み判定に使う）
+async fn store_part(
+	bucket_main: &S3Storage,
+	manifest: &StepManifest,
+	format: InputFormat,
+	data: Vec<u8>,
+	brep_data: Vec<u8>,
+	meta: Option<StepMeta>,
+	report: &StepCheckReport,
+) -> Result<(), StepError> {
+	let content_hash = manifest.content_hash.as_str();
+	let label = format.label();
+	let step_dst_key = format!("{content_hash}.{}", format.extension());
 	let (r_step, r_brep) = tokio::join!(
 		bucket_main.write(
 			&step_dst_key,
-			step_data,
-			Some("application/step".to_string()),
+			data,
+			Some(format.content_type().to_string()),
 			None,
 			None,
 		),
 		bucket_main.write(
-			&brep_key,
+			content_hash,
 			brep_data,
 			Some("application/octet-stream".to_string()),
 			None,
 			None,
 		),
 	);
-	let r_step = r_step.map_err(|e| format!("STEPアップロード失敗: {e:?}"));
-	if let Err(ref e) = r_step {
-		progress(101, e.clone()).await;
+	r_step
+		.map_err(|e| format!("{label}アップロード失敗: {e:?}"))
+		.and(r_brep.map_err(|e| format!("BRepアップロード失敗: {e:?}")))
+		.map_err(StepError::of(
+			StepErrorCode::UploadFailed,
+			StepStage::Upload,
+		))?;
+	write_json(
+		bucket_main,
+		&format!("{content_hash}.check.json"),
+		report,
+		"検査結果",
+	)
+	.await?;
+	if let Some(meta) = meta {
+		write_json(
+			bucket_main,
+			&format!("{content_hash}.json"),
+			&meta,
+			"メタデータ",
+		)
+		.await?;
 	}
-	r_step?;
-	let r_brep = r_brep.map_err(|e| format!("BRepアップロード失敗: {e:?}"));
-	if let Err(ref e) = r_brep {
-		progress(101, e.clone()).await;
+	write_json(
+		bucket_main,
+		&format!("{content_hash}.manifest.json"),
+		manifest,
+		"manifest",
+	)
+	.await
+}
+
+/// bucket_main に JSON を保存する。label は失敗したときのメッセージに使う
+async fn write_json(
+	bucket_main: &S3Storage,
+	key: &str,
+	value: &impl serde::Serialize,
+	label: &str,
+) -> Result<(), StepError> {
+	let json = serde_json::to_vec(value)
+		.map_err(|e| format!("{label}のシリアライズ失敗: {e:?}"))
+		.map_err(StepError::of(
+			StepErrorCode::UploadFailed,
+			StepStage::Upload,
+		))?;
+	bucket_main
+		.write(key, json, Some("application/json".to_string()), None, None)
+		.await
+		.map_err(|e| format!("{label}アップロード失敗: {e:?}"))
+		.map_err(StepError::of(
+			StepErrorCode::UploadFailed,
+			StepStage::Upload,
+		))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn result_exists_whenever_status_is_finished() {
+		let manifest = StepManifest {
+			content_hash: "hash".to_string(),
+			..Default::default()
+		};
+		let status = finished_status(StepStage::Upload, "完了", vec![]);
+		// 書く順に 1 つずつ置き、その時点で読める進捗が 100 なら結果も読めること
+		let mut bucket = std::collections::HashMap::new();
+		for (key, body) in finished_objects("id", &manifest, &status).unwrap() {
+			bucket.insert(key, body);
+			let Some(log) = bucket.get("_/id.log") else {
+				continue;
+			};
+			let log: StepStatusBody = serde_json::from_slice(log).unwrap();
+			if log.progress == 100 {
+				let result = bucket.get(&result_key("id")).expect("結果がありません");
+				let result: StepManifest = serde_json::from_slice(result).unwrap();
+				assert_eq!(result.content_hash, "hash");
+			}
+		}
+		assert!(bucket.contains_key("_/id.log"));
 	}
-	r_brep?;
 
-	progress(100, "完了".to_string()).await;
-	Ok(content_hash)
+	#[test]
+	fn healing_sews_a_gap_between_faces() {
+		// 四面体の 1 面だけが頂点を共有せず、0.0001 mm ずれている
+		let gap = 1e-4;
+		let solid = mesh::MeshSolid {
+			vertices: vec![
+				[0.0, 0.0, 0.0],
+				[1.0, 0.0, 0.0],
+				[0.0, 1.0, 0.0],
+				[0.0, 0.0, 1.0],
+				[1.0 + gap, 0.0, 0.0],
+				[0.0, 1.0 + gap, 0.0],
+				[0.0, 0.0, 1.0 + gap],
+			],
+			triangles: vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [4, 5, 6]],
+			colors: vec![None, None, None, Some([1.0, 0.0, 0.0])],
+		};
+		let step = mesh::faceted_step(&[solid]);
+		let shape = Shape::read_step_with_colors(&mut step.as_slice()).unwrap();
+
+		let (_, _, report) = check_and_heal(shape.deep_copy(), false);
+		assert!(report.open_edges > 0);
+		assert_eq!(report.healed, None);
+
+		let (healed, _, report) = check_and_heal(shape, true);
+		assert_eq!((report.open_edges, report.healed), (0, Some(true)));
+		// 色は縫い合わせた面に付け替わる
+		assert_eq!(healed.colormap.len(), 1);
+	}
 }
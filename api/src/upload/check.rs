@@ -0,0 +1,316 @@
+//! 変換した形状の検査。
+//!
+//! chijin (OCCT) は BRepCheck を公開していないため、検査用の三角形メッシュとエッジの折れ線から調べる。
+//! - 開いたシェル: 同じ位置の頂点をまとめたうえで、1 つの三角形にしか使われない辺
+//! - 非多様体: 3 つ以上の三角形で共有される辺
+//! - 微小エッジ: 折れ線の長さが TINY_EDGE 未満のエッジ（長さ 0 の縮退エッジは除く）
+//! - 自己交差: 頂点を共有しない三角形の組で、一方の辺が他方の内部を貫くもの
+//!
+//! メッシュは許容差 0.1 mm で近似しているため、それより近い面どうしは交差と判定されることがある。
+
+use crate::encode::shape_edges;
+use crate::openapi::StepCheckReport;
+use chijin::Shape;
+use glam::DVec3;
+use std::collections::HashMap;
+
+/// 微小エッジとみなす長さ (mm)
+pub const TINY_EDGE: f64 = 0.01;
+
+/// 修復 (STEP_HEAL=1) で縫い合わせる隙間の上限 (mm)
+pub const SEW_TOLERANCE: f64 = 0.01;
+
+/// 同じ位置とみなす頂点の間隔 (mm)
+const WELD: f64 = 1e-6;
+
+/// 自己交差の検査で調べる三角形の組の上限。超えたら検査を打ち切る
+const MAX_PAIR_TESTS: usize = 50_000_000;
+
+/// 数える自己交差の上限
+const MAX_INTERSECTIONS: usize = 1000;
+
+/// Shape を shape_mesh でメッシュ化した結果 mesh とともに検査する。メッシュ化できなければその旨の警告だけを返す
+pub fn check_shape(shape: &Shape, mesh: Result<&chijin::Mesh, &String>) -> StepCheckReport {
+	match mesh {
+		Ok(mesh) => check_mesh(mesh, &shape_edges(shape)),
+		Err(e) => StepCheckReport {
+			warnings: vec![format!("メッシュ化できないため形状を検査できません: {e}")],
+			..Default::default()
+		},
+	}
+}
+
+/// 警告の対象になった問題の数（修復の前後の比較に使う）
+pub fn problem_count(report: &StepCheckReport) -> i64 {
+	[
+		report.open_edges,
+		report.non_manifold_edges,
+		report.tiny_edges,
+		report.self_intersections.unwrap_or(0),
+	]
+	.iter()
+	.map(|&n| n as i64)
+	.sum()
+}
+
+/// 修復した形状の検査結果を使うか。問題が減ったときだけ healed を立てた検査結果を返す
+pub fn prefer_healed(report: &StepCheckReport, healed: StepCheckReport) -> Option<StepCheckReport> {
+	(problem_count(&healed) < problem_count(report)).then_some(StepCheckReport {
+		healed: Some(true),
+		..healed
+	})
+}
+
+/// メッシュとエッジの折れ線を検査する
+pub fn check_mesh(mesh: &chijin::Mesh, edges: &[Vec<DVec3>]) -> StepCheckReport {
+	let (points, ids) = weld(&mesh.vertices);
+	let triangles: Vec<[usize; 3]> = mesh
+		.indices
+		.chunks_exact(3)
+		.map(|t| [ids[t[0]], ids[t[1]], ids[t[2]]])
+		.filter(|[a, b, c]| a != b && b != c && c != a)
+		.collect();
+
+	let mut uses: HashMap<(usize, usize), u32> = HashMap::new();
+	for &[a, b, c] in &triangles {
+		for (p, q) in [(a, b), (b, c), (c, a)] {
+			*uses.entry((p.min(q), p.max(q))).or_default() += 1;
+		}
+	}
+	let tiny_edges = edges
+		.iter()
+		.map(|points| points.windows(2).map(|w| w[0].distance(w[1])).sum::<f64>())
+		.filter(|&length| length > 0.0 && length < TINY_EDGE)
+		.count();
+
+	let mut report = StepCheckReport {
+		open_edges: uses.values().filter(|&&n| n == 1).count() as i32,
+		non_manifold_edges: uses.values().filter(|&&n| n > 2).count() as i32,
+		tiny_edges: tiny_edges as i32,
+		self_intersections: self_intersections(&points, &triangles).map(|n| n as i32),
+		healed: None,
+		warnings: Vec::new(),
+	};
+	report.warnings = warnings(&report);
+	report
+}
+
+/// 検査結果の警告（日本語）。問題がなければ空
+pub fn warnings(report: &StepCheckReport) -> Vec<String> {
+	let mut warnings = Vec::new();
+	if report.open_edges > 0 {
+		warnings.push(format!(
+			"開いたシェルがあります（境界の辺 {} 本）。閉じたソリッドになっていない面があります",
+			report.open_edges
+		));
+	}
+	if report.non_manifold_edges > 0 {
+		warnings.push(format!(
+			"非多様体の辺が {} 本あります",
+			report.non_manifold_edges
+		));
+	}
+	if report.tiny_edges > 0 {
+		warnings.push(format!(
+			"{TINY_EDGE} mm 未満の微小エッジが {} 本あります",
+			report.tiny_edges
+		));
+	}
+	match report.self_intersections {
+		Some(0) => {}
+		Some(n) => warnings.push(format!("自己交差している箇所があります（三角形の組 {n}）")),
+		None => warnings.push("形状が複雑なため自己交差は検査していません".to_string()),
+	}
+	warnings
+}
+
+/// 同じ位置の頂点をまとめる。(まとめた頂点, 元の頂点ごとのまとめた番号)
+fn weld(vertices: &[DVec3]) -> (Vec<DVec3>, Vec<usize>) {
+	let mut index: HashMap<(i64, i64, i64), usize> = HashMap::new();
+	let mut points = Vec::new();
+	let ids = vertices
+		.iter()
+		.map(|v| {
+			let key = (
+				(v.x / WELD).round() as i64,
+				(v.y / WELD).round() as i64,
+				(v.z / WELD).round() as i64,
+			);
+			*index.entry(key).or_insert_with(|| {
+				points.push(*v);
+				points.len() - 1
+			})
+		})
+		.collect();
+	(points, ids)
+}
+
+/// 交差している三角形の組の数（MAX_INTERSECTIONS で打ち切る）。調べる組が多すぎれば None。
+/// 全体の最も長い軸に沿って sweep and prune で候補を絞る
+fn self_intersections(points: &[DVec3], triangles: &[[usize; 3]]) -> Option<usize> {
+	let boxes: Vec<(DVec3, DVec3)> = triangles
+		.iter()
+		.map(|t| {
+			let [a, b, c] = t.map(|i| points[i]);
+			(a.min(b).min(c), a.max(b).max(c))
+		})
+		.collect();
+	let Some(extent) = boxes
+		.iter()
+		.copied()
+		.reduce(|(lo, hi), (l, h)| (lo.min(l), hi.max(h)))
+		.map(|(lo, hi)| hi - lo)
+	else {
+		return Some(0);
+	};
+	let axis = if extent.x >= extent.y && extent.x >= extent.z {
+		0
+	} else if extent.y >= extent.z {
+		1
+	} else {
+		2
+	};
+
+	let mut order: Vec<usize> = (0..triangles.len()).collect();
+	order.sort_by(|&i, &j| boxes[i].0[axis].total_cmp(&boxes[j].0[axis]));
+	let mut active: Vec<usize> = Vec::new();
+	let mut tests = 0;
+	let mut found = 0;
+	for i in order {
+		let (lo, hi) = boxes[i];
+		active.retain(|&j| boxes[j].1[axis] >= lo[axis]);
+		for &j in &active {
+			tests += 1;
+			if tests > MAX_PAIR_TESTS {
+				return None;
+			}
+			let (l, h) = boxes[j];
+			if lo.cmpgt(h).any() || l.cmpgt(hi).any() {
+				continue;
+			}
+			let (a, b) = (triangles[i], triangles[j]);
+			if a.iter().any(|v| b.contains(v)) {
+				continue;
+			}
+			let a = a.map(|v| points[v]);
+			let b = b.map(|v| points[v]);
+			if crosses(a, b) || crosses(b, a) {
+				found += 1;
+				if found >= MAX_INTERSECTIONS {
+					return Some(found);
+				}
+			}
+		}
+		active.push(i);
+	}
+	Some(found)
+}
+
+/// 三角形 a の辺のいずれかが三角形 b の内部を貫くか
+fn crosses(a: [DVec3; 3], b: [DVec3; 3]) -> bool {
+	[(a[0], a[1]), (a[1], a[2]), (a[2], a[0])]
+		.into_iter()
+		.any(|(p, q)| segment_hits(p, q, b))
+}
+
+/// 線分 p–q が三角形 t の内部を通るか（Möller–Trumbore）。端や辺に触れるだけなら false
+fn segment_hits(p: DVec3, q: DVec3, [a, b, c]: [DVec3; 3]) -> bool {
+	const EPS: f64 = 1e-9;
+	let dir = q - p;
+	let (e1, e2) = (b - a, c - a);
+	let h = dir.cross(e2);
+	let det = e1.dot(h);
+	// 平行（同一平面上の重なりは扱わない）
+	if det.abs() <= EPS * e1.length() * e2.length() * dir.length() {
+		return false;
+	}
+	let s = p - a;
+	let u = s.dot(h) / det;
+	if u <= EPS || u >= 1.0 - EPS {
+		return false;
+	}
+	let r = s.cross(e1);
+	let v = dir.dot(r) / det;
+	if v <= EPS || u + v >= 1.0 - EPS {
+		return false;
+	}
+	let t = e2.dot(r) / det;
+	t > EPS && t < 1.0 - EPS
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// 面ごとに頂点を持つ（OCCT のメッシュと同じ）三角形の集まり
+	fn mesh(triangles: &[[DVec3; 3]]) -> chijin::Mesh {
+		chijin::Mesh {
+			vertices: triangles.iter().flatten().copied().collect(),
+			uvs: Vec::new(),
+			normals: Vec::new(),
+			indices: (0..triangles.len() * 3).collect(),
+			face_ids: vec![0; triangles.len()],
+		}
+	}
+
+	fn tetrahedron() -> Vec<[DVec3; 3]> {
+		let [o, x, y, z] = [DVec3::ZERO, DVec3::X, DVec3::Y, DVec3::Z];
+		vec![[o, y, x], [o, x, z], [o, z, y], [x, y, z]]
+	}
+
+	#[test]
+	fn closed_solid_has_no_warnings() {
+		let report = check_mesh(&mesh(&tetrahedron()), &[]);
+		assert_eq!((report.open_edges, report.non_manifold_edges), (0, 0));
+		assert_eq!(report.self_intersections, Some(0));
+		assert!(report.warnings.is_empty());
+	}
+
+	#[test]
+	fn open_shell_and_tiny_edges() {
+		let mut triangles = tetrahedron();
+		triangles.pop();
+		let edges = vec![
+			vec![DVec3::ZERO, DVec3::new(0.001, 0.0, 0.0)],
+			// 縮退エッジ（球の極など）は数えない
+			vec![DVec3::ONE, DVec3::ONE],
+			vec![DVec3::ZERO, DVec3::X],
+		];
+		let report = check_mesh(&mesh(&triangles), &edges);
+		assert_eq!(report.open_edges, 3);
+		assert_eq!(report.tiny_edges, 1);
+		assert_eq!(report.warnings.len(), 2);
+	}
+
+	#[test]
+	fn healed_report_is_used_only_when_it_improves() {
+		// 開いたシェルの 1 辺が微小エッジに分割されている。修復で穴がふさがり、辺もつながる
+		let mut triangles = tetrahedron();
+		triangles.pop();
+		let split = vec![
+			vec![DVec3::ZERO, DVec3::new(0.001, 0.0, 0.0)],
+			vec![DVec3::new(0.001, 0.0, 0.0), DVec3::X],
+		];
+		let report = check_mesh(&mesh(&triangles), &split);
+		let closed = check_mesh(&mesh(&tetrahedron()), &[vec![DVec3::ZERO, DVec3::X]]);
+		let healed = prefer_healed(&report, closed.clone()).unwrap();
+		assert_eq!(healed.healed, Some(true));
+		assert_eq!((healed.open_edges, healed.tiny_edges), (0, 0));
+		assert!(prefer_healed(&closed, closed.clone()).is_none());
+	}
+
+	#[test]
+	fn crossing_triangles_are_self_intersections() {
+		let mut triangles = tetrahedron();
+		// 四面体の底面・x=0 の面・斜めの面を貫く別の三角形
+		let (a, b, c) = (
+			DVec3::new(0.2, 0.2, -1.0),
+			DVec3::new(0.2, 0.2, 2.0),
+			DVec3::new(-1.0, 0.2, 0.5),
+		);
+		triangles.push([a, b, c]);
+		let report = check_mesh(&mesh(&triangles), &[]);
+		assert_eq!(report.self_intersections, Some(3));
+		assert!(report.warnings.iter().any(|w| w.contains("自己交差")));
+	}
+}
@@ -0,0 +1,171 @@
+//! 変換パイプラインの失敗の分類。
+//!
+//! StepStatusBody の error_code / stage / error_message に書き、ウィジェット側で
+//! 言語に合わせた表示や「別形式で再アップロード」などの案内に使う。
+
+use crate::openapi::StepStatusBody;
+
+/// アップロードを受け付ける最大サイズ（Lambda のメモリ 2048MB で読み込める目安）
+pub const MAX_UPLOAD_SIZE: i64 = 256 * 1024 * 1024;
+
+/// 失敗の種類（StepStatusBody.error_code）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepErrorCode {
+	DownloadFailed,
+	ParseFailed,
+	BrepWriteFailed,
+	UploadFailed,
+	TooLarge,
+	UnsupportedFormat,
+}
+
+impl StepErrorCode {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::DownloadFailed => "download_failed",
+			Self::ParseFailed => "parse_failed",
+			Self::BrepWriteFailed => "brep_write_failed",
+			Self::UploadFailed => "upload_failed",
+			Self::TooLarge => "too_large",
+			Self::UnsupportedFormat => "unsupported_format",
+		}
+	}
+
+	/// 英語の説明
+	fn english(self) -> String {
+		match self {
+			Self::DownloadFailed => "Failed to download the uploaded file.".to_string(),
+			Self::ParseFailed => {
+				"The uploaded file could not be read. It may be corrupted or contain unsupported geometry."
+					.to_string()
+			}
+			Self::BrepWriteFailed => "Failed to convert the model to BRep.".to_string(),
+			Self::UploadFailed => "Failed to store the converted files.".to_string(),
+			Self::TooLarge => format!(
+				"The uploaded file exceeds the {} MB limit.",
+				MAX_UPLOAD_SIZE / 1024 / 1024
+			),
+			Self::UnsupportedFormat => {
+				"Unsupported file format. Upload STEP, BRep, STL, 3MF or a ZIP of them.".to_string()
+			}
+		}
+	}
+
+	/// S3 の一時的な失敗など、同じファイルで再試行すれば成功しうるもの
+	pub fn retryable(self) -> bool {
+		matches!(self, Self::DownloadFailed | Self::UploadFailed)
+	}
+}
+
+/// 処理の段階（StepStatusBody.stage）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepStage {
+	Download,
+	Detect,
+	Read,
+	Check,
+	Write,
+	Upload,
+	Preview,
+	/// 同じファイルが変換済みで、読み込みを省いた
+	Cached,
+}
+
+impl StepStage {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Download => "download",
+			Self::Detect => "detect",
+			Self::Read => "read",
+			Self::Check => "check",
+			Self::Write => "write",
+			Self::Upload => "upload",
+			Self::Preview => "preview",
+			Self::Cached => "cached",
+		}
+	}
+}
+
+/// 変換の失敗。message は従来どおりの日本語の詳細
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepError {
+	pub code: StepErrorCode,
+	pub stage: StepStage,
+	pub message: String,
+	english: String,
+}
+
+impl StepError {
+	pub fn new(code: StepErrorCode, stage: StepStage, message: String) -> Self {
+		Self {
+			code,
+			stage,
+			message,
+			english: code.english(),
+		}
+	}
+
+	/// `map_err` 用: 日本語の詳細から StepError を作る
+	pub fn of(code: StepErrorCode, stage: StepStage) -> impl FnOnce(String) -> Self {
+		move |message| Self::new(code, stage, message)
+	}
+
+	/// ZIP のメンバーで失敗したときにファイル名を付ける
+	pub fn in_part(self, name: &str) -> Self {
+		Self {
+			message: format!("{name}: {}", self.message),
+			english: format!("{} (in {name})", self.english),
+			..self
+		}
+	}
+
+	/// 失敗を表す進捗 (progress 101)
+	pub fn status(&self) -> StepStatusBody {
+		StepStatusBody {
+			error_code: Some(self.code.as_str().to_string()),
+			error_message: Some(self.english.clone()),
+			..super::status_body(101, Some(self.stage), self.message.clone())
+		}
+	}
+}
+
+impl std::fmt::Display for StepError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.message)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn failure_status_carries_code_stage_and_english_message() {
+		let error = StepError::new(
+			StepErrorCode::ParseFailed,
+			StepStage::Read,
+			"STEP読み込み失敗: x".to_string(),
+		)
+		.in_part("parts/a.step");
+		let status = error.status();
+		assert_eq!(status.progress, 101);
+		assert_eq!(status.message, "parts/a.step: STEP読み込み失敗: x");
+		assert_eq!(status.error_code.as_deref(), Some("parse_failed"));
+		assert_eq!(status.stage.as_deref(), Some("read"));
+		let english = status.error_message.unwrap();
+		assert!(english.starts_with("The uploaded file could not be read."));
+		assert!(english.ends_with("(in parts/a.step)"));
+	}
+
+	#[test]
+	fn only_storage_failures_are_retryable() {
+		assert!(StepErrorCode::DownloadFailed.retryable());
+		assert!(StepErrorCode::UploadFailed.retryable());
+		assert!(!StepErrorCode::ParseFailed.retryable());
+		assert!(!StepErrorCode::TooLarge.retryable());
+		assert_eq!(
+			StepErrorCode::TooLarge.english(),
+			"The uploaded file exceeds the 256 MB limit."
+		);
+	}
+}
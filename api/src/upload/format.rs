@@ -0,0 +1,370 @@
+//! アップロードされたファイルの形式判定と Shape への読み込み。
+//!
+//! アップロード先のキー (`_/{uuid}.step`) は拡張子を持たないため、形式は中身の先頭から判定する。
+
+use super::error::{MAX_UPLOAD_SIZE, StepError, StepErrorCode, StepStage};
+use super::mesh;
+use super::read_progress::ReadProgress;
+use chijin::Shape;
+
+/// アップロードを受け付けるファイル形式
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputFormat {
+	Step,
+	Iges,
+	/// chijin の色付き BRep (CHJC形式)
+	BrepColor,
+	/// OCCT 標準のテキスト BRep
+	BrepText,
+	/// OCCT 標準のバイナリ BRep
+	BrepBin,
+	Stl,
+	ThreeMf,
+	/// 複数ファイルをまとめた ZIP（メンバーごとに変換する）
+	Archive,
+}
+
+impl InputFormat {
+	/// ファイルの先頭から形式を判定する
+	pub fn detect(data: &[u8]) -> Result<Self, String> {
+		let text = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+		let text = &text[text.iter().take_while(|b| b.is_ascii_whitespace()).count()..];
+		let head = &text[..text.len().min(128)];
+		let contains = |needle: &[u8]| head.windows(needle.len()).any(|w| w == needle);
+
+		if data.starts_with(b"CHJC") {
+			Ok(Self::BrepColor)
+		} else if text.starts_with(b"ISO-10303-21") {
+			Ok(Self::Step)
+		} else if data.starts_with(b"PK\x03\x04") {
+			if contains_zip_entry(data, "3D/3dmodel.model") {
+				Ok(Self::ThreeMf)
+			} else {
+				Ok(Self::Archive)
+			}
+		} else if contains(b"Open CASCADE Topology") {
+			Ok(Self::BrepBin)
+		} else if contains(b"CASCADE Topology V") || text.starts_with(b"DBRep_DrawableShape") {
+			Ok(Self::BrepText)
+		} else if is_iges(text) {
+			Ok(Self::Iges)
+		} else if mesh::is_binary_stl(data) || (text.starts_with(b"solid") && contains_facet(text))
+		{
+			Ok(Self::Stl)
+		} else {
+			Err("対応していないファイル形式です (STEP / IGES / BRep / STL / 3MF)".to_string())
+		}
+	}
+
+	/// 進捗メッセージに使う形式名
+	pub fn label(self) -> &'static str {
+		match self {
+			Self::Step => "STEP",
+			Self::Iges => "IGES",
+			Self::BrepColor | Self::BrepText | Self::BrepBin => "BRep",
+			Self::Stl => "STL",
+			Self::ThreeMf => "3MF",
+			Self::Archive => "ZIP",
+		}
+	}
+
+	/// bucket_main に元ファイルを保存するときの拡張子
+	pub fn extension(self) -> &'static str {
+		match self {
+			Self::Step => "step",
+			Self::Iges => "igs",
+			Self::BrepColor | Self::BrepText | Self::BrepBin => "brep",
+			Self::Stl => "stl",
+			Self::ThreeMf => "3mf",
+			Self::Archive => "zip",
+		}
+	}
+
+	/// bucket_main に元ファイルを保存するときの MIME タイプ
+	pub fn content_type(self) -> &'static str {
+		match self {
+			Self::Step => "application/step",
+			Self::Iges => "model/iges",
+			Self::BrepColor | Self::BrepText | Self::BrepBin => "application/octet-stream",
+			Self::Stl => "model/stl",
+			Self::ThreeMf => "model/3mf",
+			Self::Archive => "application/zip",
+		}
+	}
+}
+
+/// 判定した形式で Shape を読み込む（ブロッキング）。
+/// STL / 3MF は三角形ごとの平面を面とする STEP に変換してから読む。
+/// OCCT が入力を読み進めた位置は progress に書く。
+pub fn read_shape(
+	format: InputFormat,
+	data: &[u8],
+	progress: &ReadProgress,
+) -> Result<Shape, String> {
+	match format {
+		InputFormat::Step => Shape::read_step_with_colors(&mut progress.reader(data, true))
+			.map_err(|e| format!("STEP読み込み失敗: {e:?}")),
+		InputFormat::Iges => crate::occt::read_iges(&mut progress.reader(data, false)),
+		InputFormat::BrepColor => Shape::read_brep_color(&mut progress.reader(data, false))
+			.map_err(|e| format!("BRep読み込み失敗: {e:?}")),
+		InputFormat::BrepText => Shape::read_brep_text(&mut progress.reader(data, false))
+			.map_err(|e| format!("BRep読み込み失敗: {e:?}")),
+		InputFormat::BrepBin => Shape::read_brep_bin(&mut progress.reader(data, false))
+			.map_err(|e| format!("BRep読み込み失敗: {e:?}")),
+		InputFormat::Stl | InputFormat::ThreeMf => {
+			let solids = if format == InputFormat::Stl {
+				mesh::read_stl(data)?
+			} else {
+				mesh::read_3mf(data)?
+			};
+			let step = mesh::faceted_step(&solids);
+			Shape::read_step_with_colors(&mut progress.reader(&step, true))
+				.map_err(|e| format!("{}読み込み失敗: {e:?}", format.label()))
+		}
+		InputFormat::Archive => {
+			Err("ZIP はメンバーごとに読み込んでください (archive_members)".to_string())
+		}
+	}
+}
+
+/// ZIP から取り出した変換できるメンバー
+#[derive(Debug)]
+pub struct ArchiveMember {
+	/// 候補のメンバー（ArchiveMembers::len で数えたもの）の中での番号。0 始まり
+	pub position: usize,
+	pub name: String,
+	pub format: InputFormat,
+	pub data: Vec<u8>,
+}
+
+/// ZIP のメンバーのうち変換できるものを 1 つずつ取り出す。
+/// ディレクトリ・macOS のメタデータ・対応していない形式・入れ子の ZIP は読み飛ばす（1 つも出ないこともある）。
+/// 展開したサイズの合計は limit までとし、超えたら TooLarge で止める（小さな ZIP 爆弾でメモリを使い切らないため）。
+pub struct ArchiveMembers<'a> {
+	archive: zip::ZipArchive<std::io::Cursor<&'a [u8]>>,
+	/// 名前で読み飛ばさなかったメンバーの番号
+	indices: std::vec::IntoIter<usize>,
+	/// 取り出した候補の数
+	taken: usize,
+	/// 展開できる残りのバイト数
+	remaining: u64,
+}
+
+impl<'a> ArchiveMembers<'a> {
+	pub fn new(data: &'a [u8], limit: u64) -> Result<Self, StepError> {
+		let archive = zip::ZipArchive::new(std::io::Cursor::new(data))
+			.map_err(|e| format!("ZIP読み込み失敗: {e:?}"))
+			.map_err(StepError::of(StepErrorCode::ParseFailed, StepStage::Detect))?;
+		let indices: Vec<usize> = (0..archive.len())
+			.filter(|&i| {
+				let name = archive.name_for_index(i).unwrap_or_default();
+				let base_name = name.rsplit('/').next().unwrap_or_default();
+				!(name.ends_with('/')
+					|| name.starts_with("__MACOSX/")
+					|| base_name.starts_with("._"))
+			})
+			.collect();
+		Ok(Self {
+			archive,
+			indices: indices.into_iter(),
+			taken: 0,
+			remaining: limit,
+		})
+	}
+
+	/// 候補のメンバーの数（形式は中身を読むまでわからないので、読み飛ばすものも含む）
+	pub fn len(&self) -> usize {
+		self.taken + self.indices.len()
+	}
+
+	/// index のメンバーを展開する。変換できない形式なら None
+	fn read(&mut self, index: usize) -> Result<Option<ArchiveMember>, StepError> {
+		use StepErrorCode::*;
+		let too_large = || {
+			StepError::new(
+				TooLarge,
+				StepStage::Detect,
+				format!(
+					"ZIP の展開後のサイズが上限 {} MB を超えています",
+					MAX_UPLOAD_SIZE / 1024 / 1024
+				),
+			)
+		};
+		let file = self
+			.archive
+			.by_index(index)
+			.map_err(|e| format!("ZIP読み込み失敗: {e:?}"))
+			.map_err(StepError::of(ParseFailed, StepStage::Detect))?;
+		let name = file.name().to_string();
+		if file.is_dir() {
+			return Ok(None);
+		}
+		// 宣言されたサイズは偽れるので、読むときも残りのバイト数 + 1 で打ち切って確かめる
+		if file.size() > self.remaining {
+			return Err(too_large());
+		}
+		let mut content = Vec::with_capacity(file.size() as usize);
+		std::io::Read::read_to_end(
+			&mut std::io::Read::take(file, self.remaining + 1),
+			&mut content,
+		)
+		.map_err(|e| format!("ZIP展開失敗 ({name}): {e:?}"))
+		.map_err(StepError::of(ParseFailed, StepStage::Detect))?;
+		if content.len() as u64 > self.remaining {
+			return Err(too_large());
+		}
+		self.remaining -= content.len() as u64;
+		Ok(match InputFormat::detect(&content) {
+			Ok(InputFormat::Archive) | Err(_) => None,
+			Ok(format) => Some(ArchiveMember {
+				position: self.taken - 1,
+				name,
+				format,
+				data: content,
+			}),
+		})
+	}
+}
+
+impl Iterator for ArchiveMembers<'_> {
+	type Item = Result<ArchiveMember, StepError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		while let Some(index) = self.indices.next() {
+			self.taken += 1;
+			match self.read(index) {
+				Ok(None) => continue,
+				Ok(Some(member)) => return Some(Ok(member)),
+				Err(e) => {
+					// 失敗したら以降のメンバーは読まない
+					self.taken += self.indices.len();
+					self.indices = Vec::new().into_iter();
+					return Some(Err(e));
+				}
+			}
+		}
+		None
+	}
+}
+
+/// IGES は 80 桁固定長で、1 行目の 73 桁目がスタート部の 'S'
+fn is_iges(text: &[u8]) -> bool {
+	let line = text.split(|&b| b == b'\n').next().unwrap_or_default();
+	let line = line.strip_suffix(b"\r").unwrap_or(line);
+	line.len() == 80 && line[72] == b'S'
+}
+
+fn contains_facet(text: &[u8]) -> bool {
+	let head = &text[..text.len().min(1024)];
+	head.windows(5).any(|w| w == b"facet")
+}
+
+fn contains_zip_entry(data: &[u8], name: &str) -> bool {
+	zip::ZipArchive::new(std::io::Cursor::new(data))
+		.is_ok_and(|archive| archive.file_names().any(|n| n.eq_ignore_ascii_case(name)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	#[test]
+	fn detect_by_content() {
+		let detect = |data: &[u8]| InputFormat::detect(data);
+		assert_eq!(detect(b"ISO-10303-21;\nHEADER;"), Ok(InputFormat::Step));
+		assert_eq!(
+			detect(b"\xEF\xBB\xBF\r\nISO-10303-21;\nHEADER;"),
+			Ok(InputFormat::Step)
+		);
+		assert_eq!(detect(b"CHJC\x01"), Ok(InputFormat::BrepColor));
+		assert_eq!(
+			detect(b"\nCASCADE Topology V3, (c) Open Cascade\nLocations 0"),
+			Ok(InputFormat::BrepText)
+		);
+		assert_eq!(
+			detect(b"Open CASCADE Topology V3 (c) Open Cascade\n"),
+			Ok(InputFormat::BrepBin)
+		);
+		let iges = format!("{:<72}S{:>7}\n", "start", 1);
+		assert_eq!(detect(iges.as_bytes()), Ok(InputFormat::Iges));
+		assert_eq!(
+			detect(b"solid x\n facet normal 0 0 1\n"),
+			Ok(InputFormat::Stl)
+		);
+		let mut stl = b"solid binary header".to_vec();
+		stl.resize(80, 0);
+		stl.extend_from_slice(&1u32.to_le_bytes());
+		stl.extend_from_slice(&[0u8; 50]);
+		assert_eq!(detect(&stl), Ok(InputFormat::Stl));
+		assert!(detect(b"hello").is_err());
+	}
+
+	#[test]
+	fn detect_3mf_by_model_entry() {
+		let zip = |name: &str| {
+			let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+			zip.start_file(name, zip::write::SimpleFileOptions::default())
+				.unwrap();
+			zip.write_all(b"<model/>").unwrap();
+			zip.finish().unwrap().into_inner()
+		};
+		assert_eq!(
+			InputFormat::detect(&zip("3D/3dmodel.model")),
+			Ok(InputFormat::ThreeMf)
+		);
+		assert_eq!(
+			InputFormat::detect(&zip("a.step")),
+			Ok(InputFormat::Archive)
+		);
+	}
+
+	#[test]
+	fn archive_members_skip_unsupported_entries() {
+		let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+		let options = zip::write::SimpleFileOptions::default();
+		for (name, content) in [
+			("parts/a.step", &b"ISO-10303-21;\nHEADER;"[..]),
+			("README.txt", b"hello"),
+			("__MACOSX/parts/._a.step", b"ISO-10303-21;"),
+			("parts/b.stl", b"solid b\n facet normal 0 0 1\n"),
+		] {
+			zip.start_file(name, options).unwrap();
+			zip.write_all(content).unwrap();
+		}
+		zip.add_directory("empty/", options).unwrap();
+		let data = zip.finish().unwrap().into_inner();
+		let members = ArchiveMembers::new(&data, 1024).unwrap();
+		// ディレクトリと macOS のメタデータは名前だけで除く
+		assert_eq!(members.len(), 3);
+		let members: Vec<_> = members.collect::<Result<_, _>>().unwrap();
+		let names: Vec<(usize, &str, InputFormat)> = members
+			.iter()
+			.map(|member| (member.position, member.name.as_str(), member.format))
+			.collect();
+		assert_eq!(
+			names,
+			[
+				(0, "parts/a.step", InputFormat::Step),
+				(2, "parts/b.stl", InputFormat::Stl)
+			]
+		);
+	}
+
+	#[test]
+	fn archive_members_stop_at_the_size_limit() {
+		let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+		let options = zip::write::SimpleFileOptions::default();
+		for name in ["a.step", "b.step"] {
+			zip.start_file(name, options).unwrap();
+			zip.write_all(b"ISO-10303-21;\nHEADER;").unwrap();
+			zip.write_all(&[b' '; 1000]).unwrap();
+		}
+		let data = zip.finish().unwrap().into_inner();
+		let mut members = ArchiveMembers::new(&data, 1500).unwrap();
+		assert!(members.next().unwrap().is_ok());
+		let error = members.next().unwrap().unwrap_err();
+		assert_eq!(error.code, StepErrorCode::TooLarge);
+		assert!(members.next().is_none());
+	}
+}
@@ -0,0 +1,655 @@
+//! STL / 3MF の三角形メッシュを、三角形ごとの平面を面とする STEP (FACETED_BREP) に変換する。
+//!
+//! chijin はメッシュから Shape を作れないため、面を POLY_LOOP で囲んだ STEP テキストを組み立てて
+//! `Shape::read_step_with_colors` に渡す。3MF の色は面ごとの STYLED_ITEM として書く。
+
+use crate::encode::{step_real, step_string};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Read as _;
+
+/// 1 ソリッド分の三角形メッシュ（座標は mm）
+pub struct MeshSolid {
+	pub vertices: Vec<[f64; 3]>,
+	pub triangles: Vec<[usize; 3]>,
+	/// 三角形ごとの色 (0.0..=1.0)。色なしは None
+	pub colors: Vec<Option<[f32; 3]>>,
+}
+
+impl MeshSolid {
+	fn new() -> Self {
+		Self {
+			vertices: Vec::new(),
+			triangles: Vec::new(),
+			colors: Vec::new(),
+		}
+	}
+
+	/// 座標が完全に一致する頂点を共有して三角形を追加する
+	fn push_triangle(
+		&mut self,
+		index_of: &mut HashMap<[u64; 3], usize>,
+		corners: [[f64; 3]; 3],
+		color: Option<[f32; 3]>,
+	) {
+		let triangle = corners.map(|p| {
+			*index_of.entry(p.map(f64::to_bits)).or_insert_with(|| {
+				self.vertices.push(p);
+				self.vertices.len() - 1
+			})
+		});
+		self.triangles.push(triangle);
+		self.colors.push(color);
+	}
+}
+
+/// バイナリ / ASCII STL を読む。ASCII の `solid` ブロックごとに 1 ソリッドになる。STL は色を持たない
+pub fn read_stl(data: &[u8]) -> Result<Vec<MeshSolid>, String> {
+	if is_binary_stl(data) {
+		let mut solid = MeshSolid::new();
+		let mut index_of = HashMap::new();
+		for record in data[84..].chunks_exact(50) {
+			let value = |o: usize| f32::from_le_bytes(record[o..o + 4].try_into().unwrap()) as f64;
+			let corners = [12, 24, 36].map(|o| [value(o), value(o + 4), value(o + 8)]);
+			solid.push_triangle(&mut index_of, corners, None);
+		}
+		return Ok(vec![solid]);
+	}
+	let text = std::str::from_utf8(data).map_err(|e| format!("STL読み込み失敗: {e:?}"))?;
+	let mut solids = Vec::new();
+	let mut solid = MeshSolid::new();
+	let mut index_of = HashMap::new();
+	let mut corners: Vec<[f64; 3]> = Vec::with_capacity(3);
+	let mut tokens = text.split_whitespace();
+	while let Some(token) = tokens.next() {
+		match token {
+			"vertex" => {
+				let mut p = [0.0; 3];
+				for v in &mut p {
+					*v = tokens
+						.next()
+						.and_then(|t| t.parse().ok())
+						.ok_or("STL読み込み失敗: vertex の座標が不正です")?;
+				}
+				corners.push(p);
+			}
+			"endloop" => {
+				let [a, b, c] = corners[..] else {
+					return Err("STL読み込み失敗: 三角形でない facet があります".to_string());
+				};
+				solid.push_triangle(&mut index_of, [a, b, c], None);
+				corners.clear();
+			}
+			"endsolid" if !solid.triangles.is_empty() => {
+				solids.push(std::mem::replace(&mut solid, MeshSolid::new()));
+				index_of.clear();
+			}
+			_ => {}
+		}
+	}
+	if !solid.triangles.is_empty() {
+		solids.push(solid);
+	}
+	if solids.is_empty() {
+		return Err("STL読み込み失敗: 三角形がありません".to_string());
+	}
+	Ok(solids)
+}
+
+/// 80 バイトのヘッダ + 三角形数 + 50 バイト × 三角形数 で長さが合えばバイナリ STL
+pub fn is_binary_stl(data: &[u8]) -> bool {
+	data.len() >= 84
+		&& 84 + 50 * u32::from_le_bytes(data[80..84].try_into().unwrap()) as usize == data.len()
+}
+
+/// 3MF の build にある item ごとに、transform を適用したソリッドを返す。
+/// 色は basematerials / colorgroup を三角形 (p1) またはオブジェクト (pindex) から引く。
+pub fn read_3mf(data: &[u8]) -> Result<Vec<MeshSolid>, String> {
+	let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))
+		.map_err(|e| format!("3MF読み込み失敗: {e:?}"))?;
+	let name = (0..archive.len())
+		.filter_map(|i| archive.name_for_index(i).map(str::to_string))
+		.filter(|n| n.to_ascii_lowercase().ends_with(".model"))
+		.min_by_key(|n| n != "3D/3dmodel.model")
+		.ok_or("3MF読み込み失敗: 3D モデルがありません")?;
+	let mut xml = String::new();
+	archive
+		.by_name(&name)
+		.map_err(|e| format!("3MF読み込み失敗: {e:?}"))?
+		.read_to_string(&mut xml)
+		.map_err(|e| format!("3MF読み込み失敗: {e:?}"))?;
+	parse_3mf_model(&xml)
+}
+
+/// 3MF のオブジェクト: メッシュまたは子オブジェクトの組み合わせ
+#[derive(Default)]
+struct Object {
+	vertices: Vec<[f64; 3]>,
+	/// (頂点, プロパティ群 id, プロパティ番号)
+	triangles: Vec<([usize; 3], Option<String>, Option<usize>)>,
+	pid: Option<String>,
+	pindex: Option<usize>,
+	components: Vec<(String, Transform)>,
+}
+
+/// 3MF の変換行列（行ベクトル p' = p × M、m30..m32 が平行移動）
+type Transform = [f64; 12];
+const IDENTITY: Transform = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+
+fn parse_3mf_model(xml: &str) -> Result<Vec<MeshSolid>, String> {
+	let mut scale = 1.0;
+	let mut objects: HashMap<String, Object> = HashMap::new();
+	let mut properties: HashMap<String, Vec<[f32; 3]>> = HashMap::new();
+	let mut items: Vec<(String, Transform)> = Vec::new();
+	let mut object: Option<(String, Object)> = None;
+	let mut group: Option<String> = None;
+
+	let mut element = String::new();
+	let mut attributes: HashMap<String, String> = HashMap::new();
+	for token in xmlparser::Tokenizer::from(xml) {
+		let token = token.map_err(|e| format!("3MF読み込み失敗: {e}"))?;
+		match token {
+			xmlparser::Token::ElementStart { local, .. } => {
+				element = local.as_str().to_string();
+				attributes.clear();
+			}
+			xmlparser::Token::Attribute { local, value, .. } => {
+				attributes.insert(local.as_str().to_string(), value.as_str().to_string());
+			}
+			xmlparser::Token::ElementEnd {
+				end: xmlparser::ElementEnd::Open | xmlparser::ElementEnd::Empty,
+				..
+			} => {
+				let attr = |name: &str| attributes.get(name).map(String::as_str);
+				let number = |name: &str| -> Result<f64, String> {
+					attr(name)
+						.and_then(|v| v.parse().ok())
+						.ok_or(format!("3MF読み込み失敗: {element} の {name} が不正です"))
+				};
+				match element.as_str() {
+					"model" => scale = unit_scale(attr("unit").unwrap_or("millimeter"))?,
+					"basematerials" | "colorgroup" => {
+						let id = attr("id").unwrap_or_default().to_string();
+						properties.entry(id.clone()).or_default();
+						group = Some(id);
+					}
+					"base" | "color" => {
+						let value = attr("displaycolor").or(attr("color")).unwrap_or("#CCCCCC");
+						if let Some(group) = &group {
+							properties.get_mut(group).unwrap().push(parse_color(value)?);
+						}
+					}
+					"object" => {
+						object = Some((
+							attr("id").unwrap_or_default().to_string(),
+							Object {
+								pid: attr("pid").map(str::to_string),
+								pindex: attr("pindex").and_then(|v| v.parse().ok()),
+								..Default::default()
+							},
+						));
+					}
+					"vertex" => {
+						if let Some((_, object)) = &mut object {
+							object
+								.vertices
+								.push([number("x")?, number("y")?, number("z")?]);
+						}
+					}
+					"triangle" => {
+						if let Some((_, object)) = &mut object {
+							let v =
+								[number("v1")?, number("v2")?, number("v3")?].map(|v| v as usize);
+							if v.iter().any(|&i| i >= object.vertices.len()) {
+								return Err(
+									"3MF読み込み失敗: 三角形の頂点番号が範囲外です".to_string()
+								);
+							}
+							object.triangles.push((
+								v,
+								attr("pid").map(str::to_string),
+								attr("p1").and_then(|v| v.parse().ok()),
+							));
+						}
+					}
+					"component" => {
+						if let Some((_, object)) = &mut object {
+							object.components.push((
+								attr("objectid").unwrap_or_default().to_string(),
+								parse_transform(attr("transform"))?,
+							));
+						}
+					}
+					"item" => items.push((
+						attr("objectid").unwrap_or_default().to_string(),
+						parse_transform(attr("transform"))?,
+					)),
+					_ => {}
+				}
+			}
+			xmlparser::Token::ElementEnd {
+				end: xmlparser::ElementEnd::Close(_, local),
+				..
+			} => match local.as_str() {
+				"object" => {
+					if let Some((id, object)) = object.take() {
+						objects.insert(id, object);
+					}
+				}
+				"basematerials" | "colorgroup" => group = None,
+				_ => {}
+			},
+			_ => {}
+		}
+	}
+
+	let mut solids = Vec::new();
+	for (id, transform) in &items {
+		flatten(&objects, &properties, id, transform, scale, 0, &mut solids)?;
+	}
+	if solids.is_empty() {
+		return Err("3MF読み込み失敗: 三角形がありません".to_string());
+	}
+	Ok(solids)
+}
+
+/// オブジェクトを再帰的にたどり、メッシュごとに 1 ソリッドを作る
+fn flatten(
+	objects: &HashMap<String, Object>,
+	properties: &HashMap<String, Vec<[f32; 3]>>,
+	id: &str,
+	transform: &Transform,
+	scale: f64,
+	depth: usize,
+	solids: &mut Vec<MeshSolid>,
+) -> Result<(), String> {
+	if depth > 16 {
+		return Err("3MF読み込み失敗: コンポーネントの入れ子が深すぎます".to_string());
+	}
+	let object = objects
+		.get(id)
+		.ok_or(format!("3MF読み込み失敗: オブジェクト {id} がありません"))?;
+	for (child, local) in &object.components {
+		flatten(
+			objects,
+			properties,
+			child,
+			&compose(local, transform),
+			scale,
+			depth + 1,
+			solids,
+		)?;
+	}
+	if object.triangles.is_empty() {
+		return Ok(());
+	}
+	let color_of = |pid: Option<&String>, index: Option<usize>| {
+		properties
+			.get(pid?)
+			.and_then(|colors| colors.get(index.unwrap_or(0)))
+			.copied()
+	};
+	let mut solid = MeshSolid::new();
+	let mut index_of = HashMap::new();
+	for (v, pid, p1) in &object.triangles {
+		let corners = v.map(|i| apply(transform, object.vertices[i]).map(|x| x * scale));
+		let color = match pid {
+			Some(pid) => color_of(Some(pid), *p1),
+			None => color_of(object.pid.as_ref(), p1.or(object.pindex)),
+		};
+		solid.push_triangle(&mut index_of, corners, color);
+	}
+	solids.push(solid);
+	Ok(())
+}
+
+fn parse_transform(value: Option<&str>) -> Result<Transform, String> {
+	let Some(value) = value else {
+		return Ok(IDENTITY);
+	};
+	let numbers: Vec<f64> = value
+		.split_whitespace()
+		.map(|v| {
+			v.parse()
+				.map_err(|e| format!("3MF読み込み失敗: transform {e:?}"))
+		})
+		.collect::<Result<_, _>>()?;
+	numbers
+		.try_into()
+		.map_err(|_| "3MF読み込み失敗: transform は 12 個の数値です".to_string())
+}
+
+fn apply(m: &Transform, p: [f64; 3]) -> [f64; 3] {
+	[0, 1, 2].map(|k| p[0] * m[k] + p[1] * m[3 + k] + p[2] * m[6 + k] + m[9 + k])
+}
+
+/// 先に inner、次に outer を適用する変換
+fn compose(inner: &Transform, outer: &Transform) -> Transform {
+	let mut m = [0.0; 12];
+	for row in 0..4 {
+		for k in 0..3 {
+			m[row * 3 + k] = (0..3)
+				.map(|j| inner[row * 3 + j] * outer[j * 3 + k])
+				.sum::<f64>()
+				+ if row == 3 { outer[9 + k] } else { 0.0 };
+		}
+	}
+	m
+}
+
+fn unit_scale(unit: &str) -> Result<f64, String> {
+	match unit {
+		"micron" => Ok(0.001),
+		"millimeter" => Ok(1.0),
+		"centimeter" => Ok(10.0),
+		"inch" => Ok(25.4),
+		"foot" => Ok(304.8),
+		"meter" => Ok(1000.0),
+		_ => Err(format!("3MF読み込み失敗: 単位 {unit} は未対応です")),
+	}
+}
+
+/// #RRGGBB または #RRGGBBAA
+fn parse_color(value: &str) -> Result<[f32; 3], String> {
+	let hex = value.trim_start_matches('#');
+	let channel = |i: usize| {
+		hex.get(i..i + 2)
+			.and_then(|h| u8::from_str_radix(h, 16).ok())
+			.map(|v| v as f32 / 255.0)
+			.ok_or(format!("3MF読み込み失敗: 色 {value} が不正です"))
+	};
+	Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// ソリッドを FACETED_BREP とする AP214 の STEP テキストを組み立てる。
+/// 面積のない三角形は平面を決められないので除く。
+pub fn faceted_step(solids: &[MeshSolid]) -> Vec<u8> {
+	let mut out = String::from(
+		"ISO-10303-21;\nHEADER;\n\
+		 FILE_DESCRIPTION(('lambda360 mesh import'),'2;1');\n\
+		 FILE_NAME('mesh','',(''),(''),'lambda360','lambda360','');\n\
+		 FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));\n\
+		 ENDSEC;\nDATA;\n",
+	);
+	let mut next = 0u64;
+	let mut add = |out: &mut String, body: String| {
+		next += 1;
+		writeln!(out, "#{next}={body};").unwrap();
+		next
+	};
+	let context = add(
+		&mut out,
+		"APPLICATION_CONTEXT('core data for automotive mechanical design processes')".to_string(),
+	);
+	add(
+		&mut out,
+		format!(
+			"APPLICATION_PROTOCOL_DEFINITION('international standard','automotive_design',2000,#{context})"
+		),
+	);
+	let product_context = add(
+		&mut out,
+		format!("PRODUCT_CONTEXT('',#{context},'mechanical')"),
+	);
+	let product = add(
+		&mut out,
+		format!(
+			"PRODUCT({name},{name},'',(#{product_context}))",
+			name = step_string("mesh")
+		),
+	);
+	let formation = add(
+		&mut out,
+		format!("PRODUCT_DEFINITION_FORMATION('','',#{product})"),
+	);
+	let definition_context = add(
+		&mut out,
+		format!("PRODUCT_DEFINITION_CONTEXT('part definition',#{context},'design')"),
+	);
+	let definition = add(
+		&mut out,
+		format!("PRODUCT_DEFINITION('design','',#{formation},#{definition_context})"),
+	);
+	let definition_shape = add(
+		&mut out,
+		format!("PRODUCT_DEFINITION_SHAPE('','',#{definition})"),
+	);
+	let length = add(
+		&mut out,
+		"( LENGTH_UNIT() NAMED_UNIT(*) SI_UNIT(.MILLI.,.METRE.) )".to_string(),
+	);
+	let angle = add(
+		&mut out,
+		"( NAMED_UNIT(*) PLANE_ANGLE_UNIT() SI_UNIT($,.RADIAN.) )".to_string(),
+	);
+	let solid_angle = add(
+		&mut out,
+		"( NAMED_UNIT(*) SI_UNIT($,.STERADIAN.) SOLID_ANGLE_UNIT() )".to_string(),
+	);
+	let uncertainty = add(
+		&mut out,
+		format!(
+			"UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(1.E-07),#{length},'distance_accuracy_value','confusion accuracy')"
+		),
+	);
+	let representation_context = add(
+		&mut out,
+		format!(
+			"( GEOMETRIC_REPRESENTATION_CONTEXT(3) GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#{uncertainty})) \
+			 GLOBAL_UNIT_ASSIGNED_CONTEXT((#{length},#{angle},#{solid_angle})) \
+			 REPRESENTATION_CONTEXT('Context #1','3D Context with UNIT and UNCERTAINTY') )"
+		),
+	);
+
+	let point = |p: [f64; 3]| {
+		format!(
+			"({},{},{})",
+			step_real(p[0]),
+			step_real(p[1]),
+			step_real(p[2])
+		)
+	};
+	let mut breps = Vec::new();
+	// 色 → PRESENTATION_STYLE_ASSIGNMENT、(面, 色)
+	let mut styles: HashMap<[u32; 3], u64> = HashMap::new();
+	let mut styled_faces: Vec<(u64, u64)> = Vec::new();
+	for solid in solids {
+		let points: Vec<u64> = solid
+			.vertices
+			.iter()
+			.map(|&p| add(&mut out, format!("CARTESIAN_POINT('',{})", point(p))))
+			.collect();
+		let mut faces = Vec::new();
+		for (t, tri) in solid.triangles.iter().enumerate() {
+			let [a, b, c] = tri.map(|i| glam::DVec3::from_array(solid.vertices[i]));
+			let normal = (b - a).cross(c - a);
+			if normal.length() < 1e-12 || tri[0] == tri[1] || tri[1] == tri[2] || tri[2] == tri[0] {
+				continue;
+			}
+			let normal = add(
+				&mut out,
+				format!("DIRECTION('',{})", point(normal.normalize().to_array())),
+			);
+			let reference = add(
+				&mut out,
+				format!("DIRECTION('',{})", point((b - a).normalize().to_array())),
+			);
+			let placement = add(
+				&mut out,
+				format!(
+					"AXIS2_PLACEMENT_3D('',#{},#{normal},#{reference})",
+					points[tri[0]]
+				),
+			);
+			let plane = add(&mut out, format!("PLANE('',#{placement})"));
+			let polyloop = add(
+				&mut out,
+				format!(
+					"POLY_LOOP('',(#{},#{},#{}))",
+					points[tri[0]], points[tri[1]], points[tri[2]]
+				),
+			);
+			let bound = add(&mut out, format!("FACE_OUTER_BOUND('',#{polyloop},.T.)"));
+			let face = add(
+				&mut out,
+				format!("FACE_SURFACE('',(#{bound}),#{plane},.T.)"),
+			);
+			faces.push(format!("#{face}"));
+			if let Some(color) = solid.colors[t] {
+				let style = match styles.get(&color.map(f32::to_bits)) {
+					Some(&style) => style,
+					None => {
+						let [r, g, b] = color.map(|v| step_real(v as f64));
+						let rgb = add(&mut out, format!("COLOUR_RGB('',{r},{g},{b})"));
+						let fill = add(&mut out, format!("FILL_AREA_STYLE_COLOUR('',#{rgb})"));
+						let area = add(&mut out, format!("FILL_AREA_STYLE('',(#{fill}))"));
+						let surface = add(&mut out, format!("SURFACE_STYLE_FILL_AREA(#{area})"));
+						let side = add(&mut out, format!("SURFACE_SIDE_STYLE('',(#{surface}))"));
+						let usage = add(&mut out, format!("SURFACE_STYLE_USAGE(.BOTH.,#{side})"));
+						let style = add(
+							&mut out,
+							format!("PRESENTATION_STYLE_ASSIGNMENT((#{usage}))"),
+						);
+						styles.insert(color.map(f32::to_bits), style);
+						style
+					}
+				};
+				styled_faces.push((face, style));
+			}
+		}
+		if faces.is_empty() {
+			continue;
+		}
+		let shell = add(&mut out, format!("CLOSED_SHELL('',({}))", faces.join(",")));
+		breps.push(format!(
+			"#{}",
+			add(&mut out, format!("FACETED_BREP('',#{shell})"))
+		));
+	}
+	let representation = add(
+		&mut out,
+		format!(
+			"FACETED_BREP_SHAPE_REPRESENTATION('',({}),#{representation_context})",
+			breps.join(",")
+		),
+	);
+	add(
+		&mut out,
+		format!("SHAPE_DEFINITION_REPRESENTATION(#{definition_shape},#{representation})"),
+	);
+	if !styled_faces.is_empty() {
+		let items: Vec<String> = styled_faces
+			.iter()
+			.map(|&(face, style)| {
+				format!(
+					"#{}",
+					add(&mut out, format!("STYLED_ITEM('color',(#{style}),#{face})"))
+				)
+			})
+			.collect();
+		add(
+			&mut out,
+			format!(
+				"MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION('',({}),#{representation_context})",
+				items.join(",")
+			),
+		);
+	}
+	out.push_str("ENDSEC;\nEND-ISO-10303-21;\n");
+	out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn binary_stl(triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+		let mut data = vec![0u8; 80];
+		data.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+		for tri in triangles {
+			data.extend_from_slice(&[0u8; 12]);
+			for v in tri.iter().flatten() {
+				data.extend_from_slice(&v.to_le_bytes());
+			}
+			data.extend_from_slice(&[0u8; 2]);
+		}
+		data
+	}
+
+	#[test]
+	fn stl_binary_and_ascii_share_vertices() {
+		let triangles = [
+			[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+			[[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+		];
+		let binary = read_stl(&binary_stl(&triangles)).unwrap();
+		assert_eq!(binary.len(), 1);
+		assert_eq!(binary[0].vertices.len(), 4);
+		assert_eq!(binary[0].triangles, vec![[0, 1, 2], [1, 3, 2]]);
+
+		let ascii = "solid a\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 1 0\nendloop\nendfacet\nendsolid a\n\
+		             solid b\nfacet normal 0 0 1\nouter loop\nvertex 5 5 5\nvertex 6 5 5\nvertex 5 6 5\nendloop\nendfacet\nendsolid b\n";
+		let solids = read_stl(ascii.as_bytes()).unwrap();
+		assert_eq!(solids.len(), 2);
+		assert_eq!(solids[1].vertices[0], [5.0, 5.0, 5.0]);
+	}
+
+	#[test]
+	fn three_mf_applies_colors_units_and_transforms() {
+		let xml = r##"<?xml version="1.0" encoding="UTF-8"?>
+<model unit="centimeter" xmlns="http://schemas.microsoft.com/3dmanufacturing/core/2015/02">
+ <resources>
+  <basematerials id="1">
+   <base name="red" displaycolor="#FF0000FF"/>
+   <base name="blue" displaycolor="#0000FF"/>
+  </basematerials>
+  <object id="2" type="model" pid="1" pindex="0">
+   <mesh>
+    <vertices>
+     <vertex x="0" y="0" z="0"/><vertex x="1" y="0" z="0"/><vertex x="0" y="1" z="0"/><vertex x="1" y="1" z="0"/>
+    </vertices>
+    <triangles>
+     <triangle v1="0" v2="1" v3="2"/>
+     <triangle v1="1" v2="3" v3="2" pid="1" p1="1"/>
+    </triangles>
+   </mesh>
+  </object>
+  <object id="3" type="model">
+   <components><component objectid="2" transform="1 0 0 0 1 0 0 0 1 0 0 2"/></components>
+  </object>
+ </resources>
+ <build><item objectid="3" transform="1 0 0 0 1 0 0 0 1 10 0 0"/></build>
+</model>"##;
+		let solids = parse_3mf_model(xml).unwrap();
+		assert_eq!(solids.len(), 1);
+		// (1, 0, 0) cm → 平行移動 (10, 0, 2) cm → mm
+		assert_eq!(solids[0].vertices[1], [110.0, 0.0, 20.0]);
+		assert_eq!(
+			solids[0].colors,
+			vec![Some([1.0, 0.0, 0.0]), Some([0.0, 0.0, 1.0])]
+		);
+	}
+
+	#[test]
+	fn faceted_step_has_face_per_triangle_and_styles() {
+		let solid = MeshSolid {
+			vertices: vec![
+				[0.0, 0.0, 0.0],
+				[1.0, 0.0, 0.0],
+				[0.0, 1.0, 0.0],
+				[2.0, 0.0, 0.0],
+			],
+			// 3 つ目は面積ゼロ
+			triangles: vec![[0, 1, 2], [1, 0, 2], [0, 1, 3]],
+			colors: vec![Some([1.0, 0.0, 0.0]), None, None],
+		};
+		let step = String::from_utf8(faceted_step(&[solid])).unwrap();
+		assert!(step.starts_with("ISO-10303-21;"));
+		assert!(step.ends_with("END-ISO-10303-21;\n"));
+		assert_eq!(step.matches("=FACE_SURFACE(").count(), 2);
+		assert_eq!(step.matches("=POLY_LOOP(").count(), 2);
+		assert_eq!(step.matches("=STYLED_ITEM(").count(), 1);
+		assert!(step.contains("COLOUR_RGB('',1.0,0.0,0.0)"));
+		assert!(step.contains("=FACETED_BREP_SHAPE_REPRESENTATION('',(#"));
+	}
+}
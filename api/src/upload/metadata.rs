@@ -0,0 +1,398 @@
+//! アップロードされた STEP のヘッダと製品構造。
+//!
+//! 形状は読まず、Part 21 テキストから FILE_NAME（作成者・CAD システム）、FILE_SCHEMA、長さ単位、
+//! PRODUCT と NEXT_ASSEMBLY_USAGE_OCCURRENCE によるアセンブリ構造を取り出す。
+//! 受注時に顧客がどの CAD・単位で作ったファイルかを確認するためのもので、bucket_main の `{content_hash}.json` に保存する。
+//!
+//! 長さ単位は換算係数 (mm_per_unit) も求める。OCCT の STEP 読み込みは形状を mm に換算するので、
+//! 保存する BRep は元の単位によらず mm になる。
+
+use crate::encode::{parse_step_string, split_entity, split_params, split_statements};
+use crate::openapi::{StepAssemblyNode, StepMeta};
+use std::collections::{HashMap, HashSet};
+
+/// STEP のヘッダと製品構造を読む
+pub fn step_meta(data: &[u8]) -> Result<StepMeta, String> {
+	let text = String::from_utf8_lossy(data);
+	let header_start = text.find("HEADER;").ok_or("STEP の HEADER がありません")?;
+	let data_start = text.find("DATA;").ok_or("STEP の DATA がありません")?;
+	let header_end = text[..data_start]
+		.rfind("ENDSEC;")
+		.ok_or("STEP の HEADER が閉じていません")?;
+	let data_end = text[data_start..]
+		.find("ENDSEC;")
+		.map(|i| data_start + i)
+		.unwrap_or(text.len());
+
+	let mut meta = StepMeta::default();
+	for statement in split_statements(&text[header_start + "HEADER;".len()..header_end]) {
+		let (name, params) = split_entity(&statement);
+		let param = |i: usize| params.get(i).map(String::as_str).unwrap_or("$");
+		match name.as_str() {
+			"FILE_NAME" => {
+				meta.file_name = text_param(param(0));
+				meta.time_stamp = text_param(param(1));
+				meta.author = list_param(param(2));
+				meta.organization = list_param(param(3));
+				meta.preprocessor_version = text_param(param(4));
+				meta.originating_system = text_param(param(5));
+			}
+			"FILE_SCHEMA" => meta.schema = list_param(param(0)),
+			_ => {}
+		}
+	}
+
+	let mut entities = HashMap::new();
+	let mut occurrences = Vec::new();
+	let mut length_units = Vec::new();
+	let mut measures = HashMap::new();
+	let mut unit_contexts = Vec::new();
+	for statement in split_statements(&text[data_start + "DATA;".len()..data_end]) {
+		let Some((id, body)) = statement.split_once('=') else {
+			continue;
+		};
+		let Ok(id) = id.trim().trim_start_matches('#').parse::<u64>() else {
+			continue;
+		};
+		let (name, params) = split_entity(body);
+		match name.as_str() {
+			"PRODUCT"
+			| "PRODUCT_DEFINITION_FORMATION"
+			| "PRODUCT_DEFINITION_FORMATION_WITH_SPECIFIED_SOURCE"
+			| "PRODUCT_DEFINITION"
+			| "PRODUCT_DEFINITION_WITH_ASSOCIATED_DOCUMENTS" => {
+				entities.insert(id, (name, params));
+			}
+			"NEXT_ASSEMBLY_USAGE_OCCURRENCE" => {
+				if let (Some(parent), Some(child)) = (
+					params.get(3).and_then(|p| reference(p)),
+					params.get(4).and_then(|p| reference(p)),
+				) {
+					occurrences.push((id, parent, child));
+				}
+			}
+			"LENGTH_MEASURE_WITH_UNIT" => {
+				if let Some(measure) = measure(&params) {
+					measures.insert(id, measure);
+				}
+			}
+			// 単位や表現の文脈は複合エンティティ `( A() B() )` で書かれる
+			"" => {
+				let parts = complex_parts(body);
+				if let Some(unit) = length_unit(&parts) {
+					length_units.push((id, unit));
+				}
+				if let Some((_, params)) =
+					parts.iter().find(|(name, _)| name == "MEASURE_WITH_UNIT")
+					&& let Some(measure) = measure(params)
+				{
+					measures.insert(id, measure);
+				}
+				if let Some((_, params)) = parts
+					.iter()
+					.find(|(name, _)| name == "GLOBAL_UNIT_ASSIGNED_CONTEXT")
+				{
+					let units = params.first().map(|p| list_refs(p)).unwrap_or_default();
+					unit_contexts.push((id, units));
+				}
+			}
+			_ => {}
+		}
+	}
+
+	// 形状の表現に割り当てられた長さ単位。割り当てがなければ最初に定義された長さ単位
+	length_units.sort_by_key(|(id, _)| *id);
+	unit_contexts.sort_by_key(|(id, _)| *id);
+	if let Some((id, unit)) = unit_contexts
+		.iter()
+		.flat_map(|(_, units)| units)
+		.find_map(|unit| length_units.iter().find(|(id, _)| id == unit))
+		.or(length_units.first())
+	{
+		meta.length_unit = Some(unit.name.clone());
+		meta.mm_per_unit = mm_per_unit(*id, &length_units, &measures, 0);
+	}
+
+	meta.assembly = assembly(&entities, &mut occurrences);
+	Ok(meta)
+}
+
+/// PRODUCT_DEFINITION を NEXT_ASSEMBLY_USAGE_OCCURRENCE でつないだ木。
+/// どこにも配置されていない PRODUCT_DEFINITION を根とする
+fn assembly(
+	entities: &HashMap<u64, (String, Vec<String>)>,
+	occurrences: &mut [(u64, u64, u64)],
+) -> Vec<StepAssemblyNode> {
+	occurrences.sort_by_key(|(id, _, _)| *id);
+	let mut children: HashMap<u64, Vec<u64>> = HashMap::new();
+	for &(_, parent, child) in occurrences.iter() {
+		children.entry(parent).or_default().push(child);
+	}
+	let placed: HashSet<u64> = occurrences.iter().map(|&(_, _, child)| child).collect();
+	let mut roots: Vec<u64> = entities
+		.iter()
+		.filter(|(id, (name, _))| {
+			name.starts_with("PRODUCT_DEFINITION")
+				&& !name.contains("FORMATION")
+				&& !placed.contains(id)
+		})
+		.map(|(id, _)| *id)
+		.collect();
+	roots.sort();
+	roots
+		.into_iter()
+		.map(|root| node(root, entities, &children, &mut Vec::new()))
+		.collect()
+}
+
+/// PRODUCT_DEFINITION `definition` 以下の木。循環する参照は打ち切る
+fn node(
+	definition: u64,
+	entities: &HashMap<u64, (String, Vec<String>)>,
+	children: &HashMap<u64, Vec<u64>>,
+	ancestors: &mut Vec<u64>,
+) -> StepAssemblyNode {
+	// PRODUCT_DEFINITION → PRODUCT_DEFINITION_FORMATION → PRODUCT
+	let product = entities
+		.get(&definition)
+		.and_then(|(_, params)| reference(params.get(2)?))
+		.and_then(|formation| entities.get(&formation))
+		.and_then(|(_, params)| reference(params.get(2)?))
+		.and_then(|product| entities.get(&product))
+		.map(|(_, params)| params.as_slice())
+		.unwrap_or_default();
+	let text = |i: usize| {
+		product
+			.get(i)
+			.and_then(|p| text_param(p))
+			.unwrap_or_default()
+	};
+	ancestors.push(definition);
+	let children = children
+		.get(&definition)
+		.into_iter()
+		.flatten()
+		.filter(|child| !ancestors.contains(child))
+		.copied()
+		.collect::<Vec<_>>()
+		.into_iter()
+		.map(|child| node(child, entities, children, ancestors))
+		.collect();
+	ancestors.pop();
+	StepAssemblyNode {
+		part_number: text(0),
+		name: text(1),
+		children,
+	}
+}
+
+/// 複合エンティティ `( A(..) B(..) )` を名前とパラメータの組に分ける
+fn complex_parts(body: &str) -> Vec<(String, Vec<String>)> {
+	let body = body.trim();
+	let inner = body
+		.strip_prefix('(')
+		.and_then(|b| b.strip_suffix(')'))
+		.unwrap_or(body);
+	let mut parts = Vec::new();
+	let mut current = String::new();
+	let mut depth = 0;
+	let mut in_string = false;
+	for c in inner.chars() {
+		if depth == 0 && !in_string && c.is_whitespace() {
+			continue;
+		}
+		current.push(c);
+		match c {
+			'\'' => in_string = !in_string,
+			'(' if !in_string => depth += 1,
+			')' if !in_string => {
+				depth -= 1;
+				if depth == 0 {
+					parts.push(split_entity(&current));
+					current.clear();
+				}
+			}
+			_ => {}
+		}
+	}
+	parts
+}
+
+/// 長さ単位の定義
+struct LengthUnit {
+	/// mm / m / inch など
+	name: String,
+	/// SI 単位なら 1 単位あたりの mm
+	mm: Option<f64>,
+	/// 換算単位 (CONVERSION_BASED_UNIT) なら換算値の LENGTH_MEASURE_WITH_UNIT
+	measure: Option<u64>,
+}
+
+/// LENGTH_UNIT を含む複合エンティティなら長さ単位
+fn length_unit(parts: &[(String, Vec<String>)]) -> Option<LengthUnit> {
+	parts.iter().find(|(name, _)| name == "LENGTH_UNIT")?;
+	if let Some((_, params)) = parts.iter().find(|(name, _)| name == "SI_UNIT") {
+		let (prefix, mm) = match params.first().map(|p| p.trim_matches('.')) {
+			Some("MILLI") => ("m", 1.0),
+			Some("CENTI") => ("c", 10.0),
+			Some("DECI") => ("d", 100.0),
+			Some("MICRO") => ("u", 1e-3),
+			Some("NANO") => ("n", 1e-6),
+			Some("KILO") => ("k", 1e6),
+			_ => ("", 1e3),
+		};
+		return Some(LengthUnit {
+			name: format!("{prefix}m"),
+			mm: Some(mm),
+			measure: None,
+		});
+	}
+	let (_, params) = parts
+		.iter()
+		.find(|(name, _)| name == "CONVERSION_BASED_UNIT")?;
+	Some(LengthUnit {
+		name: text_param(params.first()?)?.to_lowercase(),
+		mm: None,
+		measure: params.get(1).and_then(|p| reference(p)),
+	})
+}
+
+/// `LENGTH_MEASURE_WITH_UNIT(LENGTH_MEASURE(25.4),#単位)` の (値, 単位)
+fn measure(params: &[String]) -> Option<(f64, u64)> {
+	let value = params.first()?;
+	let value = value
+		.split_once('(')
+		.map_or(value.as_str(), |(_, v)| v.trim_end_matches(')'));
+	Some((value.trim().parse().ok()?, reference(params.get(1)?)?))
+}
+
+/// 長さ単位 `unit` の 1 単位あたりの mm。換算単位は換算先の単位をたどる
+fn mm_per_unit(
+	unit: u64,
+	units: &[(u64, LengthUnit)],
+	measures: &HashMap<u64, (f64, u64)>,
+	depth: usize,
+) -> Option<f64> {
+	let (_, unit) = units.iter().find(|(id, _)| *id == unit)?;
+	if let Some(mm) = unit.mm {
+		return Some(mm);
+	}
+	// 循環する定義は打ち切る
+	if depth > 4 {
+		return None;
+	}
+	let (value, base) = measures.get(&unit.measure?)?;
+	Some(value * mm_per_unit(*base, units, measures, depth + 1)?)
+}
+
+/// 空でない文字列パラメータ
+fn text_param(param: &str) -> Option<String> {
+	parse_step_string(param).filter(|s| !s.trim().is_empty())
+}
+
+/// `('a','b')` の空でない文字列
+fn list_param(param: &str) -> Vec<String> {
+	list_items(param)
+		.filter_map(|item| text_param(&item))
+		.collect()
+}
+
+/// `(#1,#2)` の参照
+fn list_refs(param: &str) -> Vec<u64> {
+	list_items(param)
+		.filter_map(|item| reference(&item))
+		.collect()
+}
+
+/// `(a,b)` の最上位の要素
+fn list_items(param: &str) -> impl Iterator<Item = String> {
+	let inner = param
+		.trim()
+		.strip_prefix('(')
+		.and_then(|p| p.strip_suffix(')'))
+		.unwrap_or_default();
+	split_params(inner).into_iter()
+}
+
+/// `#N`
+fn reference(param: &str) -> Option<u64> {
+	param.trim().strip_prefix('#')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const STEP: &str = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('Open CASCADE Model'),'2;1');
+FILE_NAME('bracket.stp','2025-06-01T10:00:00',('Taro \\X2\\5C717530\\X0\\'),('ACME'),
+  'Open CASCADE STEP processor 7.8','SolidWorks 2024','');
+FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));
+ENDSEC;
+DATA;
+#1=PRODUCT('ASM-1','Bracket Assembly','',(#9));
+#2=PRODUCT_DEFINITION_FORMATION('','',#1);
+#3=PRODUCT_DEFINITION('design','',#2,#10);
+#4=PRODUCT('P-2','Plate','',(#9));
+#5=PRODUCT_DEFINITION_FORMATION_WITH_SPECIFIED_SOURCE('','',#4,.NOT_KNOWN.);
+#6=PRODUCT_DEFINITION('design','',#5,#10);
+#7=NEXT_ASSEMBLY_USAGE_OCCURRENCE('1','Plate:1','',#3,#6,$);
+#8=NEXT_ASSEMBLY_USAGE_OCCURRENCE('2','Plate:2','',#3,#6,$);
+#20=( LENGTH_UNIT() NAMED_UNIT(*) SI_UNIT(.MILLI.,.METRE.) );
+#21=LENGTH_MEASURE_WITH_UNIT(LENGTH_MEASURE(25.4),#20);
+#22=( CONVERSION_BASED_UNIT('INCH',#21) LENGTH_UNIT() NAMED_UNIT(#23) );
+#24=( GEOMETRIC_REPRESENTATION_CONTEXT(3) GLOBAL_UNIT_ASSIGNED_CONTEXT((#22,#25,#26)) REPRESENTATION_CONTEXT('','') );
+ENDSEC;
+END-ISO-10303-21;
+";
+
+	#[test]
+	fn reads_header_unit_and_assembly() {
+		let meta = step_meta(STEP.as_bytes()).unwrap();
+		assert_eq!(meta.file_name.as_deref(), Some("bracket.stp"));
+		assert_eq!(meta.author, ["Taro 山田"]);
+		assert_eq!(meta.organization, ["ACME"]);
+		assert_eq!(meta.originating_system.as_deref(), Some("SolidWorks 2024"));
+		assert_eq!(meta.schema, ["AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }"]);
+		// インチの換算元の mm ではなく、表現に割り当てられたインチ
+		assert_eq!(meta.length_unit.as_deref(), Some("inch"));
+		assert_eq!(meta.mm_per_unit, Some(25.4));
+		assert_eq!(meta.assembly.len(), 1);
+		let root = &meta.assembly[0];
+		assert_eq!(
+			(root.part_number.as_str(), root.name.as_str()),
+			("ASM-1", "Bracket Assembly")
+		);
+		let children: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+		assert_eq!(children, ["Plate", "Plate"]);
+	}
+
+	#[test]
+	fn single_part_without_unit_context() {
+		let step = STEP
+			.replace(" GLOBAL_UNIT_ASSIGNED_CONTEXT((#22,#25,#26))", "")
+			.replace("#7=NEXT", "#7=X")
+			.replace("#8=NEXT", "#8=X");
+		let meta = step_meta(step.as_bytes()).unwrap();
+		assert_eq!(meta.length_unit.as_deref(), Some("mm"));
+		assert_eq!(meta.mm_per_unit, Some(1.0));
+		let names: Vec<&str> = meta.assembly.iter().map(|n| n.name.as_str()).collect();
+		assert_eq!(names, ["Bracket Assembly", "Plate"]);
+		assert!(step_meta(b"solid x\nendsolid x\n").is_err());
+	}
+
+	#[test]
+	fn conversion_unit_through_complex_measure() {
+		let step = STEP
+			.replace("SI_UNIT(.MILLI.,.METRE.)", "SI_UNIT($,.METRE.)")
+			.replace(
+				"LENGTH_MEASURE_WITH_UNIT(LENGTH_MEASURE(25.4),#20)",
+				"( LENGTH_MEASURE_WITH_UNIT() MEASURE_WITH_UNIT(LENGTH_MEASURE(0.3048),#20) )",
+			)
+			.replace("'INCH'", "'FOOT'");
+		let meta = step_meta(step.as_bytes()).unwrap();
+		assert_eq!(meta.length_unit.as_deref(), Some("foot"));
+		assert!((meta.mm_per_unit.unwrap() - 304.8).abs() < 1e-9);
+	}
+}
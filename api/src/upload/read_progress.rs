@@ -0,0 +1,195 @@
+//! 読み込みの実際の進み具合。
+//!
+//! chijin (OCCT) の読み込みは進捗のコールバックを持たないが、入力は Rust の Read から取るので、
+//! OCCT が読み進めたバイト数を数えれば解析（パース）の進み具合がわかる。
+//! STEP はエンティティ (`#N=...`) の開始位置をあらかじめ求め、読み進めたエンティティ数として報告する。
+//! 解析が終わった後の形状への変換 (transfer) には手がかりがないので、進捗を止めて進み具合は不明とし、経過時間だけを報告する。
+
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 読み込みスレッドが更新し、進捗タスクが読む
+#[derive(Default)]
+pub struct ReadProgress {
+	consumed: AtomicUsize,
+	total: AtomicUsize,
+	/// STEP のときだけ、各エンティティの開始位置
+	entities: OnceLock<Vec<usize>>,
+}
+
+/// ある時点の進み具合
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReadSnapshot {
+	/// 入力を読んだ割合 (0〜1)
+	pub fraction: f64,
+	/// STEP のとき (読み始めたエンティティ数, 全エンティティ数)
+	pub entities: Option<(usize, usize)>,
+	/// 入力を読み終え、形状への変換中
+	pub parsed: bool,
+}
+
+impl ReadProgress {
+	/// OCCT に渡す入力を登録し、読んだバイト数を数える Read を返す。step ならエンティティも数える
+	pub fn reader<'a>(&'a self, data: &'a [u8], step: bool) -> CountingReader<'a> {
+		if step {
+			let _ = self.entities.set(entity_offsets(data));
+		}
+		self.consumed.store(0, Ordering::Relaxed);
+		self.total.store(data.len(), Ordering::Relaxed);
+		CountingReader {
+			data,
+			progress: self,
+		}
+	}
+
+	pub fn snapshot(&self) -> ReadSnapshot {
+		let consumed = self.consumed.load(Ordering::Relaxed);
+		let total = self.total.load(Ordering::Relaxed);
+		ReadSnapshot {
+			fraction: if total == 0 {
+				0.0
+			} else {
+				consumed as f64 / total as f64
+			},
+			entities: self
+				.entities
+				.get()
+				.map(|offsets| (offsets.partition_point(|&o| o < consumed), offsets.len())),
+			parsed: total > 0 && consumed >= total,
+		}
+	}
+}
+
+impl ReadSnapshot {
+	/// 読み込みに割り当てた進捗の範囲 (start, end) の中での進捗と、進み具合の説明。
+	/// 解析は入力を読んだ割合で範囲の前 2/5 を進める。変換は進み具合がわからないので、解析を終えた位置で止める
+	pub fn status(&self, (start, end): (u32, u32)) -> (u32, String) {
+		let split = start + (end - start) * 2 / 5;
+		if self.parsed {
+			return (split, "形状変換中（進み具合は不明）".to_string());
+		}
+		let detail = match self.entities {
+			Some((read, total)) => format!("解析中 {read}/{total}エンティティ"),
+			None => format!("{:.0}%", self.fraction * 100.0),
+		};
+		(
+			start + ((split - start) as f64 * self.fraction) as u32,
+			detail,
+		)
+	}
+}
+
+/// 読んだ位置を ReadProgress に書く Read
+pub struct CountingReader<'a> {
+	data: &'a [u8],
+	progress: &'a ReadProgress,
+}
+
+impl std::io::Read for CountingReader<'_> {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		let consumed = self.progress.consumed.load(Ordering::Relaxed);
+		let n = buf.len().min(self.data.len() - consumed);
+		buf[..n].copy_from_slice(&self.data[consumed..consumed + n]);
+		self.progress
+			.consumed
+			.store(consumed + n, Ordering::Relaxed);
+		Ok(n)
+	}
+}
+
+/// STEP のエンティティ定義 `#N=`（`#N =` も含む）の開始位置。参照の `#N` は数えない
+fn entity_offsets(step: &[u8]) -> Vec<usize> {
+	let mut offsets = Vec::new();
+	let mut i = 0;
+	while i < step.len() {
+		if step[i] == b'\'' {
+			// 文字列の中は読み飛ばす（'' はエスケープされた引用符）
+			i += 1;
+			while i < step.len() {
+				if step[i] == b'\'' {
+					if step.get(i + 1) == Some(&b'\'') {
+						i += 1;
+					} else {
+						break;
+					}
+				}
+				i += 1;
+			}
+		} else if step[i] == b'#' {
+			let start = i;
+			i += 1;
+			while i < step.len() && step[i].is_ascii_digit() {
+				i += 1;
+			}
+			let mut j = i;
+			while j < step.len() && step[j].is_ascii_whitespace() {
+				j += 1;
+			}
+			if i > start + 1 && step.get(j) == Some(&b'=') {
+				offsets.push(start);
+			}
+			continue;
+		}
+		i += 1;
+	}
+	offsets
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Read;
+
+	const STEP: &[u8] = b"DATA;\n#1=CARTESIAN_POINT('#9=',(0.,0.,0.));\n#2 = DIRECTION('it''s #8=',(0.,0.,1.));\n#3=AXIS2_PLACEMENT_3D('',#1,#2,$);\nENDSEC;\n";
+
+	#[test]
+	fn entity_offsets_skip_references_and_strings() {
+		let offsets = entity_offsets(STEP);
+		let names: Vec<&[u8]> = offsets.iter().map(|&o| &STEP[o..o + 2]).collect();
+		assert_eq!(names, [b"#1", b"#2", b"#3"]);
+	}
+
+	#[test]
+	fn snapshot_follows_bytes_read() {
+		let progress = ReadProgress::default();
+		assert_eq!(progress.snapshot().fraction, 0.0);
+		assert!(!progress.snapshot().parsed);
+		let mut reader = progress.reader(STEP, true);
+		// #2 の先頭 1 バイトまで読む
+		let upto = STEP.windows(3).position(|w| w == b"#2 ").unwrap() + 1;
+		let mut buf = vec![0u8; upto];
+		reader.read_exact(&mut buf).unwrap();
+		let snapshot = progress.snapshot();
+		assert_eq!(snapshot.entities, Some((2, 3)));
+		assert!(!snapshot.parsed);
+		let mut rest = Vec::new();
+		reader.read_to_end(&mut rest).unwrap();
+		assert_eq!(buf.len() + rest.len(), STEP.len());
+		let snapshot = progress.snapshot();
+		assert_eq!(snapshot.entities, Some((3, 3)));
+		assert_eq!(snapshot.fraction, 1.0);
+		assert!(snapshot.parsed);
+	}
+
+	#[test]
+	fn transfer_is_reported_as_indeterminate() {
+		let parsing = ReadSnapshot {
+			fraction: 0.5,
+			entities: Some((2, 3)),
+			parsed: false,
+		};
+		assert_eq!(
+			parsing.status((20, 70)),
+			(30, "解析中 2/3エンティティ".to_string())
+		);
+		// 変換中は進捗を進めず、エンティティ数も出さない
+		let transfer = ReadSnapshot {
+			fraction: 1.0,
+			entities: Some((3, 3)),
+			parsed: true,
+		};
+		let (p, detail) = transfer.status((20, 70));
+		assert_eq!(p, 40);
+		assert!(detail.contains("不明") && !detail.contains("エンティティ"));
+	}
+}
@@ -65,6 +65,22 @@ export class AwsStack extends cdk.Stack {
 		apiFunction.addEnvironment('BUCKET_TEMP', bucket_temp.bucketName);
 		apiFunction.addEnvironment('BUCKET_MAIN', bucket_main.bucketName);
 
+		// 変換ジョブは自分自身を非同期に呼び出して処理するで（/api/step/{id}/submit）
+		// HTTP 以外のイベントは Lambda Web Adapter が /api/step/job に POST してくれる
+		apiFunction.addEnvironment('STEP_QUEUE', 'lambda');
+		apiFunction.addEnvironment('AWS_LWA_PASS_THROUGH_PATH', '/api/step/job');
+		// 呼び出せるのは自分自身だけにするで。ロールの既定ポリシーに関数の ARN を書くと
+		// 関数 → 既定ポリシー → 関数 の循環参照になるから、別のポリシーにしてロールに付けるで
+		new aws_iam.Policy(this, 'ApiFunctionInvokeSelf', {
+			roles: [apiFunction.role!],
+			statements: [new aws_iam.PolicyStatement({
+				actions: ['lambda:InvokeFunction'],
+				resources: [apiFunction.functionArn],
+			})],
+		});
+		// 再試行はアプリ側でやるから Lambda の非同期呼び出しの再試行は切っとくで
+		apiFunction.configureAsyncInvoke({ retryAttempts: 0 });
+
 		// CloudFrontディストリビューションや
 		const distribution = new cloudfront.Distribution(this, 'ApiDistribution', {
 			defaultBehavior: {
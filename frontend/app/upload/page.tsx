@@ -2,7 +2,8 @@
 
 import { useState, useRef, useCallback, DragEvent } from 'react';
 import Link from 'next/link';
-import { stepUploadUrl, stepExecute, stepStatus } from '@/out/client';
+import { stepUploadUrl, stepSubmit, stepStatus, stepResult } from '@/out/client';
+import type { StepManifestPart } from '@/out/client';
 
 async function sha256hex(file: File): Promise<string> {
   const buf = await file.arrayBuffer();
@@ -22,6 +23,10 @@ interface FileEntry {
   message: string;
   progress: number;
   contentHash?: string;
+  /** ZIP の場合はメンバーごとの変換結果 */
+  parts?: StepManifestPart[];
+  /** 形状検査の警告（完了時） */
+  warnings?: string[];
 }
 
 let _counter = 0;
@@ -95,18 +100,21 @@ export default function UploadPage() {
         return;
       }
 
-      // 4. execute 開始（長時間ジョブ、完了まで待つ）
+      // 4. 変換ジョブを投入（サーバー側のキューで処理され、すぐに 202 が返る）
       updateEntry(entryId, { sha256, status: 'executing', message: '変換開始中...', progress: 0 });
-      const execPromise = stepExecute({ path: { id: uploadId } }).then(({ data, error }) => {
+      try {
+        const { error } = await stepSubmit({ path: { id: uploadId } });
         if (error) throw new Error(String(error));
-        return data as string;
-      });
+      } catch (e) {
+        updateEntry(entryId, { sha256, status: 'error', message: `変換開始失敗: ${e}` });
+        return;
+      }
 
-      // 5. status ポーリング
-      // 大きなファイルでは execute が 504 タイムアウトしても Lambda 側の処理は継続するため、
-      // progress >= 100 を唯一の完了判定とする
-      let done = false;
-      while (!done) {
+      // 5. status ポーリング。progress >= 100 で終了（101 以上は失敗、再試行待ちの間は 100 未満）
+      let finalProgress = 0;
+      let finalMessage = '';
+      let warnings: string[] | undefined;
+      while (finalProgress < 100) {
         await new Promise((r) => setTimeout(r, 2000));
         try {
           const { data: s } = await stepStatus({ path: { id: uploadId } });
@@ -117,20 +125,31 @@ export default function UploadPage() {
               message: s.message,
               progress: s.progress,
             });
-            if (s.progress >= 100) done = true;
+            finalProgress = s.progress;
+            finalMessage = s.message;
+            warnings = s.warnings;
           }
         } catch {
           // 一時的なエラーは無視してポーリング継続
         }
       }
+      if (finalProgress > 100) {
+        updateEntry(entryId, { sha256, status: 'error', message: `変換失敗: ${finalMessage}` });
+        return;
+      }
 
-      // 6. 完了確定。execute が 200 で返っていれば content_hash を使い、
-      //    504 などで reject 済みでも status で完了確認済みなのでエラー扱いしない
+      // 6. 完了確定。結果の manifest から content_hash を取得する
       let contentHash: string | undefined;
+      let parts: StepManifestPart[] | undefined;
       try {
-        contentHash = await execPromise;
+        const { data: manifest } = await stepResult({ path: { id: uploadId } });
+        if (manifest) {
+          contentHash = manifest.content_hash;
+          // 単一ファイルは content_hash と同じなので ZIP のときだけ一覧を出す
+          if (manifest.parts.some((p) => p.name !== '')) parts = manifest.parts;
+        }
       } catch {
-        // 504 等は無視
+        // status で完了確認済みなのでエラー扱いしない
       }
       updateEntry(entryId, {
         sha256,
@@ -138,6 +157,8 @@ export default function UploadPage() {
         message: '変換完了',
         progress: 100,
         contentHash,
+        parts,
+        warnings,
       });
     },
     [updateEntry]
@@ -145,7 +166,7 @@ export default function UploadPage() {
 
   const addFiles = useCallback(
     (fileList: FileList | File[]) => {
-      const files = Array.from(fileList).filter((f) => /\.(step|stp)$/i.test(f.name));
+      const files = Array.from(fileList).filter((f) => /\.(step|stp|igs|iges|brep|stl|3mf|zip)$/i.test(f.name));
       if (files.length === 0) return;
 
       const newEntries: FileEntry[] = files.map((file) => ({
@@ -227,15 +248,15 @@ export default function UploadPage() {
         }}
       >
         <p style={{ fontSize: '1.05rem', color: '#444' }}>
-          STEPファイルをここにドロップ
+          STEP・BRep・STL・3MF ファイルをここにドロップ
         </p>
         <p style={{ fontSize: '0.85rem', color: '#999', marginTop: '0.4rem' }}>
-          またはクリックして選択（複数可、.step / .stp）
+          またはクリックして選択（複数可、.step / .stp / .igs / .iges / .brep / .stl / .3mf / 複数ファイルをまとめた .zip）
         </p>
         <input
           ref={inputRef}
           type="file"
-          accept=".step,.stp"
+          accept=".step,.stp,.igs,.iges,.brep,.stl,.3mf,.zip"
           multiple
           onChange={onInputChange}
           style={{ display: 'none' }}
@@ -361,6 +382,29 @@ function FileCard({ entry }: { entry: FileEntry }) {
         </div>
       )}
 
+      {/* ZIP メンバーごとの content_hash（完了時） */}
+      {entry.parts && (
+        <ul style={{ marginTop: '0.6rem', paddingLeft: '1.2rem', fontSize: '0.78rem', color: '#555' }}>
+          {entry.parts.map((part) => (
+            <li key={part.name}>
+              <span style={{ fontWeight: 600 }}>{part.name}: </span>
+              <code style={{ fontFamily: 'monospace', wordBreak: 'break-all', color: '#222' }}>
+                {part.content_hash}
+              </code>
+            </li>
+          ))}
+        </ul>
+      )}
+
+      {/* 形状検査の警告（完了時） */}
+      {entry.warnings && entry.warnings.length > 0 && (
+        <ul style={{ marginTop: '0.6rem', paddingLeft: '1.2rem', fontSize: '0.78rem', color: '#b45309' }}>
+          {entry.warnings.map((warning) => (
+            <li key={warning}>{warning}</li>
+          ))}
+        </ul>
+      )}
+
     </div>
   );
 }